@@ -0,0 +1,16 @@
+/// Generates the C header of the crate into `OUT_DIR`. The header checked in
+/// `include/` must match it, which `tests/ffi.rs` checks.
+fn main() {
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+        .expect("Unable to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{crate_dir}/src/lib.rs"))
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(format!("{out_dir}/rs_merkle_tree.h"));
+}
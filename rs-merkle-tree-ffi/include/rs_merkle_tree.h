@@ -0,0 +1,229 @@
+#ifndef RS_MERKLE_TREE_H
+#define RS_MERKLE_TREE_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The call succeeded.
+ */
+#define RMT_OK 0
+
+/**
+ * A pointer argument is null.
+ */
+#define RMT_NULL_POINTER 1
+
+/**
+ * A string argument is not valid UTF-8.
+ */
+#define RMT_INVALID_UTF8 2
+
+/**
+ * The proof is not a JSON proof.
+ */
+#define RMT_INVALID_JSON 3
+
+/**
+ * The output buffer is too small.
+ */
+#define RMT_BUFFER_TOO_SMALL 4
+
+/**
+ * A chunk size is 0.
+ */
+#define RMT_INVALID_CHUNK_SIZE 5
+
+#define RMT_EMPTY_TREE 10
+
+#define RMT_LEAF_NOT_FOUND 11
+
+#define RMT_INDEX_OUT_OF_RANGE 12
+
+#define RMT_MALFORMED_PROOF 13
+
+#define RMT_HASH_LENGTH_MISMATCH 14
+
+#define RMT_ALGORITHM_MISMATCH 15
+
+#define RMT_MODE_MISMATCH 16
+
+#define RMT_UNKNOWN_ALGORITHM 17
+
+#define RMT_UNKNOWN_MODE 18
+
+#define RMT_INVALID_HEX 19
+
+#define RMT_ROOT_MISMATCH 20
+
+#define RMT_INVALID_DUMP 21
+
+#define RMT_CHUNK_MISMATCH 22
+
+#define RMT_UNKNOWN_ODD_NODE_STRATEGY 23
+
+#define RMT_PARAMS_MISMATCH 24
+
+#define RMT_LEAF_COUNT_MISMATCH 25
+
+#define RMT_INVALID_PATH 26
+
+#define RMT_INVALID_SIGNATURE 27
+
+/**
+ * Size of the leaf hashes read and written by the functions.
+ */
+#define RMT_DIGEST_SIZE 32
+
+/**
+ * Size of a buffer large enough for any root written by
+ * `rmt_proof_compute_root`, terminating NUL included.
+ */
+#define RMT_ROOT_BUFFER_SIZE 76
+
+/**
+ * Hashing modes of `RmtTreeParams`, see `HashMode`.
+ */
+#define RMT_MODE_LEGACY 0
+
+#define RMT_MODE_RFC6962 1
+
+#define RMT_MODE_SORTED_PAIR 2
+
+/**
+ * Odd node strategies of `RmtTreeParams`, see `OddNodeStrategy`.
+ */
+#define RMT_ODD_NODE_DUPLICATE 0
+
+#define RMT_ODD_NODE_PROMOTE 1
+
+#define RMT_ODD_NODE_PAD_ZERO 2
+
+/**
+ * A proof parsed from JSON, hashed with the algorithm it records.
+ */
+typedef struct RmtProof RmtProof;
+
+/**
+ * The parameters a tree is hashed with, see `TreeParams`. Vaults committed by
+ * `vault-cli` use `RMT_MODE_RFC6962`, `RMT_ODD_NODE_PROMOTE` and commit to
+ * their leaf count.
+ */
+typedef struct RmtTreeParams {
+  /**
+   * One of the `RMT_MODE_*` constants.
+   */
+  uint8_t mode;
+  /**
+   * One of the `RMT_ODD_NODE_*` constants.
+   */
+  uint8_t odd_node;
+  /**
+   * Whether the root commits to the number of leaves.
+   */
+  bool commit_leaf_count;
+} RmtTreeParams;
+
+/**
+ * Parses a JSON proof, as returned by the `proof` route of the vault API,
+ * either alone or with the whole response. On success, `*out` holds a proof
+ * to release with `rmt_proof_free`.
+ *
+ * # Safety
+ *
+ * `json` must be a NUL-terminated string and `out` a valid pointer.
+ */
+int32_t rmt_proof_parse(const char *json, struct RmtProof **out);
+
+/**
+ * Releases a proof returned by `rmt_proof_parse`. Does nothing if `proof` is
+ * null.
+ *
+ * # Safety
+ *
+ * `proof` must be null or returned by `rmt_proof_parse`, and not released yet.
+ */
+void rmt_proof_free(struct RmtProof *proof);
+
+/**
+ * Writes the hash of the leaf proven by `proof` to `out`.
+ *
+ * # Safety
+ *
+ * `proof` must be returned by `rmt_proof_parse` and `out` point to
+ * `RMT_DIGEST_SIZE` writable bytes.
+ */
+int32_t rmt_proof_leaf(const struct RmtProof *proof, uint8_t *out);
+
+/**
+ * Computes the root of `proof` and writes it to `out` as a NUL-terminated
+ * `<algorithm>:<hex>` string, e.g. `sha3-256:3a98…`. A buffer of
+ * `RMT_ROOT_BUFFER_SIZE` bytes is large enough for any root.
+ *
+ * # Safety
+ *
+ * `proof` must be returned by `rmt_proof_parse` and `out` point to `out_len`
+ * writable bytes.
+ */
+int32_t rmt_proof_compute_root(const struct RmtProof *proof, char *out, size_t out_len);
+
+/**
+ * Checks that `proof` proves the leaf hash `leaf` under `root`, given as a
+ * `<algorithm>:<hex>` string or as the bare hex of a SHA-256 root, in a tree
+ * hashed with the `params` the root was saved with. Returns `RMT_OK` if it
+ * does, `RMT_ROOT_MISMATCH` if the leaf or the root differ, and
+ * `RMT_PARAMS_MISMATCH` if the proof declares other parameters.
+ *
+ * # Safety
+ *
+ * `proof` must be returned by `rmt_proof_parse`, `leaf` point to
+ * `RMT_DIGEST_SIZE` readable bytes and `root` be a NUL-terminated string.
+ */
+int32_t rmt_proof_verify(const struct RmtProof *proof,
+                         const uint8_t *leaf,
+                         const char *root,
+                         struct RmtTreeParams params);
+
+/**
+ * Writes to `out` the hash of the `len` bytes at `data` with `algorithm`,
+ * e.g. `sha3-256`: the leaf of a file in a vault whose files are not split
+ * into chunks.
+ *
+ * # Safety
+ *
+ * `algorithm` must be a NUL-terminated string, `data` point to `len`
+ * readable bytes or be null if `len` is 0, and `out` point to
+ * `RMT_DIGEST_SIZE` writable bytes.
+ */
+int32_t rmt_hash_leaf(const char *algorithm, const uint8_t *data, size_t len, uint8_t *out);
+
+/**
+ * Writes to `out` the root of the tree of the `len` bytes at `data` split
+ * into chunks of `chunk_size` bytes, hashed with `algorithm` and `params`:
+ * the leaf of a file in a vault whose files are split into chunks, such as
+ * the vaults committed by `vault-cli` with 1 MiB chunks.
+ *
+ * # Safety
+ *
+ * `algorithm` must be a NUL-terminated string, `data` point to `len`
+ * readable bytes or be null if `len` is 0, and `out` point to
+ * `RMT_DIGEST_SIZE` writable bytes.
+ */
+int32_t rmt_chunk_root(const char *algorithm,
+                       size_t chunk_size,
+                       struct RmtTreeParams params,
+                       const uint8_t *data,
+                       size_t len,
+                       uint8_t *out);
+
+/**
+ * Returns a static NUL-terminated description of an error code.
+ */
+const char *rmt_error_message(int32_t code);
+
+#endif  /* RS_MERKLE_TREE_H */
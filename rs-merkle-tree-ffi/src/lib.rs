@@ -0,0 +1,572 @@
+//! C bindings to verify the JSON proofs served by the vault API, for clients
+//! without a Rust toolchain. The header `include/rs_merkle_tree.h` is
+//! generated from this crate by the build script, and checked in.
+//!
+//! Every function returns `RMT_OK` or an error code: the codes from 10 on
+//! match the errors of the library and the exit codes of `vault-cli`.
+
+use rs_merkle_tree::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, DIGEST_SIZE};
+use rs_merkle_tree::{
+    with_hasher, ChunkTree, MerkleError, MerkleNode, MerkleProof, MerkleRoot, OddNodeStrategy,
+    TreeParams,
+};
+use std::ffi::{c_char, CStr};
+use std::{ptr, slice};
+
+/// The call succeeded.
+pub const RMT_OK: i32 = 0;
+/// A pointer argument is null.
+pub const RMT_NULL_POINTER: i32 = 1;
+/// A string argument is not valid UTF-8.
+pub const RMT_INVALID_UTF8: i32 = 2;
+/// The proof is not a JSON proof.
+pub const RMT_INVALID_JSON: i32 = 3;
+/// The output buffer is too small.
+pub const RMT_BUFFER_TOO_SMALL: i32 = 4;
+/// A chunk size is 0.
+pub const RMT_INVALID_CHUNK_SIZE: i32 = 5;
+// Errors of the library, named after the `MerkleError` variants.
+pub const RMT_EMPTY_TREE: i32 = 10;
+pub const RMT_LEAF_NOT_FOUND: i32 = 11;
+pub const RMT_INDEX_OUT_OF_RANGE: i32 = 12;
+pub const RMT_MALFORMED_PROOF: i32 = 13;
+pub const RMT_HASH_LENGTH_MISMATCH: i32 = 14;
+pub const RMT_ALGORITHM_MISMATCH: i32 = 15;
+pub const RMT_MODE_MISMATCH: i32 = 16;
+pub const RMT_UNKNOWN_ALGORITHM: i32 = 17;
+pub const RMT_UNKNOWN_MODE: i32 = 18;
+pub const RMT_INVALID_HEX: i32 = 19;
+pub const RMT_ROOT_MISMATCH: i32 = 20;
+pub const RMT_INVALID_DUMP: i32 = 21;
+pub const RMT_CHUNK_MISMATCH: i32 = 22;
+pub const RMT_UNKNOWN_ODD_NODE_STRATEGY: i32 = 23;
+pub const RMT_PARAMS_MISMATCH: i32 = 24;
+pub const RMT_LEAF_COUNT_MISMATCH: i32 = 25;
+pub const RMT_INVALID_PATH: i32 = 26;
+pub const RMT_INVALID_SIGNATURE: i32 = 27;
+
+/// Size of the leaf hashes read and written by the functions.
+pub const RMT_DIGEST_SIZE: usize = 32;
+/// Size of a buffer large enough for any root written by
+/// `rmt_proof_compute_root`, terminating NUL included.
+pub const RMT_ROOT_BUFFER_SIZE: usize = 76;
+
+/// Hashing modes of `RmtTreeParams`, see `HashMode`.
+pub const RMT_MODE_LEGACY: u8 = 0;
+pub const RMT_MODE_RFC6962: u8 = 1;
+pub const RMT_MODE_SORTED_PAIR: u8 = 2;
+/// Odd node strategies of `RmtTreeParams`, see `OddNodeStrategy`.
+pub const RMT_ODD_NODE_DUPLICATE: u8 = 0;
+pub const RMT_ODD_NODE_PROMOTE: u8 = 1;
+pub const RMT_ODD_NODE_PAD_ZERO: u8 = 2;
+
+/// The parameters a tree is hashed with, see `TreeParams`. Vaults committed by
+/// `vault-cli` use `RMT_MODE_RFC6962`, `RMT_ODD_NODE_PROMOTE` and commit to
+/// their leaf count.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RmtTreeParams {
+    /// One of the `RMT_MODE_*` constants.
+    pub mode: u8,
+    /// One of the `RMT_ODD_NODE_*` constants.
+    pub odd_node: u8,
+    /// Whether the root commits to the number of leaves.
+    pub commit_leaf_count: bool,
+}
+
+impl TryFrom<RmtTreeParams> for TreeParams {
+    type Error = i32;
+
+    fn try_from(params: RmtTreeParams) -> Result<Self, i32> {
+        let mode = match params.mode {
+            RMT_MODE_LEGACY => HashMode::Legacy,
+            RMT_MODE_RFC6962 => HashMode::Rfc6962,
+            RMT_MODE_SORTED_PAIR => HashMode::SortedPair,
+            _ => return Err(RMT_UNKNOWN_MODE),
+        };
+        let odd_node = match params.odd_node {
+            RMT_ODD_NODE_DUPLICATE => OddNodeStrategy::Duplicate,
+            RMT_ODD_NODE_PROMOTE => OddNodeStrategy::Promote,
+            RMT_ODD_NODE_PAD_ZERO => OddNodeStrategy::PadZero,
+            _ => return Err(RMT_UNKNOWN_ODD_NODE_STRATEGY),
+        };
+        Ok(TreeParams {
+            mode,
+            odd_node,
+            commit_leaf_count: params.commit_leaf_count,
+        })
+    }
+}
+
+/// A proof parsed from JSON, hashed with the algorithm it records.
+pub struct RmtProof(Box<dyn AnyProof>);
+
+/// A `MerkleProof` whose hasher is only known at runtime.
+trait AnyProof {
+    fn nodes(&self) -> &[MerkleNode];
+    fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError>;
+    fn compute_root_with_params(&self, expected: TreeParams) -> Result<MerkleRoot, MerkleError>;
+}
+
+impl<H: MerkleHasher> AnyProof for MerkleProof<H> {
+    fn nodes(&self) -> &[MerkleNode] {
+        &self.nodes
+    }
+
+    fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError> {
+        MerkleProof::compute_tagged_root(self)
+    }
+
+    fn compute_root_with_params(&self, expected: TreeParams) -> Result<MerkleRoot, MerkleError> {
+        let root = MerkleProof::compute_root_with_params(self, expected)?;
+        Ok(MerkleRoot::new(H::ALGORITHM, root))
+    }
+}
+
+impl RmtProof {
+    /// Parses a proof, or a `download_proof` response holding it under its
+    /// `proof` key. Proofs without an algorithm are SHA-256 ones.
+    fn parse(json: &str) -> Result<Self, i32> {
+        let mut proof: serde_json::Value =
+            serde_json::from_str(json).map_err(|_| RMT_INVALID_JSON)?;
+        if let Some(inner) = proof.get_mut("proof") {
+            proof = inner.take();
+        }
+        let algorithm = match proof.get("algorithm") {
+            None => Algorithm::default(),
+            Some(algorithm) => algorithm
+                .as_str()
+                .ok_or(RMT_INVALID_JSON)?
+                .parse()
+                .map_err(|err| error_code(&err))?,
+        };
+        Ok(Self(with_hasher!(algorithm, |H| {
+            Box::new(from_value::<H>(proof)?) as Box<dyn AnyProof>
+        })))
+    }
+
+    /// Returns the hash of the proven leaf.
+    fn leaf(&self) -> Result<Digest, MerkleError> {
+        match self.0.nodes().first() {
+            Some(node) => Ok(node.hash),
+            None => Err(MerkleError::MalformedProof("missing proof")),
+        }
+    }
+
+    fn compute_root(&self) -> Result<MerkleRoot, MerkleError> {
+        self.0.compute_tagged_root()
+    }
+
+    /// Checks that the proof proves `leaf` under `root`, in a tree hashed with
+    /// the `expected` parameters rather than the ones the proof declares.
+    fn verify(
+        &self,
+        leaf: &Digest,
+        root: &MerkleRoot,
+        expected: TreeParams,
+    ) -> Result<(), MerkleError> {
+        let computed = self.0.compute_root_with_params(expected)?;
+        if computed.algorithm != root.algorithm {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: root.algorithm,
+                actual: computed.algorithm,
+            });
+        }
+        if self.leaf()? != *leaf || computed.hash != root.hash {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn from_value<H: MerkleHasher>(proof: serde_json::Value) -> Result<MerkleProof<H>, i32> {
+    serde_json::from_value(proof).map_err(|_| RMT_INVALID_JSON)
+}
+
+/// Returns the code matching an error of the library.
+fn error_code(err: &MerkleError) -> i32 {
+    match err {
+        MerkleError::EmptyTree => RMT_EMPTY_TREE,
+        MerkleError::LeafNotFound => RMT_LEAF_NOT_FOUND,
+        MerkleError::IndexOutOfRange { .. } => RMT_INDEX_OUT_OF_RANGE,
+        MerkleError::MalformedProof(_) => RMT_MALFORMED_PROOF,
+        MerkleError::HashLengthMismatch { .. } => RMT_HASH_LENGTH_MISMATCH,
+        MerkleError::AlgorithmMismatch { .. } => RMT_ALGORITHM_MISMATCH,
+        MerkleError::ModeMismatch { .. } => RMT_MODE_MISMATCH,
+        MerkleError::UnknownAlgorithm(_) => RMT_UNKNOWN_ALGORITHM,
+        MerkleError::UnknownMode(_) => RMT_UNKNOWN_MODE,
+        MerkleError::InvalidHex(_) => RMT_INVALID_HEX,
+        MerkleError::RootMismatch => RMT_ROOT_MISMATCH,
+        MerkleError::InvalidDump(_) => RMT_INVALID_DUMP,
+        MerkleError::ChunkMismatch { .. } => RMT_CHUNK_MISMATCH,
+        MerkleError::UnknownOddNodeStrategy(_) => RMT_UNKNOWN_ODD_NODE_STRATEGY,
+        MerkleError::ParamsMismatch { .. } => RMT_PARAMS_MISMATCH,
+        MerkleError::LeafCountMismatch { .. } => RMT_LEAF_COUNT_MISMATCH,
+        MerkleError::InvalidPath(_) => RMT_INVALID_PATH,
+        MerkleError::InvalidSignature => RMT_INVALID_SIGNATURE,
+    }
+}
+
+/// Reads a NUL-terminated UTF-8 string.
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, i32> {
+    if s.is_null() {
+        return Err(RMT_NULL_POINTER);
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| RMT_INVALID_UTF8)
+}
+
+/// Parses a JSON proof, as returned by the `proof` route of the vault API,
+/// either alone or with the whole response. On success, `*out` holds a proof
+/// to release with `rmt_proof_free`.
+///
+/// # Safety
+///
+/// `json` must be a NUL-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_proof_parse(json: *const c_char, out: *mut *mut RmtProof) -> i32 {
+    if out.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    *out = ptr::null_mut();
+    match read_str(json).and_then(RmtProof::parse) {
+        Ok(proof) => {
+            *out = Box::into_raw(Box::new(proof));
+            RMT_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// Releases a proof returned by `rmt_proof_parse`. Does nothing if `proof` is
+/// null.
+///
+/// # Safety
+///
+/// `proof` must be null or returned by `rmt_proof_parse`, and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_proof_free(proof: *mut RmtProof) {
+    if !proof.is_null() {
+        drop(Box::from_raw(proof));
+    }
+}
+
+/// Writes the hash of the leaf proven by `proof` to `out`.
+///
+/// # Safety
+///
+/// `proof` must be returned by `rmt_proof_parse` and `out` point to
+/// `RMT_DIGEST_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_proof_leaf(proof: *const RmtProof, out: *mut u8) -> i32 {
+    if proof.is_null() || out.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    match (*proof).leaf() {
+        Ok(leaf) => {
+            ptr::copy_nonoverlapping(leaf.0.as_ptr(), out, DIGEST_SIZE);
+            RMT_OK
+        }
+        Err(err) => error_code(&err),
+    }
+}
+
+/// Computes the root of `proof` and writes it to `out` as a NUL-terminated
+/// `<algorithm>:<hex>` string, e.g. `sha3-256:3a98…`. A buffer of
+/// `RMT_ROOT_BUFFER_SIZE` bytes is large enough for any root.
+///
+/// # Safety
+///
+/// `proof` must be returned by `rmt_proof_parse` and `out` point to `out_len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_proof_compute_root(
+    proof: *const RmtProof,
+    out: *mut c_char,
+    out_len: usize,
+) -> i32 {
+    if proof.is_null() || out.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    let root = match (*proof).compute_root() {
+        Ok(root) => root.to_string(),
+        Err(err) => return error_code(&err),
+    };
+    if root.len() >= out_len {
+        return RMT_BUFFER_TOO_SMALL;
+    }
+    ptr::copy_nonoverlapping(root.as_ptr(), out.cast::<u8>(), root.len());
+    *out.add(root.len()) = 0;
+    RMT_OK
+}
+
+/// Checks that `proof` proves the leaf hash `leaf` under `root`, given as a
+/// `<algorithm>:<hex>` string or as the bare hex of a SHA-256 root, in a tree
+/// hashed with the `params` the root was saved with. Returns `RMT_OK` if it
+/// does, `RMT_ROOT_MISMATCH` if the leaf or the root differ, and
+/// `RMT_PARAMS_MISMATCH` if the proof declares other parameters.
+///
+/// # Safety
+///
+/// `proof` must be returned by `rmt_proof_parse`, `leaf` point to
+/// `RMT_DIGEST_SIZE` readable bytes and `root` be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_proof_verify(
+    proof: *const RmtProof,
+    leaf: *const u8,
+    root: *const c_char,
+    params: RmtTreeParams,
+) -> i32 {
+    if proof.is_null() || leaf.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    let params = match TreeParams::try_from(params) {
+        Ok(params) => params,
+        Err(code) => return code,
+    };
+    let root = match read_str(root) {
+        Ok(root) => root,
+        Err(code) => return code,
+    };
+    let root: MerkleRoot = match root.parse() {
+        Ok(root) => root,
+        Err(err) => return error_code(&err),
+    };
+    let leaf = Digest(*leaf.cast::<[u8; DIGEST_SIZE]>());
+    match (*proof).verify(&leaf, &root, params) {
+        Ok(()) => RMT_OK,
+        Err(err) => error_code(&err),
+    }
+}
+
+/// Reads the `len` bytes at `data`, which may be null if `len` is 0.
+unsafe fn read_bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], i32> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(RMT_NULL_POINTER),
+        (false, _) => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// Writes to `out` the hash of the `len` bytes at `data` with `algorithm`,
+/// e.g. `sha3-256`: the leaf of a file in a vault whose files are not split
+/// into chunks.
+///
+/// # Safety
+///
+/// `algorithm` must be a NUL-terminated string, `data` point to `len`
+/// readable bytes or be null if `len` is 0, and `out` point to
+/// `RMT_DIGEST_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_hash_leaf(
+    algorithm: *const c_char,
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+) -> i32 {
+    if out.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    let algorithm: Algorithm = match read_str(algorithm) {
+        Ok(algorithm) => match algorithm.parse() {
+            Ok(algorithm) => algorithm,
+            Err(err) => return error_code(&err),
+        },
+        Err(code) => return code,
+    };
+    let data = match read_bytes(data, len) {
+        Ok(data) => data,
+        Err(code) => return code,
+    };
+    ptr::copy_nonoverlapping(algorithm.hash(data).0.as_ptr(), out, DIGEST_SIZE);
+    RMT_OK
+}
+
+/// Writes to `out` the root of the tree of the `len` bytes at `data` split
+/// into chunks of `chunk_size` bytes, hashed with `algorithm` and `params`:
+/// the leaf of a file in a vault whose files are split into chunks, such as
+/// the vaults committed by `vault-cli` with 1 MiB chunks.
+///
+/// # Safety
+///
+/// `algorithm` must be a NUL-terminated string, `data` point to `len`
+/// readable bytes or be null if `len` is 0, and `out` point to
+/// `RMT_DIGEST_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rmt_chunk_root(
+    algorithm: *const c_char,
+    chunk_size: usize,
+    params: RmtTreeParams,
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+) -> i32 {
+    if out.is_null() {
+        return RMT_NULL_POINTER;
+    }
+    if chunk_size == 0 {
+        return RMT_INVALID_CHUNK_SIZE;
+    }
+    let algorithm: Algorithm = match read_str(algorithm) {
+        Ok(algorithm) => match algorithm.parse() {
+            Ok(algorithm) => algorithm,
+            Err(err) => return error_code(&err),
+        },
+        Err(code) => return code,
+    };
+    let params = match TreeParams::try_from(params) {
+        Ok(params) => params,
+        Err(code) => return code,
+    };
+    let data = match read_bytes(data, len) {
+        Ok(data) => data,
+        Err(code) => return code,
+    };
+    // reading a slice cannot fail
+    let root = with_hasher!(algorithm, |H| ChunkTree::<H>::from_reader(
+        data, chunk_size, params
+    )
+    .unwrap()
+    .root());
+    ptr::copy_nonoverlapping(root.0.as_ptr(), out, DIGEST_SIZE);
+    RMT_OK
+}
+
+/// Returns a static NUL-terminated description of an error code.
+#[no_mangle]
+pub extern "C" fn rmt_error_message(code: i32) -> *const c_char {
+    let message = match code {
+        RMT_OK => c"Success",
+        RMT_NULL_POINTER => c"Null pointer argument",
+        RMT_INVALID_UTF8 => c"String is not valid UTF-8",
+        RMT_INVALID_JSON => c"Not a JSON proof",
+        RMT_BUFFER_TOO_SMALL => c"Output buffer too small",
+        RMT_INVALID_CHUNK_SIZE => c"Chunk size is 0",
+        RMT_EMPTY_TREE => c"Tree is empty",
+        RMT_LEAF_NOT_FOUND => c"Leaf not found",
+        RMT_INDEX_OUT_OF_RANGE => c"Leaf index out of range",
+        RMT_MALFORMED_PROOF => c"Malformed proof",
+        RMT_HASH_LENGTH_MISMATCH => c"Hash length does not match the algorithm",
+        RMT_ALGORITHM_MISMATCH => c"Hash algorithm does not match",
+        RMT_MODE_MISMATCH => c"Hash mode does not match",
+        RMT_UNKNOWN_ALGORITHM => c"Unknown hash algorithm",
+        RMT_UNKNOWN_MODE => c"Unknown hash mode",
+        RMT_INVALID_HEX => c"Invalid hexadecimal string",
+        RMT_ROOT_MISMATCH => c"Proof does not match the root hashes",
+        RMT_INVALID_DUMP => c"Invalid tree dump",
+        RMT_CHUNK_MISMATCH => c"Chunk does not match its proven hash",
+        RMT_UNKNOWN_ODD_NODE_STRATEGY => c"Unknown odd node strategy",
+        RMT_PARAMS_MISMATCH => c"Tree parameters do not match",
+        RMT_LEAF_COUNT_MISMATCH => c"Leaf count does not match",
+        RMT_INVALID_PATH => c"Invalid path",
+        RMT_INVALID_SIGNATURE => c"Signature does not match the tree head",
+        _ => c"Unknown error code",
+    };
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rs_merkle_tree::utils::crypto::{HashMode, Sha3_256};
+    use rs_merkle_tree::MerkleTree;
+
+    const PARAMS: RmtTreeParams = RmtTreeParams {
+        mode: RMT_MODE_RFC6962,
+        odd_node: RMT_ODD_NODE_PROMOTE,
+        commit_leaf_count: true,
+    };
+
+    fn tree() -> MerkleTree<Sha3_256> {
+        let leaves: Vec<_> = (0u8..5).map(|i| Sha3_256::hash(&[i])).collect();
+        MerkleTree::build(leaves, TreeParams::try_from(PARAMS).unwrap())
+    }
+
+    #[test]
+    fn should_verify_proof_response() {
+        let tree = tree();
+        let proof = tree.proof_for_index(4).unwrap();
+        let response = serde_json::json!({ "success": true, "proof": proof });
+        let proof = RmtProof::parse(&response.to_string()).unwrap();
+        let root = tree.tagged_root().unwrap();
+        let leaf = Sha3_256::hash(&[4]);
+
+        assert_eq!(proof.leaf(), Ok(leaf));
+        assert_eq!(proof.compute_root(), Ok(root.clone()));
+        assert_eq!(RMT_DIGEST_SIZE, DIGEST_SIZE);
+        assert!(root.to_string().len() < RMT_ROOT_BUFFER_SIZE);
+        let params = TreeParams::try_from(PARAMS).unwrap();
+        assert_eq!(proof.verify(&leaf, &root, params), Ok(()));
+        assert_eq!(
+            proof.verify(&Sha3_256::hash(&[3]), &root, params),
+            Err(MerkleError::RootMismatch)
+        );
+        let sha256_root = MerkleRoot::new(Algorithm::Sha256, root.hash);
+        assert!(matches!(
+            proof.verify(&leaf, &sha256_root, params),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+        // the proof declares its params, the verifier must not trust them
+        let legacy = TreeParams {
+            mode: HashMode::Legacy,
+            ..params
+        };
+        assert!(matches!(
+            proof.verify(&leaf, &root, legacy),
+            Err(MerkleError::ParamsMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn should_hash_leaves_as_vault_cli() {
+        let data = [7u8; 10];
+        let sha3 = c"sha3-256".as_ptr();
+        let mut out = [0u8; RMT_DIGEST_SIZE];
+
+        assert_eq!(
+            unsafe { rmt_hash_leaf(sha3, data.as_ptr(), data.len(), out.as_mut_ptr()) },
+            RMT_OK
+        );
+        assert_eq!(Digest(out), Sha3_256::hash(&data));
+        assert_eq!(
+            unsafe { rmt_hash_leaf(sha3, ptr::null(), 0, out.as_mut_ptr()) },
+            RMT_OK
+        );
+        assert_eq!(Digest(out), Sha3_256::hash(&[]));
+        assert_eq!(
+            unsafe { rmt_hash_leaf(sha3, ptr::null(), 1, out.as_mut_ptr()) },
+            RMT_NULL_POINTER
+        );
+
+        let chunk_root = |chunk_size, params, out: &mut [u8; RMT_DIGEST_SIZE]| unsafe {
+            rmt_chunk_root(
+                sha3,
+                chunk_size,
+                params,
+                data.as_ptr(),
+                data.len(),
+                out.as_mut_ptr(),
+            )
+        };
+        assert_eq!(chunk_root(4, PARAMS, &mut out), RMT_OK);
+        let params = TreeParams::try_from(PARAMS).unwrap();
+        let tree = ChunkTree::<Sha3_256>::from_reader(&data[..], 4, params).unwrap();
+        assert_eq!(Digest(out), tree.root());
+        assert_eq!(chunk_root(0, PARAMS, &mut out), RMT_INVALID_CHUNK_SIZE);
+        let unknown = RmtTreeParams { mode: 3, ..PARAMS };
+        assert_eq!(chunk_root(4, unknown, &mut out), RMT_UNKNOWN_MODE);
+    }
+
+    #[test]
+    fn should_reject_invalid_proofs() {
+        assert!(matches!(RmtProof::parse("{"), Err(RMT_INVALID_JSON)));
+        assert!(matches!(
+            RmtProof::parse(r#"{"nodes": [], "algorithm": "md5"}"#),
+            Err(RMT_UNKNOWN_ALGORITHM)
+        ));
+        let empty = RmtProof::parse(r#"{"nodes": []}"#).unwrap();
+        assert_eq!(
+            empty.compute_root(),
+            Err(MerkleError::MalformedProof("missing proof"))
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Builds `tests/ffi/verify.c` against the static library and runs it on a
+//! proof response of the vault API.
+
+use rs_merkle_tree::utils::crypto::{HashMode, MerkleHasher, Sha3_256};
+use rs_merkle_tree::{ChunkTree, MerkleTree, OddNodeStrategy, TreeParams};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+/// Returns the directory holding the libraries of the crate, the parent of
+/// the `deps` directory of the test executable.
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().and_then(Path::parent).unwrap().to_path_buf()
+}
+
+#[test]
+fn checked_in_header_should_be_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/rs_merkle_tree.h"));
+    let checked_in = include_str!("../include/rs_merkle_tree.h");
+    assert!(
+        generated == checked_in,
+        "include/rs_merkle_tree.h is outdated, copy it from {}",
+        env!("OUT_DIR")
+    );
+}
+
+#[test]
+fn c_program_should_verify_proof_response() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let tmp_dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+
+    // the leaves of a vault committed by `vault-cli`, with 4 bytes chunks
+    let data = "this is file 6";
+    let params = TreeParams {
+        mode: HashMode::Rfc6962,
+        odd_node: OddNodeStrategy::Promote,
+        commit_leaf_count: true,
+    };
+    let mut leaves: Vec<_> = (0u8..6).map(|i| Sha3_256::hash(&[i])).collect();
+    let chunks = ChunkTree::<Sha3_256>::from_reader(data.as_bytes(), 4, params).unwrap();
+    leaves.push(chunks.root());
+    let tree = MerkleTree::<Sha3_256>::build(leaves, params);
+    let proof = tree.proof_for_index(6).unwrap();
+    let response = serde_json::json!({ "success": true, "proof": proof });
+    let proof_path = tmp_dir.join("proof.json");
+    fs::write(&proof_path, response.to_string()).unwrap();
+
+    let program = tmp_dir.join("verify");
+    let status = Command::new(env::var("CC").unwrap_or("cc".to_string()))
+        .arg(manifest_dir.join("tests/ffi/verify.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(library_dir().join("librs_merkle_tree_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("Unable to run the C compiler");
+    assert!(status.success(), "Unable to build tests/ffi/verify.c");
+
+    let output = Command::new(&program)
+        .arg(&proof_path)
+        .arg(tree.tagged_root().unwrap().to_string())
+        .arg(data)
+        .arg(hex::encode(Sha3_256::hash(data.as_bytes())))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,112 @@
+/*
+ * Verifies a proof through the C bindings of the library, as a client
+ * without a Rust toolchain would. Built and run by `tests/ffi.rs`.
+ *
+ * Usage: verify <proof.json> <root> <data> <data hash hex>
+ *
+ * The proven leaf is the root of the 4 bytes chunks of `data`, in a vault
+ * hashed as `vault-cli` commits them.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rs_merkle_tree.h"
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,         \
+                    __LINE__, #condition);                                 \
+            exit(1);                                                       \
+        }                                                                  \
+    } while (0)
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    CHECK(file != NULL);
+    CHECK(fseek(file, 0, SEEK_END) == 0);
+    long len = ftell(file);
+    CHECK(len >= 0);
+    rewind(file);
+    char *content = malloc((size_t)len + 1);
+    CHECK(content != NULL);
+    CHECK(fread(content, 1, (size_t)len, file) == (size_t)len);
+    content[len] = '\0';
+    fclose(file);
+    return content;
+}
+
+static void parse_hex(const char *hex, uint8_t out[RMT_DIGEST_SIZE]) {
+    CHECK(strlen(hex) == 2 * RMT_DIGEST_SIZE);
+    for (size_t i = 0; i < RMT_DIGEST_SIZE; i++) {
+        CHECK(sscanf(hex + 2 * i, "%2hhx", &out[i]) == 1);
+    }
+}
+
+int main(int argc, char **argv) {
+    if (argc != 5) {
+        fprintf(stderr, "Usage: %s <proof.json> <root> <data> <data hash hex>\n",
+                argv[0]);
+        return 2;
+    }
+    char *json = read_file(argv[1]);
+    const char *root = argv[2];
+    const uint8_t *data = (const uint8_t *)argv[3];
+    size_t len = strlen(argv[3]);
+    const RmtTreeParams params = {
+        .mode = RMT_MODE_RFC6962,
+        .odd_node = RMT_ODD_NODE_PROMOTE,
+        .commit_leaf_count = true,
+    };
+
+    uint8_t hash[RMT_DIGEST_SIZE], expected[RMT_DIGEST_SIZE];
+    parse_hex(argv[4], expected);
+    CHECK(rmt_hash_leaf("sha3-256", data, len, hash) == RMT_OK);
+    CHECK(memcmp(hash, expected, RMT_DIGEST_SIZE) == 0);
+    CHECK(rmt_hash_leaf("md5", data, len, hash) == RMT_UNKNOWN_ALGORITHM);
+
+    uint8_t leaf[RMT_DIGEST_SIZE];
+    CHECK(rmt_chunk_root("sha3-256", 4, params, data, len, leaf) == RMT_OK);
+    CHECK(rmt_chunk_root("sha3-256", 0, params, data, len, leaf) ==
+          RMT_INVALID_CHUNK_SIZE);
+
+    RmtProof *proof = NULL;
+    CHECK(rmt_proof_parse(json, &proof) == RMT_OK);
+    CHECK(proof != NULL);
+
+    uint8_t proven[RMT_DIGEST_SIZE];
+    CHECK(rmt_proof_leaf(proof, proven) == RMT_OK);
+    CHECK(memcmp(proven, leaf, RMT_DIGEST_SIZE) == 0);
+
+    char computed[RMT_ROOT_BUFFER_SIZE];
+    CHECK(rmt_proof_compute_root(proof, computed, sizeof(computed)) == RMT_OK);
+    CHECK(strcmp(computed, root) == 0);
+    CHECK(rmt_proof_compute_root(proof, computed, 8) == RMT_BUFFER_TOO_SMALL);
+
+    CHECK(rmt_proof_verify(proof, leaf, root, params) == RMT_OK);
+    leaf[0] ^= 1;
+    CHECK(rmt_proof_verify(proof, leaf, root, params) == RMT_ROOT_MISMATCH);
+    leaf[0] ^= 1;
+    RmtTreeParams legacy = params;
+    legacy.mode = RMT_MODE_LEGACY;
+    CHECK(rmt_proof_verify(proof, leaf, root, legacy) == RMT_PARAMS_MISMATCH);
+    legacy.mode = 42;
+    CHECK(rmt_proof_verify(proof, leaf, root, legacy) == RMT_UNKNOWN_MODE);
+    CHECK(rmt_proof_verify(proof, leaf, "sha256:00", params) ==
+          RMT_HASH_LENGTH_MISMATCH);
+    CHECK(rmt_proof_verify(proof, leaf, "sha256:zz", params) == RMT_INVALID_HEX);
+    CHECK(rmt_proof_verify(proof, leaf, NULL, params) == RMT_NULL_POINTER);
+    rmt_proof_free(proof);
+
+    RmtProof *invalid = NULL;
+    CHECK(rmt_proof_parse("{\"success\": true", &invalid) == RMT_INVALID_JSON);
+    CHECK(invalid == NULL);
+    CHECK(rmt_proof_parse("{\"nodes\": [], \"algorithm\": \"md5\"}", &invalid) ==
+          RMT_UNKNOWN_ALGORITHM);
+    CHECK(strcmp(rmt_error_message(RMT_ROOT_MISMATCH),
+                 "Proof does not match the root hashes") == 0);
+
+    free(json);
+    return 0;
+}
@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rs_merkle_tree::utils::crypto::{hash, Digest, Hash, HashMode, MerkleHasher, Sha256};
+use rs_merkle_tree::MerkleTree;
+
+const LEAF_COUNT: usize = 1_000_000;
+
+/// Builds the nodes the way trees were built before `Digest`: one `Vec<u8>`
+/// per node and one concatenation buffer per interior node.
+fn build_with_vec_hashes(leaves: Vec<Hash>) -> Vec<Hash> {
+    let mut hashes = leaves;
+    let mut level_start = 0;
+    let mut level_len = hashes.len();
+    while level_len > 1 {
+        for i in (0..level_len).step_by(2) {
+            let left = hashes[level_start + i].clone();
+            let right = hashes[level_start + (i + 1).min(level_len - 1)].clone();
+            hashes.push(hash(&[left, right].concat()));
+        }
+        level_start += level_len;
+        level_len = level_len.div_ceil(2);
+    }
+    hashes
+}
+
+fn bench_build(c: &mut Criterion) {
+    let leaves: Vec<Digest> = (0..LEAF_COUNT)
+        .map(|i| Sha256::hash(&i.to_le_bytes()))
+        .collect();
+    let vec_leaves: Vec<Hash> = leaves.iter().map(Digest::to_vec).collect();
+
+    let mut group = c.benchmark_group("build 1M leaves");
+    group.sample_size(10);
+    group.bench_function("Vec<u8> nodes", |b| {
+        b.iter_batched(
+            || vec_leaves.clone(),
+            build_with_vec_hashes,
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("Digest nodes", |b| {
+        b.iter_batched(
+            || leaves.clone(),
+            |leaves| MerkleTree::<Sha256>::build(leaves, HashMode::Legacy),
+            BatchSize::LargeInput,
+        )
+    });
+    #[cfg(feature = "parallel")]
+    group.bench_function("Digest nodes, parallel", |b| {
+        b.iter_batched(
+            || leaves.clone(),
+            |leaves| MerkleTree::<Sha256>::build_parallel(leaves, HashMode::Legacy),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_build);
+criterion_main!(benches);
@@ -0,0 +1,472 @@
+use crate::consistency_proof::ConsistencyProof;
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Hashing mode of append-only trees, whose shape and proofs follow RFC 6962.
+pub(crate) const MODE: HashMode = HashMode::Rfc6962;
+
+/// Bags the roots of the perfect subtrees covering consecutive leaves, from
+/// the highest to the lowest, into the root of the tree of those leaves by
+/// merging them from the right to the left.
+pub(crate) fn bag_peaks<H: MerkleHasher>(peaks: &[Digest]) -> Option<Digest> {
+    let mut peaks = peaks.iter().rev();
+    let mut root = *peaks.next()?;
+    for peak in peaks {
+        root = MODE.hash_nodes::<H>(peak, &root);
+    }
+    Some(root)
+}
+
+/// Represents an append-only Merkle tree, shaped and hashed as described in
+/// [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1).
+///
+/// Unlike `MerkleTree`, the last node of an odd level is not paired with
+/// itself: a tree of `n` leaves is split into a perfect left subtree of the
+/// largest power of two lower than `n` leaves, and the rest. Both trees have
+/// the same root when the number of leaves is a power of two.
+///
+/// The tree keeps every node of the perfect subtrees of its leaves, about `2n`
+/// hashes, so that it can compute the root of any prefix of the leaves and
+/// generate consistency proofs in O(log n). To only append leaves and compute
+/// the root, `AppendOnlyFrontier` keeps O(log n) hashes instead.
+pub struct AppendOnlyTree<H: MerkleHasher = Sha256> {
+    /// Nodes of the perfect subtrees by height: `levels[h][i]` is the root of
+    /// the `2^h` leaves starting at `i * 2^h`, `levels[0]` the hashed leaves.
+    levels: Vec<Vec<Digest>>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> Default for AppendOnlyTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> AppendOnlyTree<H> {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self {
+            levels: vec![Vec::new()],
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Creates a tree by appending `leaves` in order.
+    pub fn from_leaves(leaves: &[Digest]) -> Self {
+        let mut tree = Self::new();
+        for leaf in leaves {
+            tree.push(leaf);
+        }
+        tree
+    }
+
+    /// Returns the hash algorithm of the tree.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn size(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Appends a leaf to the tree.
+    pub fn push(&mut self, leaf: &Digest) {
+        self.levels[0].push(MODE.hash_leaf::<H>(leaf));
+        // each perfect subtree the new leaf completes is merged with its left
+        // neighbour into a subtree one level higher
+        let mut height = 0;
+        while self.levels[height].len().is_multiple_of(2) {
+            let level = &self.levels[height];
+            let node = MODE.hash_nodes::<H>(&level[level.len() - 2], &level[level.len() - 1]);
+            if self.levels.len() == height + 1 {
+                self.levels.push(Vec::new());
+            }
+            self.levels[height + 1].push(node);
+            height += 1;
+        }
+    }
+
+    /// Returns the root of the perfect subtree of the `2^height` leaves
+    /// starting at `index * 2^height`.
+    pub(crate) fn node(&self, height: usize, index: usize) -> Digest {
+        self.levels[height][index]
+    }
+
+    /// Returns the roots of the perfect subtrees covering the leaves, from the
+    /// highest to the lowest.
+    pub(crate) fn peaks(&self) -> Vec<Digest> {
+        self.subtree_peaks(0, self.size())
+    }
+
+    /// Returns the frontier of the tree, which can keep appending leaves and
+    /// computing the root without the other nodes.
+    pub fn frontier(&self) -> AppendOnlyFrontier<H> {
+        AppendOnlyFrontier::new(self.size(), self.peaks()).expect("one peak per bit of the size")
+    }
+
+    /// Returns the root hash of the tree.
+    pub fn root(&self) -> Option<Digest> {
+        bag_peaks::<H>(&self.peaks())
+    }
+
+    /// Returns the root hash of the tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, root))
+    }
+
+    /// Returns the root hash of the tree made of the first `size` leaves.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` if `size` is 0 and
+    /// `MerkleError::IndexOutOfRange` if it is greater than the tree size.
+    pub fn root_at(&self, size: usize) -> Result<Digest, MerkleError> {
+        self.check_size(size)?;
+        Ok(self.subtree_root(0, size))
+    }
+
+    /// Generates a proof that the tree made of the first `old_size` leaves is
+    /// a prefix of the current tree, as described in
+    /// [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1.2).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` if `old_size` is 0 and
+    /// `MerkleError::IndexOutOfRange` if it is greater than the tree size.
+    pub fn consistency_proof(&self, old_size: usize) -> Result<ConsistencyProof<H>, MerkleError> {
+        self.check_size(old_size)?;
+        let mut nodes = Vec::new();
+        self.subproof(old_size, 0, self.size(), true, &mut nodes);
+        Ok(ConsistencyProof::new(old_size, self.size(), nodes))
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), MerkleError> {
+        if size == 0 {
+            return Err(MerkleError::EmptyTree);
+        }
+        if size > self.size() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: size,
+                leaf_count: self.size(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the roots of the perfect subtrees matching the set bits of
+    /// `len`, which cover the `len` leaves starting at `start`, a multiple of
+    /// the smallest power of two not lower than `len`.
+    fn subtree_peaks(&self, start: usize, len: usize) -> Vec<Digest> {
+        let mut start = start;
+        let mut peaks = Vec::new();
+        for height in (0..self.levels.len()).rev() {
+            if len & (1 << height) != 0 {
+                peaks.push(self.levels[height][start >> height]);
+                start += 1 << height;
+            }
+        }
+        peaks
+    }
+
+    /// Computes the root of the subtree of the `len` leaves starting at
+    /// `start`, a multiple of the smallest power of two not lower than `len`.
+    fn subtree_root(&self, start: usize, len: usize) -> Digest {
+        bag_peaks::<H>(&self.subtree_peaks(start, len)).expect("subtree of at least one leaf")
+    }
+
+    /// Pushes the nodes of `SUBPROOF(m, D[start:start + n], complete)` from
+    /// RFC 6962.
+    ///
+    /// Every subtree is perfect once the recursion has turned left, so at most
+    /// one pushed root is not a stored node.
+    fn subproof(&self, m: usize, start: usize, n: usize, complete: bool, nodes: &mut Vec<Digest>) {
+        if m == n {
+            if !complete {
+                nodes.push(self.subtree_root(start, n));
+            }
+            return;
+        }
+        let split = split_point(n);
+        if m <= split {
+            self.subproof(m, start, split, complete, nodes);
+            nodes.push(self.subtree_root(start + split, n - split));
+        } else {
+            self.subproof(m - split, start + split, n - split, false, nodes);
+            nodes.push(self.subtree_root(start, split));
+        }
+    }
+}
+
+/// Represents the frontier of an append-only tree: the roots of the perfect
+/// subtrees covering its leaves, one per set bit of its size, without the
+/// other nodes.
+///
+/// It only keeps O(log n) hashes, and appends a leaf and computes the root in
+/// O(log n) as well, but cannot compute the root of an earlier size nor
+/// generate consistency proofs: that needs the nodes kept by
+/// `AppendOnlyTree`. The root of the frontier of a tree is the root of the
+/// tree, so the frontier is enough to check a consistency proof ending at the
+/// current size.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(try_from = "RawFrontier")]
+pub struct AppendOnlyFrontier<H: MerkleHasher = Sha256> {
+    size: usize,
+    peaks: Vec<Digest>,
+    algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+/// Serialized `AppendOnlyFrontier`, before its algorithm and number of peaks
+/// are checked.
+#[derive(Deserialize)]
+struct RawFrontier {
+    size: usize,
+    peaks: Vec<Digest>,
+    algorithm: Algorithm,
+}
+
+impl<H: MerkleHasher> TryFrom<RawFrontier> for AppendOnlyFrontier<H> {
+    type Error = MerkleError;
+
+    fn try_from(raw: RawFrontier) -> Result<Self, Self::Error> {
+        if raw.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: raw.algorithm,
+            });
+        }
+        Self::new(raw.size, raw.peaks)
+    }
+}
+
+impl<H: MerkleHasher> Default for AppendOnlyFrontier<H> {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            peaks: Vec::new(),
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+}
+
+impl<H: MerkleHasher> AppendOnlyFrontier<H> {
+    /// Creates the frontier of a tree of `size` leaves from its peaks, from the
+    /// highest to the lowest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if there is not one peak per set
+    /// bit of `size`.
+    pub fn new(size: usize, peaks: Vec<Digest>) -> Result<Self, MerkleError> {
+        if peaks.len() != size.count_ones() as usize {
+            return Err(MerkleError::MalformedProof(
+                "not one peak per bit of the size",
+            ));
+        }
+        Ok(Self {
+            size,
+            peaks,
+            ..Self::default()
+        })
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the peaks, from the highest to the lowest.
+    pub fn peaks(&self) -> &[Digest] {
+        &self.peaks
+    }
+
+    /// Returns the hash algorithm of the tree.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Appends a leaf to the tree.
+    pub fn push(&mut self, leaf: &Digest) {
+        let mut node = MODE.hash_leaf::<H>(leaf);
+        // each trailing one bit of the size is a perfect subtree the new leaf
+        // completes, merging it with its left neighbour
+        let mut size = self.size;
+        while size % 2 == 1 {
+            let left = self.peaks.pop().expect("one peak per bit of the size");
+            node = MODE.hash_nodes::<H>(&left, &node);
+            size /= 2;
+        }
+        self.peaks.push(node);
+        self.size += 1;
+    }
+
+    /// Returns the root hash of the tree, bagging the peaks.
+    pub fn root(&self) -> Option<Digest> {
+        bag_peaks::<H>(&self.peaks)
+    }
+
+    /// Returns the root hash of the tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, root))
+    }
+}
+
+/// Returns the largest power of two lower than `n`, for `n` greater than 1.
+fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::Sha3_256;
+
+    fn leaves(count: usize) -> Vec<Digest> {
+        (0..count).map(|i| Sha256::hash(&i.to_le_bytes())).collect()
+    }
+
+    /// Computes `MTH(D[n])` straight from its definition in RFC 6962.
+    fn reference_root(leaves: &[Digest]) -> Digest {
+        if leaves.len() == 1 {
+            return MODE.hash_leaf::<Sha256>(&leaves[0]);
+        }
+        let mut k = 1;
+        while k * 2 < leaves.len() {
+            k *= 2;
+        }
+        MODE.hash_nodes::<Sha256>(&reference_root(&leaves[..k]), &reference_root(&leaves[k..]))
+    }
+
+    #[test]
+    fn should_split_at_largest_power_of_two() {
+        let expected = [(2, 1), (3, 2), (4, 2), (5, 4), (8, 4), (9, 8), (1000, 512)];
+        for (n, k) in expected {
+            assert_eq!(split_point(n), k, "split point of {n}");
+        }
+    }
+
+    #[test]
+    fn should_compute_rfc6962_root_while_appending() {
+        let leaves = leaves(70);
+        let mut tree = AppendOnlyTree::<Sha256>::new();
+        assert_eq!(tree.root(), None);
+
+        for size in 1..=leaves.len() {
+            tree.push(&leaves[size - 1]);
+            assert_eq!(tree.size(), size);
+            assert_eq!(tree.root(), Some(reference_root(&leaves[..size])));
+            for (height, level) in tree.levels.iter().enumerate() {
+                assert_eq!(level.len(), size >> height);
+            }
+        }
+        for size in 1..=leaves.len() {
+            assert_eq!(tree.root_at(size), Ok(reference_root(&leaves[..size])));
+        }
+    }
+
+    #[test]
+    fn should_append_and_compute_root_from_frontier_only() {
+        let leaves = leaves(70);
+        let mut frontier = AppendOnlyFrontier::<Sha256>::default();
+        assert_eq!(frontier.root(), None);
+
+        for size in 1..=leaves.len() {
+            frontier.push(&leaves[size - 1]);
+            assert_eq!(frontier.size(), size);
+            assert_eq!(frontier.peaks().len(), size.count_ones() as usize);
+            assert_eq!(frontier.root(), Some(reference_root(&leaves[..size])));
+        }
+
+        // a frontier saved at some size checks the consistency proofs of the
+        // tree which kept growing
+        let tree = AppendOnlyTree::<Sha256>::from_leaves(&leaves);
+        let saved = AppendOnlyTree::<Sha256>::from_leaves(&leaves[..45]).frontier();
+        let json = serde_json::to_string(&saved).unwrap();
+        let saved: AppendOnlyFrontier<Sha256> = serde_json::from_str(&json).unwrap();
+        let proof = tree.consistency_proof(saved.size()).unwrap();
+        assert_eq!(
+            proof.verify(&saved.root().unwrap(), &frontier.root().unwrap()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_reject_frontier_not_matching_its_size() {
+        let leaves = leaves(3);
+        assert!(matches!(
+            AppendOnlyFrontier::<Sha256>::new(3, leaves[..1].to_vec()),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        let json = serde_json::to_string(
+            &AppendOnlyFrontier::<Sha256>::new(1, leaves[..1].to_vec()).unwrap(),
+        )
+        .unwrap();
+        let forged = json.replace("\"size\":1", "\"size\":3");
+        assert_ne!(forged, json);
+        assert!(serde_json::from_str::<AppendOnlyFrontier<Sha256>>(&forged).is_err());
+        assert!(serde_json::from_str::<AppendOnlyFrontier<Sha3_256>>(&json).is_err());
+    }
+
+    #[test]
+    fn should_match_merkle_tree_on_powers_of_two() {
+        for size in [1, 2, 4, 8, 32] {
+            let tree = MerkleTree::<Sha256>::build(leaves(size), HashMode::Rfc6962);
+            assert_eq!(
+                AppendOnlyTree::<Sha256>::from_leaves(&leaves(size)).root(),
+                tree.root().copied()
+            );
+        }
+    }
+
+    #[test]
+    fn should_prove_consistency_between_any_sizes() {
+        let leaves = leaves(40);
+        let tree = AppendOnlyTree::<Sha256>::from_leaves(&leaves);
+
+        for new_size in 1..=leaves.len() {
+            let new_tree = AppendOnlyTree::<Sha256>::from_leaves(&leaves[..new_size]);
+            let new_root = new_tree.root().unwrap();
+            for old_size in 1..=new_size {
+                let old_root = tree.root_at(old_size).unwrap();
+                let proof = new_tree.consistency_proof(old_size).unwrap();
+                assert_eq!(proof.verify(&old_root, &new_root), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn should_reject_rewritten_history() {
+        let mut rewritten = leaves(7);
+        rewritten[2] = Sha256::hash(b"rewritten");
+        let old_root = AppendOnlyTree::<Sha256>::from_leaves(&leaves(7))
+            .root()
+            .unwrap();
+
+        let mut tree = AppendOnlyTree::<Sha256>::from_leaves(&rewritten);
+        tree.push(&Sha256::hash(b"appended"));
+        let proof = tree.consistency_proof(7).unwrap();
+
+        assert_eq!(
+            proof.verify(&old_root, &tree.root().unwrap()),
+            Err(MerkleError::RootMismatch)
+        );
+        assert_eq!(
+            tree.consistency_proof(0).unwrap_err(),
+            MerkleError::EmptyTree
+        );
+        assert!(matches!(
+            tree.consistency_proof(9),
+            Err(MerkleError::IndexOutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::error::MerkleError;
+use crate::merkle_range_proof::RangeProof;
+use crate::merkle_tree::MerkleTree;
+#[cfg(feature = "std")]
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Digest, IncrementalHasher, MerkleHasher, Sha256};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+/// Size of the chunks files are split into by default: 1 MiB.
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Represents the Merkle tree of a file split into fixed-size chunks, the last
+/// one being shorter. An empty file is made of a single empty chunk.
+///
+/// Its root stands for the whole file, e.g. as a leaf of a vault tree, while
+/// range proofs of the tree prove chunks on their own: a part of the file can
+/// be checked without reading the rest of it.
+pub struct ChunkTree<H: MerkleHasher = Sha256> {
+    tree: MerkleTree<H>,
+    chunk_size: usize,
+    file_size: u64,
+}
+
+impl<H: MerkleHasher> ChunkTree<H> {
+    /// Creates the chunk tree of the content of `reader`, hashing it chunk by
+    /// chunk so that a single chunk is held in memory at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `reader` if reading fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    #[cfg(feature = "std")]
+    pub fn from_reader(
+        mut reader: impl Read,
+        chunk_size: usize,
+        params: impl Into<TreeParams>,
+    ) -> io::Result<Self> {
+        assert!(chunk_size > 0, "chunks must not be empty");
+        let mut chunk = vec![0; chunk_size];
+        let mut chunks = Vec::new();
+        let mut file_size = 0;
+        loop {
+            let len = read_chunk(&mut reader, &mut chunk)?;
+            file_size += len as u64;
+            if len > 0 || chunks.is_empty() {
+                chunks.push(H::hash(&chunk[..len]));
+            }
+            if len < chunk_size {
+                break;
+            }
+        }
+
+        Ok(Self {
+            tree: MerkleTree::build(chunks, params),
+            chunk_size,
+            file_size,
+        })
+    }
+
+    /// Returns the root hash of the tree, which stands for the whole file.
+    pub fn root(&self) -> Digest {
+        *self.tree.root().expect("at least one chunk")
+    }
+
+    /// Returns the Merkle tree of the chunk hashes.
+    pub fn tree(&self) -> &MerkleTree<H> {
+        &self.tree
+    }
+
+    /// Returns the size of the chunks.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the number of chunks of the file.
+    pub fn chunk_count(&self) -> usize {
+        self.tree.leaf_count()
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Generates a proof for the chunks at positions `chunks`, holding their
+    /// hashes.
+    pub fn range_proof(&self, chunks: Range<usize>) -> Result<RangeProof<H>, MerkleError> {
+        self.tree.range_proof(chunks)
+    }
+}
+
+/// Fills `chunk` from `reader`, returning the number of bytes read, lower than
+/// the chunk size only at the end of the reader.
+#[cfg(feature = "std")]
+fn read_chunk(reader: &mut impl Read, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+/// Checks chunks of a file as they are received, e.g. while streaming an HTTP
+/// range response, against the chunk hashes of a range proof.
+///
+/// The data fed to the verifier must start at the first chunk of the proof.
+/// Each chunk is checked as soon as it is complete, so a corrupted download is
+/// detected at its first altered chunk, and `verified_len` bytes can be kept
+/// to resume it.
+pub struct ChunkVerifier<H: MerkleHasher = Sha256> {
+    chunks: Vec<Digest>,
+    start: usize,
+    next: usize,
+    chunk_size: usize,
+    hasher: IncrementalHasher,
+    filled: usize,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> ChunkVerifier<H> {
+    /// Creates a verifier for the chunks proven by `proof`, after checking
+    /// that the proof leads to `root`, the root of the chunk tree of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::RootMismatch` if the proof does not lead to
+    /// `root`, or the error of `RangeProof::compute_root` if it fails.
+    pub fn new(
+        proof: &RangeProof<H>,
+        root: &Digest,
+        chunk_size: usize,
+    ) -> Result<Self, MerkleError> {
+        if proof.compute_root()? != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(Self {
+            chunks: proof.leaves.clone(),
+            start: proof.start,
+            next: 0,
+            chunk_size,
+            hasher: IncrementalHasher::new(H::ALGORITHM),
+            filled: 0,
+            _hasher: PhantomData,
+        })
+    }
+
+    /// Returns the number of bytes fed to the verifier and already checked,
+    /// i.e. in complete chunks.
+    pub fn verified_len(&self) -> u64 {
+        (self.next * self.chunk_size) as u64
+    }
+
+    /// Feeds the next bytes of the file to the verifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ChunkMismatch` if a chunk completed by `data`
+    /// does not match its hash, or goes beyond the proven chunks.
+    pub fn update(&mut self, mut data: &[u8]) -> Result<(), MerkleError> {
+        while !data.is_empty() {
+            let len = data.len().min(self.chunk_size - self.filled);
+            self.hasher.update(&data[..len]);
+            self.filled += len;
+            data = &data[len..];
+            if self.filled == self.chunk_size {
+                self.check_chunk()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the last chunk, which may be shorter, and that every proven
+    /// chunk was received.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ChunkMismatch` if the last chunk does not match
+    /// its hash or if chunks are missing.
+    pub fn finalize(mut self) -> Result<(), MerkleError> {
+        // the pending chunk may be empty, as the only chunk of an empty file
+        if self.filled > 0 || self.next < self.chunks.len() {
+            self.check_chunk()?;
+        }
+        match self.next == self.chunks.len() {
+            true => Ok(()),
+            false => Err(MerkleError::ChunkMismatch {
+                index: self.start + self.next,
+            }),
+        }
+    }
+
+    fn check_chunk(&mut self) -> Result<(), MerkleError> {
+        let hasher = core::mem::replace(&mut self.hasher, IncrementalHasher::new(H::ALGORITHM));
+        let index = self.start + self.next;
+        if self.chunks.get(self.next) != Some(&hasher.finalize()) {
+            return Err(MerkleError::ChunkMismatch { index });
+        }
+        self.next += 1;
+        self.filled = 0;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::{HashMode, Sha3_256};
+
+    const CHUNK_SIZE: usize = 1000;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7) as u8).collect()
+    }
+
+    #[test]
+    fn should_split_file_into_chunks() {
+        for (len, chunk_count) in [(0, 1), (1, 1), (1000, 1), (1001, 2), (4500, 5)] {
+            let tree = ChunkTree::<Sha3_256>::from_reader(
+                data(len).as_slice(),
+                CHUNK_SIZE,
+                HashMode::Rfc6962,
+            )
+            .unwrap();
+            assert_eq!(tree.chunk_count(), chunk_count, "{len} bytes");
+            assert_eq!(tree.file_size(), len as u64);
+        }
+
+        // a single chunk tree stands for the hash of the file in legacy mode
+        let file = data(600);
+        let tree =
+            ChunkTree::<Sha3_256>::from_reader(file.as_slice(), CHUNK_SIZE, HashMode::Legacy)
+                .unwrap();
+        assert_eq!(tree.root(), Sha3_256::hash(&file));
+    }
+
+    #[test]
+    fn should_verify_streamed_chunks() {
+        let file = data(4500);
+        let tree =
+            ChunkTree::<Sha3_256>::from_reader(file.as_slice(), CHUNK_SIZE, HashMode::Rfc6962)
+                .unwrap();
+
+        // resumed download, streamed in pieces straddling the chunks
+        let proof = tree.range_proof(2..5).unwrap();
+        let mut verifier = ChunkVerifier::new(&proof, &tree.root(), CHUNK_SIZE).unwrap();
+        for piece in file[2000..].chunks(300) {
+            verifier.update(piece).unwrap();
+        }
+        assert_eq!(verifier.verified_len(), 2000);
+        assert_eq!(verifier.finalize(), Ok(()));
+
+        let empty =
+            ChunkTree::<Sha3_256>::from_reader(&[][..], CHUNK_SIZE, HashMode::Rfc6962).unwrap();
+        let proof = empty.range_proof(0..1).unwrap();
+        let verifier = ChunkVerifier::new(&proof, &empty.root(), CHUNK_SIZE).unwrap();
+        assert_eq!(verifier.finalize(), Ok(()));
+    }
+
+    #[test]
+    fn should_detect_altered_or_missing_chunks() {
+        let file = data(4500);
+        let tree =
+            ChunkTree::<Sha3_256>::from_reader(file.as_slice(), CHUNK_SIZE, HashMode::Rfc6962)
+                .unwrap();
+        let proof = tree.range_proof(0..5).unwrap();
+
+        let mut altered = file.clone();
+        altered[3210] ^= 1;
+        let mut verifier = ChunkVerifier::new(&proof, &tree.root(), CHUNK_SIZE).unwrap();
+        assert_eq!(
+            verifier.update(&altered),
+            Err(MerkleError::ChunkMismatch { index: 3 })
+        );
+        assert_eq!(verifier.verified_len(), 3000);
+
+        let mut verifier = ChunkVerifier::new(&proof, &tree.root(), CHUNK_SIZE).unwrap();
+        verifier.update(&file[..4000]).unwrap();
+        assert_eq!(
+            verifier.finalize(),
+            Err(MerkleError::ChunkMismatch { index: 4 })
+        );
+
+        let other = ChunkTree::<Sha3_256>::from_reader(&altered[..], CHUNK_SIZE, HashMode::Rfc6962)
+            .unwrap();
+        assert!(matches!(
+            ChunkVerifier::new(&proof, &other.root(), CHUNK_SIZE),
+            Err(MerkleError::RootMismatch)
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::error::MerkleError;
+use crate::merkle_proof::MerkleProof;
+use crate::merkle_root::MerkleRoot;
+use crate::merkle_tree::{Direction, MerkleNode};
+use crate::utils::crypto::{Digest, HashMode, MerkleHasher, Sha256};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// Represents a Merkle tree laid out as a complete binary tree, as
+/// OpenZeppelin's `merkle-tree` library builds them.
+///
+/// The nodes are stored as a heap: the root first, the children of node `i` at
+/// `2i + 1` and `2i + 2`, and the leaves at the end in reverse order. Levels
+/// are filled from the left and odd nodes are never padded, so its root
+/// differs from the one of a `MerkleTree` of the same leaves unless their
+/// number is a power of two.
+pub struct CompleteMerkleTree<H: MerkleHasher = Sha256> {
+    nodes: Vec<Digest>,
+    /// Leaves the tree was built from, before `HashMode::hash_leaf` was applied.
+    leaves: Vec<Digest>,
+    mode: HashMode,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> CompleteMerkleTree<H> {
+    /// Creates a new complete Merkle tree from a list of leaf hashes, in the
+    /// given order. OpenZeppelin's `SimpleMerkleTree.of` sorts the leaves
+    /// first unless `sortLeaves` is unset: sort them too to get its root.
+    pub fn build(leaves: Vec<Digest>, mode: HashMode) -> Self {
+        let len = (2 * leaves.len()).saturating_sub(1);
+        let mut nodes = vec![Digest::default(); len];
+        for (i, leaf) in leaves.iter().enumerate() {
+            nodes[len - 1 - i] = mode.hash_leaf::<H>(leaf);
+        }
+        for i in (0..len - leaves.len()).rev() {
+            nodes[i] = mode.hash_nodes::<H>(&nodes[2 * i + 1], &nodes[2 * i + 2]);
+        }
+        Self {
+            nodes,
+            leaves,
+            mode,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns the hashing mode of the leaves and nodes of the tree.
+    pub fn mode(&self) -> HashMode {
+        self.mode
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Generates the proof of the leaf at position `leaf_index`. It does not
+    /// record the position of the leaf, which only matches the layout of a
+    /// `MerkleTree`.
+    pub fn proof_for_index(&self, leaf_index: usize) -> Result<MerkleProof<H>, MerkleError> {
+        if self.leaves.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        if leaf_index >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let mut index = self.nodes.len() - 1 - leaf_index;
+        let mut nodes = vec![MerkleNode {
+            hash: self.leaves[leaf_index],
+            direction: Direction::Right,
+        }];
+        while index > 0 {
+            // left children have an odd index, their sibling is on their right
+            let (sibling, direction) = match index % 2 {
+                1 => (index + 1, Direction::Right),
+                _ => (index - 1, Direction::Left),
+            };
+            nodes.push(MerkleNode {
+                hash: self.nodes[sibling],
+                direction,
+            });
+            index = (index - 1) / 2;
+        }
+        Ok(MerkleProof::with_mode(nodes, self.mode))
+    }
+
+    /// Returns the root hash of the tree.
+    pub fn root(&self) -> Option<&Digest> {
+        self.nodes.first()
+    }
+
+    /// Returns the root hash of the tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, *root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::{Keccak256, Sha3_256};
+    use crate::MerkleTree;
+
+    fn digest(hex: &str) -> Digest {
+        Digest::try_from(hex::decode(hex).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn should_match_openzeppelin_roots() {
+        // `SimpleMerkleTree.of(leaves).root` of `@openzeppelin/merkle-tree`,
+        // the leaves being the sorted `keccak256` of the bytes 0 to 2, then 0 to 4
+        let leaves = [
+            "5fe7f977e71dba2ea1a68e21057beebb9be2ac30c6410aa38d4f3fbe41dcffd2",
+            "69c322e3248a5dfc29d73c5b0553b0185a35cd5bb6386747517ef7e53b15e287",
+            "bc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+            "f2ee15ea639b73fa3db9b34a245bdfa015c260c598b211bf05a1ecc4b3e3b4f2",
+            "f343681465b9efe82c933c3e8748c70cb8aa06539c361de20f72eac04e766393",
+        ];
+        let leaves: Vec<Digest> = leaves.into_iter().map(digest).collect();
+        let three = [leaves[0], leaves[2], leaves[3]].to_vec();
+
+        let tree = CompleteMerkleTree::<Keccak256>::build(three, HashMode::SortedPair);
+        assert_eq!(
+            *tree.root().unwrap(),
+            digest("d359d2743bb3a93ded4c902716931497ae3080f478c14e7af96344a92e9ddd51")
+        );
+        let tree = CompleteMerkleTree::<Keccak256>::build(leaves.clone(), HashMode::SortedPair);
+        assert_eq!(
+            *tree.root().unwrap(),
+            digest("806ebfdd294fe1b9c4cf959ffb742f7122806379a001ba00e067ea1ac90d259b")
+        );
+        // the layered layout pairs the fifth leaf higher up
+        let layered = MerkleTree::<Keccak256>::build(leaves, HashMode::SortedPair);
+        assert_ne!(layered.root(), tree.root());
+    }
+
+    #[test]
+    fn should_generate_valid_proofs_for_every_index() {
+        let leaves: Vec<Digest> = (0..9u8).map(|i| Sha3_256::hash(&[i])).collect();
+
+        for mode in [HashMode::Legacy, HashMode::Rfc6962, HashMode::SortedPair] {
+            for leaf_count in 1..=leaves.len() {
+                let tree =
+                    CompleteMerkleTree::<Sha3_256>::build(leaves[..leaf_count].to_vec(), mode);
+                let root = *tree.root().unwrap();
+                for index in 0..leaf_count {
+                    let proof = tree.proof_for_index(index).unwrap();
+                    assert_eq!(proof.compute_root(), Ok(root));
+                }
+                assert_eq!(
+                    tree.proof_for_index(leaf_count).unwrap_err(),
+                    MerkleError::IndexOutOfRange {
+                        index: leaf_count,
+                        leaf_count
+                    }
+                );
+            }
+        }
+
+        let tree = CompleteMerkleTree::<Sha3_256>::build(Vec::new(), HashMode::Legacy);
+        assert_eq!(tree.root(), None);
+        assert_eq!(tree.proof_for_index(0).unwrap_err(), MerkleError::EmptyTree);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::error::MerkleError;
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Represents an RFC 6962 consistency proof: the nodes needed to check that
+/// the append-only tree of `old_size` leaves is a prefix of the one of
+/// `new_size` leaves, i.e. that no leaf was rewritten, only appended.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsistencyProof<H: MerkleHasher = Sha256> {
+    pub old_size: usize,
+    pub new_size: usize,
+    pub nodes: Vec<Digest>,
+    pub algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> ConsistencyProof<H> {
+    pub fn new(old_size: usize, new_size: usize, nodes: Vec<Digest>) -> Self {
+        Self {
+            old_size,
+            new_size,
+            nodes,
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Checks that the proof leads from `old_root` to `new_root`, following
+    /// the verification algorithm of
+    /// [RFC 9162](https://www.rfc-editor.org/rfc/rfc9162#section-2.1.4.2).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the sizes or the number of
+    /// nodes are inconsistent, `MerkleError::AlgorithmMismatch` if it was
+    /// built with another algorithm than `H`, and `MerkleError::RootMismatch`
+    /// if it does not lead to the given roots.
+    pub fn verify(&self, old_root: &Digest, new_root: &Digest) -> Result<(), MerkleError> {
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return Err(MerkleError::MalformedProof("invalid tree sizes"));
+        }
+        if self.old_size == self.new_size {
+            if !self.nodes.is_empty() {
+                return Err(MerkleError::MalformedProof("unused nodes"));
+            }
+            return match old_root == new_root {
+                true => Ok(()),
+                false => Err(MerkleError::RootMismatch),
+            };
+        }
+
+        let mode = HashMode::Rfc6962;
+        let malformed = MerkleError::MalformedProof("nodes do not match the tree sizes");
+        // the old tree is a perfect subtree of the new one: its root is the
+        // first node of the path
+        let mut path = Vec::with_capacity(self.nodes.len() + 1);
+        if self.old_size.is_power_of_two() {
+            path.push(*old_root);
+        }
+        path.extend_from_slice(&self.nodes);
+        let (first, rest) = path.split_first().ok_or(malformed.clone())?;
+
+        let mut old_node = self.old_size - 1;
+        let mut new_node = self.new_size - 1;
+        while old_node % 2 == 1 {
+            old_node /= 2;
+            new_node /= 2;
+        }
+        let (mut old_hash, mut new_hash) = (*first, *first);
+        for node in rest {
+            if new_node == 0 {
+                return Err(malformed);
+            }
+            if old_node % 2 == 1 || old_node == new_node {
+                old_hash = mode.hash_nodes::<H>(node, &old_hash);
+                new_hash = mode.hash_nodes::<H>(node, &new_hash);
+                while old_node.is_multiple_of(2) && old_node != 0 {
+                    old_node /= 2;
+                    new_node /= 2;
+                }
+            } else {
+                new_hash = mode.hash_nodes::<H>(&new_hash, node);
+            }
+            old_node /= 2;
+            new_node /= 2;
+        }
+
+        if new_node != 0 {
+            return Err(malformed);
+        }
+        match old_hash == *old_root && new_hash == *new_root {
+            true => Ok(()),
+            false => Err(MerkleError::RootMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::append_only_tree::AppendOnlyTree;
+    use crate::utils::crypto::Sha3_256;
+
+    #[test]
+    fn should_verify_deserialized_proof() {
+        let leaves: Vec<Digest> = (0..11u8).map(|i| Sha3_256::hash(&[i])).collect();
+        let old_tree = AppendOnlyTree::<Sha3_256>::from_leaves(&leaves[..6]);
+        let new_tree = AppendOnlyTree::<Sha3_256>::from_leaves(&leaves);
+        let (old_root, new_root) = (old_tree.root().unwrap(), new_tree.root().unwrap());
+
+        let json = serde_json::to_string(&new_tree.consistency_proof(6).unwrap()).unwrap();
+        let proof: ConsistencyProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(proof.verify(&old_root, &new_root), Ok(()));
+        assert_eq!(
+            proof.verify(&new_root, &old_root),
+            Err(MerkleError::RootMismatch)
+        );
+
+        let proof: ConsistencyProof<Sha256> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            proof.verify(&old_root, &new_root),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn should_reject_truncated_proof() {
+        let leaves: Vec<Digest> = (0..11u8).map(|i| Sha256::hash(&[i])).collect();
+        let tree = AppendOnlyTree::<Sha256>::from_leaves(&leaves);
+        let old_root = tree.root_at(3).unwrap();
+
+        let mut proof = tree.consistency_proof(3).unwrap();
+        proof.nodes.pop();
+        assert!(matches!(
+            proof.verify(&old_root, &tree.root().unwrap()),
+            Err(MerkleError::MalformedProof(_))
+        ));
+    }
+}
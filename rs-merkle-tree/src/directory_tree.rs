@@ -0,0 +1,427 @@
+use crate::error::MerkleError;
+use crate::merkle_proof::MerkleProof;
+use crate::merkle_root::MerkleRoot;
+use crate::merkle_tree::MerkleTree;
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Digest, MerkleHasher, Sha256};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the leaf of a file entry of a directory.
+const FILE_ENTRY_PREFIX: u8 = 0x00;
+/// Prefix of the leaf of a subdirectory entry of a directory.
+const DIRECTORY_ENTRY_PREFIX: u8 = 0x01;
+
+/// Kind of an entry of a directory.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+/// Returns the leaf of the entry `name` of a directory, which binds the kind
+/// and the name of the entry to `hash`: the hash of a file, or the root of a
+/// subdirectory.
+fn entry_leaf<H: MerkleHasher>(kind: EntryKind, name: &str, hash: &Digest) -> Digest {
+    let prefix = match kind {
+        EntryKind::File => FILE_ENTRY_PREFIX,
+        EntryKind::Directory => DIRECTORY_ENTRY_PREFIX,
+    };
+    H::hash_parts(&[
+        &[prefix],
+        &(name.len() as u64).to_le_bytes(),
+        name.as_bytes(),
+        &hash.0,
+    ])
+}
+
+/// Splits a `/` separated path into its components, the empty path being the
+/// root directory.
+///
+/// # Errors
+///
+/// Returns `MerkleError::InvalidPath` if a component is empty, `.` or `..`.
+fn components(path: &str) -> Result<Vec<&str>, MerkleError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    path.split('/')
+        .map(|component| match component {
+            "" | "." | ".." => Err(MerkleError::InvalidPath(path.to_string())),
+            component => Ok(component),
+        })
+        .collect()
+}
+
+/// Splits a path which is not the root directory into the path of its
+/// directory and its name.
+fn split(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+/// Returns the path of the entry `name` of the directory at `path`.
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path => format!("{path}/{name}"),
+    }
+}
+
+/// A directory of a `DirectoryTree`: its entries sorted by name, and the
+/// Merkle tree of their leaves.
+struct Directory<H: MerkleHasher> {
+    entries: Vec<(String, EntryKind)>,
+    tree: MerkleTree<H>,
+}
+
+/// Represents a tree of trees mirroring a folder structure, as git trees do:
+/// each directory has its own Merkle tree, whose leaves are its entries.
+///
+/// The leaf of an entry binds its name and its kind to the hash of a file or
+/// to the root of a subdirectory, so the root of a directory stands for its
+/// whole content and does not depend on where it is. Entries are sorted by
+/// name, so the root does not depend on the order files are given in either.
+pub struct DirectoryTree<H: MerkleHasher = Sha256> {
+    /// Directories by path, the root directory being the empty path.
+    directories: BTreeMap<String, Directory<H>>,
+    params: TreeParams,
+}
+
+impl<H: MerkleHasher> DirectoryTree<H> {
+    /// Creates a directory tree from files given by `/` separated path, e.g.
+    /// `src/main.rs`, and hash. The tree of each directory is built according
+    /// to `params`, or to a `HashMode`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::InvalidPath` if a path is empty, has an empty,
+    /// `.` or `..` component, is given twice, or is both a file and a
+    /// directory.
+    pub fn build(
+        files: impl IntoIterator<Item = (String, Digest)>,
+        params: impl Into<TreeParams>,
+    ) -> Result<Self, MerkleError> {
+        let params = params.into();
+        // entries of each directory, with the hash of files
+        let mut listing: BTreeMap<String, BTreeMap<String, Option<Digest>>> = BTreeMap::new();
+        listing.insert(String::new(), BTreeMap::new());
+        for (path, hash) in files {
+            let invalid = || MerkleError::InvalidPath(path.clone());
+            if components(&path)?.is_empty() {
+                return Err(invalid());
+            }
+            // registers the file in its directory, then each directory in its
+            // parent until one is already registered
+            let (mut entry, mut hash) = (path.as_str(), Some(hash));
+            loop {
+                let (directory, name) = split(entry);
+                let entries = listing.entry(directory.to_string()).or_default();
+                match (entries.get(name), hash) {
+                    (None, _) => {
+                        entries.insert(name.to_string(), hash);
+                    }
+                    (Some(None), None) => break,
+                    _ => return Err(invalid()),
+                }
+                if directory.is_empty() {
+                    break;
+                }
+                (entry, hash) = (directory, None);
+            }
+        }
+
+        // subdirectories are hashed before their parent
+        let mut paths: Vec<String> = listing.keys().cloned().collect();
+        paths.sort_by_key(|path| Reverse(components(path).map_or(0, |names| names.len())));
+        let mut directories: BTreeMap<String, Directory<H>> = BTreeMap::new();
+        for path in paths {
+            let mut entries = Vec::new();
+            let mut leaves = Vec::new();
+            for (name, hash) in &listing[&path] {
+                let (kind, hash) = match hash {
+                    Some(hash) => (EntryKind::File, *hash),
+                    None => (
+                        EntryKind::Directory,
+                        *directories[&join(&path, name)]
+                            .tree
+                            .root()
+                            .expect("directories hold at least one entry"),
+                    ),
+                };
+                leaves.push(entry_leaf::<H>(kind, name, &hash));
+                entries.push((name.clone(), kind));
+            }
+            let tree = MerkleTree::build(leaves, params);
+            directories.insert(path, Directory { entries, tree });
+        }
+        Ok(Self {
+            directories,
+            params,
+        })
+    }
+
+    /// Returns the parameters the tree of each directory was built with.
+    pub fn params(&self) -> TreeParams {
+        self.params
+    }
+
+    /// Returns the root hash of the root directory, if it is not empty.
+    pub fn root(&self) -> Option<&Digest> {
+        self.directory_root("")
+    }
+
+    /// Returns the root hash of the root directory, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, *root))
+    }
+
+    /// Returns the root hash of the directory at `path`, if there is one.
+    pub fn directory_root(&self, path: &str) -> Option<&Digest> {
+        self.directories.get(path)?.tree.root()
+    }
+
+    /// Generates the proof of the file or the directory at `path`, from its
+    /// entry in its directory up to the root directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::InvalidPath` if `path` is not a valid path and
+    /// `MerkleError::LeafNotFound` if there is no file nor directory at `path`.
+    pub fn proof(&self, path: &str) -> Result<DirectoryProof<H>, MerkleError> {
+        components(path)?;
+        let kind = match self.directories.contains_key(path) {
+            true => EntryKind::Directory,
+            false => EntryKind::File,
+        };
+
+        let mut steps = Vec::new();
+        let mut entry = path;
+        while !entry.is_empty() {
+            let (path, name) = split(entry);
+            let directory = self
+                .directories
+                .get(path)
+                .ok_or(MerkleError::LeafNotFound)?;
+            let index = directory
+                .entries
+                .binary_search_by(|(entry, _)| entry.as_str().cmp(name))
+                .map_err(|_| MerkleError::LeafNotFound)?;
+            steps.push(directory.tree.proof_for_index(index)?);
+            entry = path;
+        }
+        Ok(DirectoryProof {
+            path: path.to_string(),
+            kind,
+            steps,
+        })
+    }
+}
+
+/// Represents the proof of a file or a directory of a `DirectoryTree`: the
+/// proof of its entry in its directory, then of the entry of each directory in
+/// its parent, up to the root directory.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct DirectoryProof<H: MerkleHasher = Sha256> {
+    pub path: String,
+    pub kind: EntryKind,
+    /// One proof per component of the path, from the last one to the first.
+    pub steps: Vec<MerkleProof<H>>,
+}
+
+impl<H: MerkleHasher> DirectoryProof<H> {
+    /// Computes the root hash the proof leads to from `hash`, the hash of the
+    /// proven file or the root hash of the proven directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the steps do not match the
+    /// path, or the error of `MerkleProof::compute_root` if a step fails.
+    pub fn compute_root(&self, hash: &Digest) -> Result<Digest, MerkleError> {
+        let names =
+            components(&self.path).map_err(|_| MerkleError::MalformedProof("invalid path"))?;
+        if names.len() != self.steps.len() {
+            return Err(MerkleError::MalformedProof("steps do not match the path"));
+        }
+        if names.is_empty() && self.kind == EntryKind::File {
+            return Err(MerkleError::MalformedProof("the root is not a file"));
+        }
+
+        let (mut hash, mut kind) = (*hash, self.kind);
+        for (name, step) in names.iter().rev().zip(&self.steps) {
+            let leaf = entry_leaf::<H>(kind, name, &hash);
+            if step.nodes.first().map(|node| &node.hash) != Some(&leaf) {
+                return Err(MerkleError::MalformedProof(
+                    "step does not prove the entry below",
+                ));
+            }
+            hash = step.compute_root()?;
+            kind = EntryKind::Directory;
+        }
+        Ok(hash)
+    }
+
+    /// Computes the root hash the proof leads to like `compute_root`,
+    /// refusing to do so if a step was not built with the `expected` tree
+    /// parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ParamsMismatch` if the parameters differ, or the
+    /// error of `compute_root` if it fails.
+    pub fn compute_root_with_params(
+        &self,
+        hash: &Digest,
+        expected: TreeParams,
+    ) -> Result<Digest, MerkleError> {
+        if let Some(step) = self.steps.iter().find(|step| step.params() != expected) {
+            return Err(MerkleError::ParamsMismatch {
+                expected,
+                actual: step.params(),
+            });
+        }
+        self.compute_root(hash)
+    }
+
+    /// Checks that the proof leads from `hash` to `root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `compute_root` if it fails, and
+    /// `MerkleError::RootMismatch` if the proof does not lead to `root`.
+    pub fn verify(&self, hash: &Digest, root: &Digest) -> Result<(), MerkleError> {
+        match self.compute_root(hash)? == *root {
+            true => Ok(()),
+            false => Err(MerkleError::RootMismatch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::{HashMode, Sha3_256};
+
+    fn files(paths: &[&str]) -> Vec<(String, Digest)> {
+        paths
+            .iter()
+            .map(|path| (path.to_string(), Sha3_256::hash(path.as_bytes())))
+            .collect()
+    }
+
+    const PROJECT: [&str; 6] = [
+        "README.md",
+        "src/lib.rs",
+        "src/utils/crypto.rs",
+        "src/utils/mod.rs",
+        "tests/e2e.sh",
+        "Cargo.toml",
+    ];
+
+    #[test]
+    fn should_prove_files_and_directories() {
+        let tree = DirectoryTree::<Sha3_256>::build(files(&PROJECT), HashMode::Rfc6962).unwrap();
+        let root = *tree.root().unwrap();
+
+        for (path, hash) in files(&PROJECT) {
+            let proof = tree.proof(&path).unwrap();
+            assert_eq!(proof.kind, EntryKind::File);
+            assert_eq!(proof.steps.len(), path.split('/').count());
+            assert_eq!(proof.verify(&hash, &root), Ok(()), "{path}");
+            assert!(proof.verify(&Sha3_256::hash(b"altered"), &root).is_err());
+        }
+        for path in ["src", "src/utils", "tests", ""] {
+            let proof = tree.proof(path).unwrap();
+            let subroot = tree.directory_root(path).unwrap();
+            assert_eq!(proof.kind, EntryKind::Directory);
+            assert_eq!(
+                proof.compute_root_with_params(subroot, HashMode::Rfc6962.into()),
+                Ok(root),
+                "{path}"
+            );
+        }
+
+        // a file cannot be passed off as a directory, nor moved
+        let mut proof = tree.proof("src/lib.rs").unwrap();
+        proof.kind = EntryKind::Directory;
+        assert!(proof.verify(&Sha3_256::hash(b"src/lib.rs"), &root).is_err());
+        let mut proof = tree.proof("src/lib.rs").unwrap();
+        proof.path = "tests/lib.rs".to_string();
+        assert!(proof.verify(&Sha3_256::hash(b"src/lib.rs"), &root).is_err());
+
+        assert_eq!(
+            tree.proof("src/main.rs").unwrap_err(),
+            MerkleError::LeafNotFound
+        );
+        assert_eq!(
+            tree.proof("docs/index.md").unwrap_err(),
+            MerkleError::LeafNotFound
+        );
+    }
+
+    #[test]
+    fn should_root_directories_independently_of_their_location() {
+        let tree = DirectoryTree::<Sha3_256>::build(files(&PROJECT), HashMode::Rfc6962).unwrap();
+        let utils: Vec<(String, Digest)> = files(&PROJECT)
+            .into_iter()
+            .filter_map(|(path, hash)| Some((path.strip_prefix("src/utils/")?.to_string(), hash)))
+            .collect();
+        let subtree = DirectoryTree::<Sha3_256>::build(utils, HashMode::Rfc6962).unwrap();
+        assert_eq!(subtree.root(), tree.directory_root("src/utils"));
+
+        let mut reversed = files(&PROJECT);
+        reversed.reverse();
+        let same = DirectoryTree::<Sha3_256>::build(reversed, HashMode::Rfc6962).unwrap();
+        assert_eq!(same.root(), tree.root());
+
+        let renamed: Vec<(String, Digest)> = files(&PROJECT)
+            .into_iter()
+            .map(|(path, hash)| (path.replace("lib.rs", "main.rs"), hash))
+            .collect();
+        let renamed = DirectoryTree::<Sha3_256>::build(renamed, HashMode::Rfc6962).unwrap();
+        assert_ne!(renamed.root(), tree.root());
+        assert_eq!(
+            renamed.directory_root("src/utils"),
+            tree.directory_root("src/utils")
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_paths() {
+        for paths in [
+            vec![""],
+            vec!["/etc/passwd"],
+            vec!["src//lib.rs"],
+            vec!["src/../lib.rs"],
+            vec!["src/lib.rs", "src/lib.rs"],
+            vec!["src", "src/lib.rs"],
+            vec!["src/lib.rs", "src"],
+        ] {
+            assert!(
+                matches!(
+                    DirectoryTree::<Sha3_256>::build(files(&paths), HashMode::Rfc6962),
+                    Err(MerkleError::InvalidPath(_))
+                ),
+                "{paths:?}"
+            );
+        }
+        let empty = DirectoryTree::<Sha3_256>::build(Vec::new(), HashMode::Rfc6962).unwrap();
+        assert_eq!(empty.root(), None);
+    }
+
+    #[test]
+    fn should_verify_deserialized_proof() {
+        let tree = DirectoryTree::<Sha3_256>::build(files(&PROJECT), HashMode::Rfc6962).unwrap();
+        let json = serde_json::to_string(&tree.proof("src/utils/mod.rs").unwrap()).unwrap();
+
+        let proof: DirectoryProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            proof.verify(&Sha3_256::hash(b"src/utils/mod.rs"), tree.root().unwrap()),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Algorithm, HashMode};
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+
+/// Errors returned by the library.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MerkleError {
+    /// The tree has no leaves.
+    EmptyTree,
+    /// No leaf of the tree matches the requested hash.
+    LeafNotFound,
+    /// The requested leaf index is not lower than the number of leaves.
+    IndexOutOfRange { index: usize, leaf_count: usize },
+    /// The proof is inconsistent, e.g. empty or not matching its recorded position.
+    MalformedProof(&'static str),
+    /// A hash does not have the output size of the algorithm in use.
+    HashLengthMismatch { expected: usize, actual: usize },
+    /// The proof or root was computed with another algorithm than the expected one.
+    AlgorithmMismatch {
+        expected: Algorithm,
+        actual: Algorithm,
+    },
+    /// The proof was computed with another hashing mode than the expected one.
+    ModeMismatch {
+        expected: HashMode,
+        actual: HashMode,
+    },
+    /// The algorithm identifier is not known.
+    UnknownAlgorithm(String),
+    /// The hashing mode identifier is not known.
+    UnknownMode(String),
+    /// The string is not a valid hexadecimal encoding.
+    InvalidHex(String),
+    /// The proof does not lead to the expected root hashes.
+    RootMismatch,
+    /// The bytes are not a valid binary dump of a tree.
+    InvalidDump(&'static str),
+    /// A chunk of a file does not match the hash proven for it.
+    ChunkMismatch { index: usize },
+    /// The odd node strategy identifier is not known.
+    UnknownOddNodeStrategy(String),
+    /// The proof was computed with other tree parameters than the expected ones.
+    ParamsMismatch {
+        expected: TreeParams,
+        actual: TreeParams,
+    },
+    /// The trees compared do not have the same number of leaves.
+    LeafCountMismatch { expected: usize, actual: usize },
+    /// The path is not a valid relative path, or conflicts with another one.
+    InvalidPath(String),
+    /// The signature does not match the signed data and public key.
+    InvalidSignature,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::EmptyTree => write!(f, "Tree is empty"),
+            MerkleError::LeafNotFound => write!(f, "Leaf not found"),
+            MerkleError::IndexOutOfRange { index, leaf_count } => {
+                write!(f, "Leaf index {index} out of range for {leaf_count} leaves")
+            }
+            MerkleError::MalformedProof(reason) => write!(f, "Malformed proof: {reason}"),
+            MerkleError::HashLengthMismatch { expected, actual } => {
+                write!(f, "Expected a {expected} bytes hash, got {actual} bytes")
+            }
+            MerkleError::AlgorithmMismatch { expected, actual } => {
+                write!(f, "Expected `{expected}` hashes, got `{actual}`")
+            }
+            MerkleError::ModeMismatch { expected, actual } => {
+                write!(f, "Expected `{expected}` hashing, got `{actual}`")
+            }
+            MerkleError::UnknownAlgorithm(algorithm) => {
+                write!(f, "Unknown hash algorithm `{algorithm}`")
+            }
+            MerkleError::UnknownMode(mode) => write!(f, "Unknown hash mode `{mode}`"),
+            MerkleError::InvalidHex(reason) => write!(f, "Invalid hexadecimal string: {reason}"),
+            MerkleError::RootMismatch => write!(f, "Proof does not match the root hashes"),
+            MerkleError::InvalidDump(reason) => write!(f, "Invalid tree dump: {reason}"),
+            MerkleError::ChunkMismatch { index } => {
+                write!(f, "Chunk {index} does not match its proven hash")
+            }
+            MerkleError::UnknownOddNodeStrategy(strategy) => {
+                write!(f, "Unknown odd node strategy `{strategy}`")
+            }
+            MerkleError::ParamsMismatch { expected, actual } => {
+                write!(f, "Expected a tree with {expected}, got {actual}")
+            }
+            MerkleError::LeafCountMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "Expected a tree of {expected} leaves, got {actual} leaves"
+                )
+            }
+            MerkleError::InvalidPath(path) => write!(f, "Invalid path `{path}`"),
+            MerkleError::InvalidSignature => write!(f, "Signature does not match the tree head"),
+        }
+    }
+}
+
+impl Error for MerkleError {}
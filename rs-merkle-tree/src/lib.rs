@@ -1,6 +1,47 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub use append_only_tree::{AppendOnlyFrontier, AppendOnlyTree};
+pub use chunk_tree::{ChunkTree, ChunkVerifier, DEFAULT_CHUNK_SIZE};
+pub use complete_tree::CompleteMerkleTree;
+pub use consistency_proof::ConsistencyProof;
+pub use directory_tree::{DirectoryProof, DirectoryTree, EntryKind};
+pub use error::MerkleError;
+pub use merkle_compact_proof::CompactProof;
+pub use merkle_mountain_range::{MerkleMountainRange, MmrPeaks, MmrProof};
+pub use merkle_multi_proof::MerkleMultiProof;
 pub use merkle_proof::MerkleProof;
+pub use merkle_range_proof::RangeProof;
+pub use merkle_root::MerkleRoot;
 pub use merkle_tree::{MerkleNode, MerkleTree};
+pub use merkle_tree_render::RenderOptions;
+pub use merkle_tree_view::MerkleTreeView;
+pub use merkle_update_proof::{LeafUpdate, UpdateProof};
+#[cfg(feature = "signing")]
+pub use signed_tree_head::{Signature, SignedTreeHead, SigningKey, VerifyingKey};
+pub use sparse_merkle_tree::{SparseMerkleProof, SparseMerkleTree};
+pub use tree_params::{OddNodeStrategy, TreeParams};
 
+mod append_only_tree;
+mod chunk_tree;
+mod complete_tree;
+mod consistency_proof;
+mod directory_tree;
+mod error;
+mod merkle_compact_proof;
+mod merkle_mountain_range;
+mod merkle_multi_proof;
 mod merkle_proof;
+mod merkle_range_proof;
+mod merkle_root;
 mod merkle_tree;
+mod merkle_tree_dump;
+mod merkle_tree_render;
+mod merkle_tree_view;
+mod merkle_update_proof;
+#[cfg(feature = "signing")]
+mod signed_tree_head;
+mod sparse_merkle_tree;
+mod tree_params;
 pub mod utils;
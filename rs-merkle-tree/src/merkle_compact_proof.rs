@@ -0,0 +1,287 @@
+//! Compact binary encoding of `MerkleProof`.
+//!
+//! All integers are little-endian:
+//!
+//! | Size   | Content                                                       |
+//! | ------ | ------------------------------------------------------------- |
+//! | 1      | encoding version, currently 1                                 |
+//! | 1      | algorithm: 0 SHA-256, 1 SHA-512/256, 2 SHA3-256,              |
+//! |        | 3 Keccak-256                                                  |
+//! | 1      | hashing mode: 0 legacy, 1 RFC 6962, 2 sorted pair             |
+//! | 1      | flags: bit 0 set if the leaf position follows, bits 1-2 odd   |
+//! |        | node strategy, bit 3 set if the root commits to the leaf count |
+//! | 8 + 8  | leaf index and leaf count, only if flagged                    |
+//! | 4      | number of nodes `n`                                           |
+//! | 32 * n | node hashes, the proven leaf first                            |
+//! | n / 8  | direction bitmap, rounded up: bit `i` set if node `i` is left |
+
+use crate::error::MerkleError;
+use crate::merkle_proof::MerkleProof;
+use crate::merkle_tree::{Direction, MerkleNode};
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, DIGEST_SIZE};
+use alloc::vec;
+use alloc::vec::Vec;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use core::fmt;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const VERSION: u8 = 1;
+const HAS_POSITION: u8 = 0b1;
+
+/// Represents a `MerkleProof` in its compact binary encoding, about three
+/// times smaller than its JSON form.
+///
+/// It is serialized as a base64 string in human-readable formats such as
+/// JSON, and as raw bytes otherwise.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompactProof(Vec<u8>);
+
+impl CompactProof {
+    /// Encodes `proof`.
+    pub fn encode<H: MerkleHasher>(proof: &MerkleProof<H>) -> Self {
+        let count = proof.nodes.len();
+        let mut bytes = Vec::with_capacity(4 + 16 + 4 + count * DIGEST_SIZE + count.div_ceil(8));
+        bytes.push(VERSION);
+        bytes.push(proof.algorithm.id());
+        bytes.push(proof.mode.id());
+        let params = proof.params().flags() << 1;
+        match (proof.leaf_index, proof.leaf_count) {
+            (Some(leaf_index), Some(leaf_count)) => {
+                bytes.push(params | HAS_POSITION);
+                bytes.extend_from_slice(&(leaf_index as u64).to_le_bytes());
+                bytes.extend_from_slice(&(leaf_count as u64).to_le_bytes());
+            }
+            _ => bytes.push(params),
+        }
+        bytes.extend_from_slice(&(count as u32).to_le_bytes());
+        for node in &proof.nodes {
+            bytes.extend_from_slice(node.hash.as_bytes());
+        }
+        let mut bitmap = vec![0u8; count.div_ceil(8)];
+        for (i, node) in proof.nodes.iter().enumerate() {
+            if node.direction == Direction::Left {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes.extend_from_slice(&bitmap);
+        Self(bytes)
+    }
+
+    /// Decodes the proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the bytes are not a valid
+    /// encoding. The algorithm of the proof is checked by
+    /// `MerkleProof::compute_root`, not here.
+    pub fn decode<H: MerkleHasher>(&self) -> Result<MerkleProof<H>, MerkleError> {
+        let mut reader = Reader(&self.0);
+        if reader.take(1)?[0] != VERSION {
+            return Err(MerkleError::MalformedProof(
+                "unsupported compact proof version",
+            ));
+        }
+        let algorithm = Algorithm::from_id(reader.take(1)?[0])
+            .ok_or(MerkleError::MalformedProof("unknown algorithm"))?;
+        let mode = HashMode::from_id(reader.take(1)?[0])
+            .ok_or(MerkleError::MalformedProof("unknown hashing mode"))?;
+        let flags = reader.take(1)?[0];
+        let params = TreeParams::from_flags(mode, flags >> 1)
+            .ok_or(MerkleError::MalformedProof("unknown flags"))?;
+        let position = match flags & HAS_POSITION {
+            0 => None,
+            _ => Some((reader.usize()?, reader.usize()?)),
+        };
+        let count = u32::from_le_bytes(reader.take(4)?.try_into().expect("4 bytes")) as usize;
+        let hashes = reader.take(count.checked_mul(DIGEST_SIZE).ok_or(TRUNCATED)?)?;
+        let bitmap = reader.take(count.div_ceil(8))?;
+        if !reader.0.is_empty() {
+            return Err(MerkleError::MalformedProof("trailing bytes"));
+        }
+
+        let nodes = hashes
+            .chunks_exact(DIGEST_SIZE)
+            .enumerate()
+            .map(|(i, hash)| MerkleNode {
+                hash: Digest::try_from(hash).expect("32 bytes chunk"),
+                direction: match bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    true => Direction::Left,
+                    false => Direction::Right,
+                },
+            })
+            .collect();
+        let mut proof = match position {
+            Some((leaf_index, leaf_count)) => {
+                MerkleProof::with_position(nodes, params, leaf_index, leaf_count)
+            }
+            None => MerkleProof::with_mode(nodes, params),
+        };
+        proof.algorithm = algorithm;
+        Ok(proof)
+    }
+
+    /// Returns the encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for CompactProof {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+const TRUNCATED: MerkleError = MerkleError::MalformedProof("truncated compact proof");
+
+/// Reads the encoding from the front.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MerkleError> {
+        if self.0.len() < len {
+            return Err(TRUNCATED);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn usize(&mut self) -> Result<usize, MerkleError> {
+        let value = u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes"));
+        usize::try_from(value).or(Err(MerkleError::MalformedProof("leaf position overflow")))
+    }
+}
+
+impl Serialize for CompactProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.serialize_str(&BASE64.encode(&self.0)),
+            false => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+struct CompactProofVisitor;
+
+impl<'de> Visitor<'de> for CompactProofVisitor {
+    type Value = CompactProof;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a base64 encoded compact proof")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<CompactProof, E> {
+        BASE64.decode(v).map(CompactProof).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<CompactProof, E> {
+        Ok(CompactProof(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<CompactProof, E> {
+        Ok(CompactProof(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for CompactProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_str(CompactProofVisitor),
+            false => deserializer.deserialize_byte_buf(CompactProofVisitor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::{Keccak256, Sha256, Sha3_256};
+
+    fn tree(leaf_count: usize) -> MerkleTree<Sha3_256> {
+        let leaves: Vec<Digest> = (0..leaf_count)
+            .map(|i| Sha3_256::hash(&i.to_le_bytes()))
+            .collect();
+        MerkleTree::build(leaves, HashMode::Rfc6962)
+    }
+
+    #[test]
+    fn should_round_trip_compact_proof() {
+        let tree = tree(1000);
+        let proof = tree.proof_for_index(617).unwrap();
+        let compact = proof.to_compact();
+
+        let decoded = compact.decode::<Sha3_256>().unwrap();
+        assert_eq!(decoded.nodes, proof.nodes);
+        assert_eq!(decoded.leaf_index, Some(617));
+        assert_eq!(decoded.compute_root().unwrap(), *tree.root().unwrap());
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert!(compact.as_bytes().len() * 3 < json.len());
+
+        let positionless = MerkleProof::<Sha3_256>::with_mode(proof.nodes, HashMode::Rfc6962);
+        let decoded = positionless.to_compact().decode::<Sha3_256>().unwrap();
+        assert_eq!(decoded.leaf_index, None);
+        assert_eq!(decoded.compute_root().unwrap(), *tree.root().unwrap());
+    }
+
+    #[test]
+    fn should_write_algorithm_and_mode_ids() {
+        let leaves = vec![Keccak256::hash(b"a"), Keccak256::hash(b"b")];
+        let tree = MerkleTree::<Keccak256>::build(leaves, HashMode::SortedPair);
+        let compact = tree.proof_for_index(1).unwrap().to_compact();
+        assert_eq!(compact.as_bytes()[1..3], [3, 2]);
+
+        let decoded = compact.decode::<Keccak256>().unwrap();
+        assert_eq!(decoded.compute_root().unwrap(), *tree.root().unwrap());
+    }
+
+    #[test]
+    fn should_round_trip_through_serde() {
+        let compact = tree(5).proof_for_index(4).unwrap().to_compact();
+
+        let json = serde_json::to_string(&compact).unwrap();
+        assert_eq!(json, format!("\"{}\"", BASE64.encode(compact.as_bytes())));
+        assert_eq!(
+            serde_json::from_str::<CompactProof>(&json).unwrap(),
+            compact
+        );
+
+        let bytes = bincode::serialize(&compact).unwrap();
+        assert_eq!(bytes.len(), 8 + compact.as_bytes().len());
+        assert_eq!(
+            bincode::deserialize::<CompactProof>(&bytes).unwrap(),
+            compact
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_encoding() {
+        let compact = tree(5).proof_for_index(2).unwrap().to_compact();
+        let bytes = compact.as_bytes();
+
+        let truncated = CompactProof::from(bytes[..bytes.len() - 1].to_vec());
+        assert_eq!(truncated.decode::<Sha3_256>().unwrap_err(), TRUNCATED);
+
+        let mut newer = bytes.to_vec();
+        newer[0] = 2;
+        assert!(matches!(
+            CompactProof::from(newer).decode::<Sha3_256>(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        let mut trailing = bytes.to_vec();
+        trailing.push(0);
+        assert!(CompactProof::from(trailing).decode::<Sha3_256>().is_err());
+
+        let decoded = compact.decode::<Sha256>().unwrap();
+        assert!(matches!(
+            decoded.compute_root(),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+}
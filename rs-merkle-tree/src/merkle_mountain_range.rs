@@ -0,0 +1,377 @@
+use crate::append_only_tree::{bag_peaks, AppendOnlyFrontier, AppendOnlyTree, MODE};
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use crate::utils::crypto::{Algorithm, Digest, MerkleHasher, Sha256};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Returns the height of the mountain holding the leaf at `leaf_index` among
+/// `leaf_count` leaves, its position among the peaks and its first leaf.
+///
+/// Mountains are the perfect subtrees matching the set bits of `leaf_count`,
+/// from the highest to the lowest.
+fn locate_mountain(leaf_index: usize, leaf_count: usize) -> (usize, usize, usize) {
+    let mut start = 0;
+    let mut position = 0;
+    for height in (0..usize::BITS as usize).rev() {
+        if leaf_count & (1 << height) == 0 {
+            continue;
+        }
+        if leaf_index < start + (1 << height) {
+            return (height, position, start);
+        }
+        start += 1 << height;
+        position += 1;
+    }
+    unreachable!("leaf index lower than the leaf count")
+}
+
+/// Represents a Merkle Mountain Range: an append-only list of perfect Merkle
+/// trees, the mountains, whose roots are the peaks.
+///
+/// Appending a leaf merges the mountains of equal height it completes, so
+/// there is one mountain per set bit of the number of leaves. The peaks are
+/// bagged into a single root, and any leaf is proven by the path to its peak
+/// and the other peaks, in O(log n).
+///
+/// The mountains are the perfect subtrees an `AppendOnlyTree` keeps, and both
+/// have the same root.
+pub struct MerkleMountainRange<H: MerkleHasher = Sha256> {
+    tree: AppendOnlyTree<H>,
+}
+
+impl<H: MerkleHasher> Default for MerkleMountainRange<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> MerkleMountainRange<H> {
+    /// Creates an empty Merkle Mountain Range.
+    pub fn new() -> Self {
+        Self {
+            tree: AppendOnlyTree::new(),
+        }
+    }
+
+    /// Returns the hash algorithm of the Merkle Mountain Range.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Returns the number of leaves.
+    pub fn leaf_count(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Appends a leaf, returning its index.
+    pub fn push(&mut self, leaf: &Digest) -> usize {
+        self.tree.push(leaf);
+        self.leaf_count() - 1
+    }
+
+    /// Returns the peaks, from the highest mountain to the lowest.
+    pub fn peaks(&self) -> Vec<Digest> {
+        self.tree.peaks()
+    }
+
+    /// Returns the peaks with the number of leaves, which is all that is
+    /// needed to keep appending leaves and checking proofs.
+    pub fn to_peaks(&self) -> MmrPeaks<H> {
+        MmrPeaks(self.tree.frontier())
+    }
+
+    /// Returns the root hash, bagging the peaks.
+    pub fn root(&self) -> Option<Digest> {
+        self.tree.root()
+    }
+
+    /// Returns the root hash, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.tree.tagged_root()
+    }
+
+    /// Generates a proof that the leaf at `leaf_index` was appended at this
+    /// position.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::IndexOutOfRange` if `leaf_index` is not lower
+    /// than the number of leaves.
+    pub fn proof(&self, leaf_index: usize) -> Result<MmrProof<H>, MerkleError> {
+        if leaf_index >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let (height, position, _) = locate_mountain(leaf_index, self.leaf_count());
+        let siblings = (0..height)
+            .map(|level| self.tree.node(level, (leaf_index >> level) ^ 1))
+            .collect();
+        let mut peaks = self.peaks();
+        peaks.remove(position);
+        Ok(MmrProof::new(
+            leaf_index,
+            self.leaf_count(),
+            self.tree.node(0, leaf_index),
+            siblings,
+            peaks,
+        ))
+    }
+}
+
+/// Represents the persistent state of a Merkle Mountain Range: its peaks and
+/// number of leaves, without the other nodes, i.e. the frontier of its
+/// append-only tree.
+///
+/// It can keep appending leaves and check proofs generated by the full
+/// `MerkleMountainRange`, but cannot generate proofs itself.
+///
+/// There is always one peak per set bit of the number of leaves: it is checked
+/// when creating or deserializing peaks.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(transparent, bound = "")]
+pub struct MmrPeaks<H: MerkleHasher = Sha256>(AppendOnlyFrontier<H>);
+
+impl<H: MerkleHasher> MmrPeaks<H> {
+    /// Creates the peaks of a Merkle Mountain Range of `leaf_count` leaves.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if there is not one peak per set
+    /// bit of `leaf_count`.
+    pub fn new(leaf_count: usize, peaks: Vec<Digest>) -> Result<Self, MerkleError> {
+        AppendOnlyFrontier::new(leaf_count, peaks).map(Self)
+    }
+
+    /// Returns the number of leaves.
+    pub fn leaf_count(&self) -> usize {
+        self.0.size()
+    }
+
+    /// Returns the peaks, from the highest mountain to the lowest.
+    pub fn peaks(&self) -> &[Digest] {
+        self.0.peaks()
+    }
+
+    /// Returns the hash algorithm the peaks were computed with.
+    pub fn algorithm(&self) -> Algorithm {
+        self.0.algorithm()
+    }
+
+    /// Appends a leaf, returning its index.
+    pub fn push(&mut self, leaf: &Digest) -> usize {
+        self.0.push(leaf);
+        self.leaf_count() - 1
+    }
+
+    /// Returns the root hash, bagging the peaks.
+    pub fn root(&self) -> Option<Digest> {
+        self.0.root()
+    }
+
+    /// Checks that `proof` proves a leaf of this Merkle Mountain Range.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::RootMismatch` if the proof was generated for
+    /// another number of leaves or does not lead to the bagged peaks, and the
+    /// error of `MmrProof::compute_root` if it fails.
+    pub fn verify(&self, proof: &MmrProof<H>) -> Result<(), MerkleError> {
+        let root = proof.compute_root()?;
+        match proof.leaf_count == self.leaf_count() && Some(root) == self.root() {
+            true => Ok(()),
+            false => Err(MerkleError::RootMismatch),
+        }
+    }
+}
+
+/// Represents a proof that `leaf` is the leaf at `leaf_index` of a Merkle
+/// Mountain Range of `leaf_count` leaves: the siblings on the path to the
+/// peak of its mountain, from the leaf up, and the other peaks.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MmrProof<H: MerkleHasher = Sha256> {
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub leaf: Digest,
+    pub siblings: Vec<Digest>,
+    pub peaks: Vec<Digest>,
+    pub algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> MmrProof<H> {
+    pub fn new(
+        leaf_index: usize,
+        leaf_count: usize,
+        leaf: Digest,
+        siblings: Vec<Digest>,
+        peaks: Vec<Digest>,
+    ) -> Self {
+        Self {
+            leaf_index,
+            leaf_count,
+            leaf,
+            siblings,
+            peaks,
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the proof holds the leaf hash of `leaf`.
+    pub fn proves(&self, leaf: &Digest) -> bool {
+        self.leaf == MODE.hash_leaf::<H>(leaf)
+    }
+
+    /// Computes the root hash of the Merkle Mountain Range using the proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the leaf index is out of range
+    /// or the number of siblings or peaks does not match the position of the
+    /// leaf, and `MerkleError::AlgorithmMismatch` if it was built with another
+    /// algorithm than `H`.
+    pub fn compute_root(&self) -> Result<Digest, MerkleError> {
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        if self.leaf_index >= self.leaf_count {
+            return Err(MerkleError::MalformedProof("leaf index out of range"));
+        }
+        let (height, position, start) = locate_mountain(self.leaf_index, self.leaf_count);
+        if self.siblings.len() != height {
+            return Err(MerkleError::MalformedProof(
+                "path does not match the mountain height",
+            ));
+        }
+        if self.peaks.len() + 1 != self.leaf_count.count_ones() as usize {
+            return Err(MerkleError::MalformedProof(
+                "peaks do not match the leaf count",
+            ));
+        }
+
+        let offset = self.leaf_index - start;
+        let mut peak = self.leaf;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            peak = match (offset >> level) % 2 {
+                0 => MODE.hash_nodes::<H>(&peak, sibling),
+                _ => MODE.hash_nodes::<H>(sibling, &peak),
+            };
+        }
+        let mut peaks = self.peaks.clone();
+        peaks.insert(position, peak);
+        Ok(bag_peaks::<H>(&peaks).expect("at least one peak"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Roots of successive vaults, the entries of an audit log.
+    fn vault_roots(count: usize) -> Vec<Digest> {
+        (0..count)
+            .map(|i| Sha256::hash(format!("vault {i}").as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn should_keep_one_peak_per_bit_of_the_leaf_count() {
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+        assert_eq!(mmr.root(), None);
+
+        for (i, root) in vault_roots(45).iter().enumerate() {
+            assert_eq!(mmr.push(root), i);
+            assert_eq!(mmr.peaks().len(), (i + 1).count_ones() as usize);
+        }
+        // bagging the peaks from the right gives the RFC 6962 root
+        assert_eq!(
+            mmr.root(),
+            AppendOnlyTree::<Sha256>::from_leaves(&vault_roots(45)).root()
+        );
+    }
+
+    #[test]
+    fn should_prove_every_entry() {
+        let roots = vault_roots(37);
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+        for root in &roots {
+            mmr.push(root);
+        }
+
+        for (k, root) in roots.iter().enumerate() {
+            let proof = mmr.proof(k).unwrap();
+            assert!(proof.proves(root));
+            assert!(proof.siblings.len() + proof.peaks.len() <= 2 * 6);
+            assert_eq!(proof.compute_root(), Ok(mmr.root().unwrap()));
+        }
+        assert!(matches!(
+            mmr.proof(37),
+            Err(MerkleError::IndexOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn should_append_and_verify_from_peaks_only() {
+        let roots = vault_roots(20);
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+        for root in &roots[..13] {
+            mmr.push(root);
+        }
+
+        let json = serde_json::to_string(&mmr.to_peaks()).unwrap();
+        let mut peaks: MmrPeaks<Sha256> = serde_json::from_str(&json).unwrap();
+        for root in &roots[13..] {
+            assert_eq!(peaks.push(root), mmr.push(root));
+            assert_eq!(peaks.root(), mmr.root());
+        }
+
+        assert_eq!(peaks.verify(&mmr.proof(4).unwrap()), Ok(()));
+        let mut proof = mmr.proof(4).unwrap();
+        proof.leaf = MODE.hash_leaf::<Sha256>(&Sha256::hash(b"forged"));
+        assert_eq!(peaks.verify(&proof), Err(MerkleError::RootMismatch));
+    }
+
+    #[test]
+    fn should_reject_peaks_not_matching_the_leaf_count() {
+        let roots = vault_roots(3);
+        assert!(matches!(
+            MmrPeaks::<Sha256>::new(3, roots[..1].to_vec()),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+        for root in &roots {
+            mmr.push(root);
+        }
+        let json = serde_json::to_string(&mmr.to_peaks()).unwrap();
+        let forged = json.replace("\"size\":3", "\"size\":4");
+        assert_ne!(forged, json);
+        assert!(serde_json::from_str::<MmrPeaks<Sha256>>(&forged).is_err());
+    }
+
+    #[test]
+    fn should_reject_proof_for_another_position() {
+        let mut mmr = MerkleMountainRange::<Sha256>::new();
+        for root in vault_roots(11) {
+            mmr.push(&root);
+        }
+
+        let mut proof = mmr.proof(5).unwrap();
+        proof.leaf_index = 4;
+        assert_ne!(proof.compute_root(), Ok(mmr.root().unwrap()));
+        proof.leaf_index = 9;
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+    }
+}
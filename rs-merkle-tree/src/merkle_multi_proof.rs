@@ -0,0 +1,292 @@
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use crate::tree_params::{OddNodeStrategy, TreeParams};
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Represents a Merkle proof for several leaves at once.
+///
+/// It holds the proven leaves, sorted by index, and only the siblings which
+/// cannot be computed from them, level by level from the leaves to the root
+/// and from left to right within a level.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MerkleMultiProof<H: MerkleHasher = Sha256> {
+    pub leaf_indices: Vec<usize>,
+    pub leaves: Vec<Digest>,
+    pub leaf_count: usize,
+    pub siblings: Vec<Digest>,
+    pub mode: HashMode,
+    #[serde(default)]
+    pub odd_node: OddNodeStrategy,
+    #[serde(default)]
+    pub commit_leaf_count: bool,
+    pub algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> MerkleMultiProof<H> {
+    pub fn new(
+        leaf_indices: Vec<usize>,
+        leaves: Vec<Digest>,
+        leaf_count: usize,
+        siblings: Vec<Digest>,
+        params: impl Into<TreeParams>,
+    ) -> Self {
+        let params = params.into();
+        Self {
+            leaf_indices,
+            leaves,
+            leaf_count,
+            siblings,
+            mode: params.mode,
+            odd_node: params.odd_node,
+            commit_leaf_count: params.commit_leaf_count,
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns the parameters of the tree the proof was built from.
+    pub fn params(&self) -> TreeParams {
+        TreeParams {
+            mode: self.mode,
+            odd_node: self.odd_node,
+            commit_leaf_count: self.commit_leaf_count,
+        }
+    }
+
+    /// Checks that there is one strictly increasing index per leaf, all lower
+    /// than the leaf count.
+    fn check_indices(&self) -> Result<(), MerkleError> {
+        if self.leaf_indices.len() != self.leaves.len() {
+            return Err(MerkleError::MalformedProof(
+                "leaf indices do not match the leaves",
+            ));
+        }
+        if self.leaf_indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(MerkleError::MalformedProof("leaf indices are not sorted"));
+        }
+        match self.leaf_indices.last() {
+            Some(index) if *index >= self.leaf_count => {
+                Err(MerkleError::MalformedProof("leaf index out of range"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Computes the Merkle root hash using the Merkle multi-proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the proof has no leaves, has
+    /// invalid indices or does not hold exactly the siblings needed, and
+    /// `MerkleError::AlgorithmMismatch` if it was built with another algorithm
+    /// than `H`.
+    pub fn compute_root(&self) -> Result<Digest, MerkleError> {
+        if self.leaves.is_empty() {
+            return Err(MerkleError::MalformedProof("missing proof"));
+        }
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        self.check_indices()?;
+
+        let params = self.params();
+        let missing_sibling = MerkleError::MalformedProof("missing sibling");
+        let mut siblings = self.siblings.iter();
+        let mut nodes: Vec<(usize, Digest)> = self
+            .leaf_indices
+            .iter()
+            .zip(&self.leaves)
+            .map(|(index, leaf)| (*index, self.mode.hash_leaf::<H>(leaf)))
+            .collect();
+
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut i = 0;
+            while i < nodes.len() {
+                let (index, hash) = nodes[i];
+                let (left, right) = if index % 2 == 1 {
+                    (*siblings.next().ok_or(missing_sibling.clone())?, hash)
+                } else if nodes.get(i + 1).map(|n| n.0) == Some(index + 1) {
+                    i += 1;
+                    (hash, nodes[i].1)
+                } else if index + 1 < level_len {
+                    (hash, *siblings.next().ok_or(missing_sibling.clone())?)
+                } else {
+                    parents.push((index / 2, params.parent::<H>(&hash, None)));
+                    i += 1;
+                    continue;
+                };
+                parents.push((index / 2, params.parent::<H>(&left, Some(&right))));
+                i += 1;
+            }
+            nodes = parents;
+            level_len = level_len.div_ceil(2);
+        }
+
+        if siblings.next().is_some() {
+            return Err(MerkleError::MalformedProof("unused siblings"));
+        }
+        Ok(params.root::<H>(&nodes[0].1, self.leaf_count))
+    }
+
+    /// Computes the Merkle root hash using the Merkle multi-proof, tagged with its algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `compute_root` fails.
+    pub fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError> {
+        Ok(MerkleRoot::new(H::ALGORITHM, self.compute_root()?))
+    }
+
+    /// Computes the Merkle root hash using the Merkle multi-proof, refusing to
+    /// do so if the proof was not built with the `expected` hashing mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ModeMismatch` if the modes differ, or the error
+    /// of `compute_root` if it fails.
+    pub fn compute_root_with_mode(&self, expected: HashMode) -> Result<Digest, MerkleError> {
+        if self.mode != expected {
+            return Err(MerkleError::ModeMismatch {
+                expected,
+                actual: self.mode,
+            });
+        }
+        self.compute_root()
+    }
+
+    /// Computes the Merkle root hash using the Merkle multi-proof, refusing to do so
+    /// if the proof was not built with the `expected` tree parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ParamsMismatch` if the parameters differ, or the
+    /// error of `compute_root` if it fails.
+    pub fn compute_root_with_params(&self, expected: TreeParams) -> Result<Digest, MerkleError> {
+        if self.params() != expected {
+            return Err(MerkleError::ParamsMismatch {
+                expected,
+                actual: self.params(),
+            });
+        }
+        self.compute_root()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::Sha3_256;
+
+    fn leaves(count: usize) -> Vec<Digest> {
+        (0..count).map(|i| Sha256::hash(&i.to_le_bytes())).collect()
+    }
+
+    #[test]
+    fn should_prove_any_set_of_leaves() {
+        for leaf_count in 1..=10 {
+            for mode in [HashMode::Legacy, HashMode::Rfc6962] {
+                let tree = MerkleTree::<Sha256>::build(leaves(leaf_count), mode);
+                // every subset of the leaves, encoded as a bit mask
+                for mask in 1..(1u32 << leaf_count) {
+                    let indices: Vec<usize> =
+                        (0..leaf_count).filter(|i| mask & (1 << i) != 0).collect();
+                    let proof = tree.multi_proof(&indices).unwrap();
+                    assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_only_hold_needed_siblings() {
+        let tree = MerkleTree::<Sha256>::build(leaves(8), HashMode::Rfc6962);
+
+        assert_eq!(tree.multi_proof(&[0, 1]).unwrap().siblings.len(), 2);
+        assert_eq!(tree.multi_proof(&[0, 1, 2, 3]).unwrap().siblings.len(), 1);
+        assert_eq!(tree.multi_proof(&[0, 7]).unwrap().siblings.len(), 4);
+        assert!(tree
+            .multi_proof(&(0..8).collect::<Vec<_>>())
+            .unwrap()
+            .siblings
+            .is_empty());
+    }
+
+    #[test]
+    fn should_sort_and_deduplicate_indices() {
+        let tree = MerkleTree::<Sha256>::build(leaves(5), HashMode::Legacy);
+        let proof = tree.multi_proof(&[4, 1, 4]).unwrap();
+
+        assert_eq!(proof.leaf_indices, vec![1, 4]);
+        assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+        assert_eq!(
+            tree.multi_proof(&[1, 5]).unwrap_err(),
+            MerkleError::IndexOutOfRange {
+                index: 5,
+                leaf_count: 5
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_tampered_proof() {
+        let tree = MerkleTree::<Sha256>::build(leaves(6), HashMode::Rfc6962);
+
+        let mut proof = tree.multi_proof(&[1, 4]).unwrap();
+        proof.leaves[1] = Sha256::hash(b"tampered");
+        assert_ne!(proof.compute_root().unwrap(), *tree.root().unwrap());
+
+        let mut proof = tree.multi_proof(&[1, 4]).unwrap();
+        proof.siblings.pop();
+        assert_eq!(
+            proof.compute_root().unwrap_err(),
+            MerkleError::MalformedProof("missing sibling")
+        );
+
+        let mut proof = tree.multi_proof(&[1, 4]).unwrap();
+        proof.siblings.push(Sha256::hash(b"extra"));
+        assert_eq!(
+            proof.compute_root().unwrap_err(),
+            MerkleError::MalformedProof("unused siblings")
+        );
+
+        let mut proof = tree.multi_proof(&[1, 4]).unwrap();
+        proof.leaf_indices.reverse();
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+    }
+
+    #[test]
+    fn should_serialize_multi_proof() {
+        let leaves: Vec<Digest> = ["a", "b", "c"]
+            .iter()
+            .map(|x| Sha3_256::hash(x.as_bytes()))
+            .collect();
+        let tree = MerkleTree::<Sha3_256>::build(leaves, HashMode::Rfc6962);
+
+        let json = serde_json::to_string(&tree.multi_proof(&[0, 2]).unwrap()).unwrap();
+        let proof: MerkleMultiProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            proof.compute_tagged_root().unwrap(),
+            tree.tagged_root().unwrap()
+        );
+
+        let proof: MerkleMultiProof<Sha256> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+}
@@ -1,41 +1,233 @@
+use crate::error::MerkleError;
+use crate::merkle_compact_proof::CompactProof;
+use crate::merkle_root::MerkleRoot;
 use crate::merkle_tree::{Direction, MerkleNode};
-use crate::utils::crypto::{hash, Hash};
+use crate::tree_params::{OddNodeStrategy, TreeParams};
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 
 /// Represents a Merkle proof, which is a list of Merkle nodes.
+///
+/// The first node holds the proven leaf, the following ones its siblings up to
+/// the root. The parameters and the algorithm of the tree are recorded
+/// alongside; proofs serialized before they existed are read back with the
+/// default `TreeParams` and `Algorithm::Sha256`. Proofs generated from a leaf
+/// index also record it, with the number of leaves of the tree.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct MerkleProof {
+pub struct MerkleProof<H: MerkleHasher = Sha256> {
     pub nodes: Vec<MerkleNode>,
+    #[serde(default)]
+    pub mode: HashMode,
+    #[serde(default)]
+    pub odd_node: OddNodeStrategy,
+    #[serde(default)]
+    pub commit_leaf_count: bool,
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    #[serde(default)]
+    pub leaf_index: Option<usize>,
+    #[serde(default)]
+    pub leaf_count: Option<usize>,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
 }
 
-impl MerkleProof {
+impl<H: MerkleHasher> MerkleProof<H> {
     pub fn new(hashes: Vec<MerkleNode>) -> Self {
-        Self { nodes: hashes }
+        Self::with_mode(hashes, HashMode::Legacy)
+    }
+
+    pub fn with_mode(hashes: Vec<MerkleNode>, params: impl Into<TreeParams>) -> Self {
+        let params = params.into();
+        Self {
+            nodes: hashes,
+            mode: params.mode,
+            odd_node: params.odd_node,
+            commit_leaf_count: params.commit_leaf_count,
+            algorithm: H::ALGORITHM,
+            leaf_index: None,
+            leaf_count: None,
+            _hasher: PhantomData,
+        }
+    }
+
+    pub fn with_position(
+        hashes: Vec<MerkleNode>,
+        params: impl Into<TreeParams>,
+        leaf_index: usize,
+        leaf_count: usize,
+    ) -> Self {
+        Self {
+            leaf_index: Some(leaf_index),
+            leaf_count: Some(leaf_count),
+            ..Self::with_mode(hashes, params)
+        }
+    }
+
+    /// Returns the proof of `leaf` in a `HashMode::SortedPair` tree from the
+    /// hashes of its siblings, e.g. those of `sibling_hashes`. Such proofs do
+    /// not need the side of each sibling.
+    ///
+    /// Trees built by OpenZeppelin's `merkle-tree` library are laid out as a
+    /// `CompleteMerkleTree`, whose proofs are read back the same way.
+    pub fn from_sorted_pair(leaf: Digest, siblings: &[Digest]) -> Self {
+        let nodes = core::iter::once(&leaf)
+            .chain(siblings)
+            .map(|&hash| MerkleNode {
+                hash,
+                direction: Direction::Right,
+            })
+            .collect();
+        Self::with_mode(nodes, HashMode::SortedPair)
+    }
+
+    /// Returns the hashes of the siblings of the proven leaf, from the leaf to
+    /// the root, without their side: the `bytes32[] proof` argument of
+    /// OpenZeppelin's `MerkleProof.verify` for a `HashMode::SortedPair` tree.
+    pub fn sibling_hashes(&self) -> Vec<Digest> {
+        self.nodes.iter().skip(1).map(|node| node.hash).collect()
+    }
+
+    /// Returns the parameters of the tree the proof was built from.
+    pub fn params(&self) -> TreeParams {
+        TreeParams {
+            mode: self.mode,
+            odd_node: self.odd_node,
+            commit_leaf_count: self.commit_leaf_count,
+        }
+    }
+
+    /// Checks that the siblings of the proof match the recorded leaf position:
+    /// there must be one sibling per level of a tree of `leaf_count` leaves,
+    /// promoted nodes excepted, on the side given by the bits of `leaf_index`.
+    fn check_position(&self) -> Result<(), MerkleError> {
+        let (leaf_index, leaf_count) = match (self.leaf_index, self.leaf_count) {
+            (Some(leaf_index), Some(leaf_count)) => (leaf_index, leaf_count),
+            _ => return Ok(()),
+        };
+        if leaf_index >= leaf_count {
+            return Err(MerkleError::MalformedProof("leaf index out of range"));
+        }
+
+        let length_mismatch = MerkleError::MalformedProof("length does not match the leaf count");
+        let mut siblings = self.nodes.iter().skip(1);
+        let mut index = leaf_index;
+        let mut level_len = leaf_count;
+        while level_len > 1 {
+            let promoted = self.odd_node == OddNodeStrategy::Promote
+                && index.is_multiple_of(2)
+                && index + 1 == level_len;
+            if !promoted {
+                let expected = if index.is_multiple_of(2) {
+                    Direction::Right
+                } else {
+                    Direction::Left
+                };
+                if siblings.next().ok_or(length_mismatch.clone())?.direction != expected {
+                    return Err(MerkleError::MalformedProof(
+                        "path does not match the leaf index",
+                    ));
+                }
+            }
+            index /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+        match siblings.next() {
+            Some(_) => Err(length_mismatch),
+            None => Ok(()),
+        }
     }
 
     /// Computes the Merkle root hash using the Merkle proof.
     ///
     /// # Errors
     ///
-    /// Returns an error if the Merkle proof is empty or if there's an issue computing the root hash.
-    pub fn compute_root(&self) -> Result<Hash, Box<dyn Error>> {
+    /// Returns `MerkleError::MalformedProof` if the Merkle proof is empty, does
+    /// not match its recorded position or lacks the leaf count its root
+    /// commits to, `MerkleError::AlgorithmMismatch` if it was built with
+    /// another algorithm than `H`.
+    pub fn compute_root(&self) -> Result<Digest, MerkleError> {
         if self.nodes.is_empty() {
-            return Err("missing proof".into());
+            return Err(MerkleError::MalformedProof("missing proof"));
         }
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        self.check_position()?;
 
-        let mut merkle_root_from_proof = self.nodes[0].hash.clone();
+        let mut merkle_root_from_proof = self.mode.hash_leaf::<H>(&self.nodes[0].hash);
 
-        for i in 1..self.nodes.len() {
-            if self.nodes[i].direction == Direction::Right {
-                merkle_root_from_proof =
-                    hash(&[merkle_root_from_proof, self.nodes[i].hash.clone()].concat());
-            } else {
-                merkle_root_from_proof =
-                    hash(&[self.nodes[i].hash.clone(), merkle_root_from_proof].concat());
+        for node in self.nodes.iter().skip(1) {
+            merkle_root_from_proof = match node.direction {
+                Direction::Right => self
+                    .mode
+                    .hash_nodes::<H>(&merkle_root_from_proof, &node.hash),
+                Direction::Left => self
+                    .mode
+                    .hash_nodes::<H>(&node.hash, &merkle_root_from_proof),
+            };
+        }
+        match (self.commit_leaf_count, self.leaf_count) {
+            (false, _) => Ok(merkle_root_from_proof),
+            (true, Some(leaf_count)) => {
+                Ok(self.params().root::<H>(&merkle_root_from_proof, leaf_count))
             }
+            (true, None) => Err(MerkleError::MalformedProof("missing leaf count")),
         }
-        return Ok(merkle_root_from_proof);
+    }
+
+    /// Computes the Merkle root hash using the Merkle proof, tagged with its algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `compute_root` fails.
+    pub fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError> {
+        Ok(MerkleRoot::new(H::ALGORITHM, self.compute_root()?))
+    }
+
+    /// Computes the Merkle root hash using the Merkle proof, refusing to do so
+    /// if the proof was not built with the `expected` hashing mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ModeMismatch` if the modes differ, or the error
+    /// of `compute_root` if it fails.
+    pub fn compute_root_with_mode(&self, expected: HashMode) -> Result<Digest, MerkleError> {
+        if self.mode != expected {
+            return Err(MerkleError::ModeMismatch {
+                expected,
+                actual: self.mode,
+            });
+        }
+        self.compute_root()
+    }
+
+    /// Computes the Merkle root hash using the Merkle proof, refusing to do so
+    /// if the proof was not built with the `expected` tree parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ParamsMismatch` if the parameters differ, or the
+    /// error of `compute_root` if it fails.
+    pub fn compute_root_with_params(&self, expected: TreeParams) -> Result<Digest, MerkleError> {
+        if self.params() != expected {
+            return Err(MerkleError::ParamsMismatch {
+                expected,
+                actual: self.params(),
+            });
+        }
+        self.compute_root()
+    }
+
+    /// Returns the compact binary encoding of the proof.
+    pub fn to_compact(&self) -> CompactProof {
+        CompactProof::encode(self)
     }
 
     /// Computes the hexadecimal representation of the Merkle root hash using the Merkle proof.
@@ -43,7 +235,7 @@ impl MerkleProof {
     /// # Errors
     ///
     /// Returns an error if there's an issue computing the root hash or encoding it as hexadecimal.
-    pub fn compute_root_hex(&self) -> Result<String, Box<dyn Error>> {
+    pub fn compute_root_hex(&self) -> Result<String, MerkleError> {
         Ok(hex::encode(self.compute_root()?))
     }
 }
@@ -52,18 +244,168 @@ impl MerkleProof {
 mod tests {
     use super::*;
     use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::{hash, Hash, Keccak256, Sha3_256, Sha512_256};
+    use crate::CompleteMerkleTree;
 
     #[test]
     fn should_generate_proof() {
         let leaf_values = ["a", "b", "c", "d", "e", "f"];
-        let leaf_hashes: Vec<Hash> = leaf_values
-            .iter()
-            .map(|x| hash(&x.as_bytes().to_vec()))
-            .collect();
+        let leaf_hashes: Vec<Hash> = leaf_values.iter().map(|x| hash(x.as_bytes())).collect();
 
         let tree = MerkleTree::from_leaves(leaf_hashes.clone());
         let proof = tree.proof(leaf_hashes[0].clone()).unwrap();
 
         assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
     }
+    #[test]
+    fn should_reject_proof_with_unexpected_mode() {
+        let leaf_hashes: Vec<Hash> = ["a", "b", "c"].iter().map(|x| hash(x.as_bytes())).collect();
+
+        let tree = MerkleTree::from_leaves_with_mode(leaf_hashes.clone(), HashMode::Rfc6962);
+        let proof = tree.proof(leaf_hashes[1].clone()).unwrap();
+
+        assert_eq!(
+            proof.compute_root_with_mode(HashMode::Legacy).unwrap_err(),
+            MerkleError::ModeMismatch {
+                expected: HashMode::Legacy,
+                actual: HashMode::Rfc6962
+            }
+        );
+        assert_eq!(
+            proof.compute_root_with_mode(HashMode::Rfc6962).unwrap(),
+            *tree.root().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_read_proof_without_mode_as_legacy() {
+        let proof: MerkleProof = serde_json::from_str(r#"{"nodes":[]}"#).unwrap();
+        assert_eq!(proof.mode, HashMode::Legacy);
+        assert_eq!(proof.algorithm, Algorithm::Sha256);
+    }
+
+    #[test]
+    fn should_reject_proof_with_unexpected_algorithm() {
+        let leaf_hashes: Vec<Digest> = ["a", "b", "c"]
+            .iter()
+            .map(|x| Sha3_256::hash(x.as_bytes()))
+            .collect();
+
+        let tree = MerkleTree::<Sha3_256>::build(leaf_hashes.clone(), HashMode::Rfc6962);
+        let json = serde_json::to_string(&tree.proof(leaf_hashes[0]).unwrap()).unwrap();
+        assert!(json.contains(r#""algorithm":"sha3-256""#));
+
+        let proof: MerkleProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            proof.compute_tagged_root().unwrap(),
+            tree.tagged_root().unwrap()
+        );
+
+        let proof: MerkleProof<Sha512_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            proof.compute_root().unwrap_err(),
+            MerkleError::AlgorithmMismatch {
+                expected: Algorithm::Sha512_256,
+                actual: Algorithm::Sha3_256
+            }
+        );
+    }
+    #[test]
+    fn should_reject_proof_not_matching_its_position() {
+        let leaf_hashes: Vec<Hash> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|x| hash(x.as_bytes()))
+            .collect();
+        let tree = MerkleTree::from_leaves(leaf_hashes);
+
+        let mut proof = tree.proof_for_index(3).unwrap();
+        proof.leaf_index = Some(2);
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        proof.leaf_index = Some(5);
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        let mut proof = tree.proof_for_index(3).unwrap();
+        proof.leaf_count = Some(4);
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::MalformedProof(_))
+        ));
+    }
+
+    /// Computes the root a proof leads to as OpenZeppelin's
+    /// `MerkleProof.processProof` does.
+    fn process_proof(proof: &[Digest], leaf: Digest) -> Digest {
+        proof.iter().fold(leaf, |computed, sibling| {
+            let (a, b) = match computed < *sibling {
+                true => (computed, *sibling),
+                false => (*sibling, computed),
+            };
+            Keccak256::hash(&[a.0, b.0].concat())
+        })
+    }
+
+    #[test]
+    fn should_match_openzeppelin_sorted_pair_proofs() {
+        let leaves: Vec<Digest> = (0..16u8)
+            .map(|i| Keccak256::hash(&Keccak256::hash(&[i]).0))
+            .collect();
+
+        for leaf_count in 1..=leaves.len() {
+            for odd_node in [OddNodeStrategy::Duplicate, OddNodeStrategy::Promote] {
+                let params = TreeParams {
+                    mode: HashMode::SortedPair,
+                    odd_node,
+                    commit_leaf_count: false,
+                };
+                let tree = MerkleTree::<Keccak256>::build(leaves[..leaf_count].to_vec(), params);
+                let root = *tree.root().unwrap();
+                for (index, leaf) in leaves[..leaf_count].iter().enumerate() {
+                    let siblings = tree.proof_for_index(index).unwrap().sibling_hashes();
+                    assert_eq!(process_proof(&siblings, *leaf), root);
+
+                    let proof = MerkleProof::<Keccak256>::from_sorted_pair(*leaf, &siblings);
+                    assert_eq!(proof.compute_root(), Ok(root));
+                }
+            }
+        }
+
+        // OpenZeppelin's complete layout pairs the same nodes when the leaf
+        // count is a power of two, and its proofs verify the same way
+        for leaf_count in 1..=leaves.len() {
+            let leaves = leaves[..leaf_count].to_vec();
+            let tree = CompleteMerkleTree::<Keccak256>::build(leaves.clone(), HashMode::SortedPair);
+            let root = *tree.root().unwrap();
+            for (index, leaf) in leaves.iter().enumerate() {
+                let siblings = tree.proof_for_index(index).unwrap().sibling_hashes();
+                assert_eq!(process_proof(&siblings, *leaf), root);
+            }
+            let layered = MerkleTree::<Keccak256>::build(leaves, HashMode::SortedPair);
+            assert_eq!(
+                *layered.root().unwrap() == root,
+                leaf_count.is_power_of_two()
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_proof_with_truncated_hash() {
+        let node = |len: usize| format!(r#"{{"hash":{:?},"direction":"Right"}}"#, vec![0u8; len]);
+        let proof = |len: usize| format!(r#"{{"nodes":[{},{}]}}"#, node(32), node(len));
+
+        assert!(serde_json::from_str::<MerkleProof>(&proof(32)).is_ok());
+        assert!(serde_json::from_str::<MerkleProof>(&proof(31)).is_err());
+        assert_eq!(
+            MerkleProof::<Sha256>::new(vec![])
+                .compute_root()
+                .unwrap_err(),
+            MerkleError::MalformedProof("missing proof")
+        );
+    }
 }
@@ -0,0 +1,265 @@
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use crate::tree_params::{OddNodeStrategy, TreeParams};
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Represents a Merkle proof that the leaves `start..start + leaves.len()` of
+/// a tree are exactly `leaves`, in this order.
+///
+/// Only the siblings along the left and right boundaries of the range are
+/// needed, level by level from the leaves to the root: every node in between
+/// is computed from the leaves, so no leaf can be skipped, added or moved.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RangeProof<H: MerkleHasher = Sha256> {
+    pub start: usize,
+    pub leaves: Vec<Digest>,
+    pub leaf_count: usize,
+    pub left: Vec<Digest>,
+    pub right: Vec<Digest>,
+    pub mode: HashMode,
+    #[serde(default)]
+    pub odd_node: OddNodeStrategy,
+    #[serde(default)]
+    pub commit_leaf_count: bool,
+    pub algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> RangeProof<H> {
+    pub fn new(
+        start: usize,
+        leaves: Vec<Digest>,
+        leaf_count: usize,
+        left: Vec<Digest>,
+        right: Vec<Digest>,
+        params: impl Into<TreeParams>,
+    ) -> Self {
+        let params = params.into();
+        Self {
+            start,
+            leaves,
+            leaf_count,
+            left,
+            right,
+            mode: params.mode,
+            odd_node: params.odd_node,
+            commit_leaf_count: params.commit_leaf_count,
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns the parameters of the tree the proof was built from.
+    pub fn params(&self) -> TreeParams {
+        TreeParams {
+            mode: self.mode,
+            odd_node: self.odd_node,
+            commit_leaf_count: self.commit_leaf_count,
+        }
+    }
+
+    /// Returns the positions of the proven leaves.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the last position overflows.
+    pub fn range(&self) -> Result<core::ops::Range<usize>, MerkleError> {
+        match self.start.checked_add(self.leaves.len()) {
+            Some(end) => Ok(self.start..end),
+            None => Err(MerkleError::MalformedProof("range out of the tree")),
+        }
+    }
+
+    /// Computes the Merkle root hash using the range proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the proof has no leaves, does
+    /// not fit in the tree or does not hold exactly the boundary siblings
+    /// needed, and `MerkleError::AlgorithmMismatch` if it was built with
+    /// another algorithm than `H`.
+    pub fn compute_root(&self) -> Result<Digest, MerkleError> {
+        if self.leaves.is_empty() {
+            return Err(MerkleError::MalformedProof("missing proof"));
+        }
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        if self.range()?.end > self.leaf_count {
+            return Err(MerkleError::MalformedProof("range out of the tree"));
+        }
+
+        let params = self.params();
+        let missing_sibling = MerkleError::MalformedProof("missing boundary sibling");
+        let mut left = self.left.iter();
+        let mut right = self.right.iter();
+        let mut nodes: Vec<Digest> = self
+            .leaves
+            .iter()
+            .map(|leaf| self.mode.hash_leaf::<H>(leaf))
+            .collect();
+
+        let mut start = self.start;
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let end = start
+                .checked_add(nodes.len())
+                .ok_or(MerkleError::MalformedProof("range out of the tree"))?;
+            let mut level = Vec::with_capacity(nodes.len() + 2);
+            if start % 2 == 1 {
+                level.push(*left.next().ok_or(missing_sibling.clone())?);
+            }
+            level.extend_from_slice(&nodes);
+            // the last node of an odd level is left unpaired
+            if end % 2 == 1 && end < level_len {
+                level.push(*right.next().ok_or(missing_sibling.clone())?);
+            }
+            nodes = level
+                .chunks(2)
+                .map(|pair| params.parent::<H>(&pair[0], pair.get(1)))
+                .collect();
+            start /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+
+        if left.next().is_some() || right.next().is_some() {
+            return Err(MerkleError::MalformedProof("unused boundary siblings"));
+        }
+        Ok(params.root::<H>(&nodes[0], self.leaf_count))
+    }
+
+    /// Computes the Merkle root hash using the range proof, tagged with its algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `compute_root` fails.
+    pub fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError> {
+        Ok(MerkleRoot::new(H::ALGORITHM, self.compute_root()?))
+    }
+
+    /// Computes the Merkle root hash using the range proof, refusing to do so
+    /// if the proof was not built with the `expected` hashing mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ModeMismatch` if the modes differ, or the error
+    /// of `compute_root` if it fails.
+    pub fn compute_root_with_mode(&self, expected: HashMode) -> Result<Digest, MerkleError> {
+        if self.mode != expected {
+            return Err(MerkleError::ModeMismatch {
+                expected,
+                actual: self.mode,
+            });
+        }
+        self.compute_root()
+    }
+
+    /// Computes the Merkle root hash using the range proof, refusing to do so
+    /// if the proof was not built with the `expected` tree parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ParamsMismatch` if the parameters differ, or the
+    /// error of `compute_root` if it fails.
+    pub fn compute_root_with_params(&self, expected: TreeParams) -> Result<Digest, MerkleError> {
+        if self.params() != expected {
+            return Err(MerkleError::ParamsMismatch {
+                expected,
+                actual: self.params(),
+            });
+        }
+        self.compute_root()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    fn leaves(count: usize) -> Vec<Digest> {
+        (0..count).map(|i| Sha256::hash(&i.to_le_bytes())).collect()
+    }
+
+    #[test]
+    fn should_prove_every_range() {
+        for leaf_count in 1..=17 {
+            for mode in [HashMode::Legacy, HashMode::Rfc6962] {
+                let tree = MerkleTree::<Sha256>::build(leaves(leaf_count), mode);
+                for start in 0..leaf_count {
+                    for end in start + 1..=leaf_count {
+                        let proof = tree.range_proof(start..end).unwrap();
+                        assert_eq!(proof.range(), Ok(start..end));
+                        assert_eq!(proof.leaves, leaves(leaf_count)[start..end]);
+                        assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_only_hold_boundary_siblings() {
+        let tree = MerkleTree::<Sha256>::build(leaves(16), HashMode::Rfc6962);
+
+        let proof = tree.range_proof(3..13).unwrap();
+        assert_eq!((proof.left.len(), proof.right.len()), (2, 2));
+        let proof = tree.range_proof(0..16).unwrap();
+        assert!(proof.left.is_empty() && proof.right.is_empty());
+    }
+
+    #[test]
+    fn should_detect_skipped_or_reordered_leaves() {
+        let tree = MerkleTree::<Sha256>::build(leaves(11), HashMode::Rfc6962);
+        let root = *tree.root().unwrap();
+
+        let mut proof = tree.range_proof(2..9).unwrap();
+        proof.leaves.swap(3, 4);
+        assert_ne!(proof.compute_root().unwrap(), root);
+
+        let mut proof = tree.range_proof(2..9).unwrap();
+        proof.leaves.remove(3);
+        assert_ne!(proof.compute_root().ok(), Some(root));
+
+        let mut proof = tree.range_proof(2..9).unwrap();
+        proof.start = 3;
+        assert_ne!(proof.compute_root().ok(), Some(root));
+
+        let mut proof = tree.range_proof(2..9).unwrap();
+        proof.right.push(Sha256::hash(b"extra"));
+        assert_eq!(
+            proof.compute_root().unwrap_err(),
+            MerkleError::MalformedProof("unused boundary siblings")
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_ranges() {
+        let tree = MerkleTree::<Sha256>::build(leaves(5), HashMode::Legacy);
+
+        assert_eq!(
+            tree.range_proof(3..6).unwrap_err(),
+            MerkleError::IndexOutOfRange {
+                index: 5,
+                leaf_count: 5
+            }
+        );
+        assert!(matches!(
+            tree.range_proof(2..2),
+            Err(MerkleError::MalformedProof(_))
+        ));
+
+        let mut proof = tree.range_proof(1..3).unwrap();
+        proof.start = usize::MAX;
+        let out_of_the_tree = MerkleError::MalformedProof("range out of the tree");
+        assert_eq!(proof.range(), Err(out_of_the_tree.clone()));
+        assert_eq!(proof.compute_root(), Err(out_of_the_tree));
+    }
+}
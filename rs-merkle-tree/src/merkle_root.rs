@@ -0,0 +1,97 @@
+use crate::error::MerkleError;
+use crate::utils::crypto::{Algorithm, Digest};
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A Merkle root hash tagged with the algorithm that produced it.
+///
+/// It is serialized as `<algorithm>:<hex>`, e.g. `sha3-256:3a98…`. Bare hex
+/// strings, as stored for vaults committed before algorithms were pluggable,
+/// are read back as SHA-256 roots.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MerkleRoot {
+    pub algorithm: Algorithm,
+    pub hash: Digest,
+}
+
+impl MerkleRoot {
+    pub fn new(algorithm: Algorithm, hash: Digest) -> Self {
+        Self { algorithm, hash }
+    }
+
+    /// Returns the hexadecimal representation of the root hash, without its algorithm.
+    pub fn hash_hex(&self) -> String {
+        hex::encode(self.hash)
+    }
+}
+
+impl fmt::Display for MerkleRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hash_hex())
+    }
+}
+
+impl FromStr for MerkleRoot {
+    type Err = MerkleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hash) = match s.split_once(':') {
+            Some((algorithm, hash)) => (algorithm.parse::<Algorithm>()?, hash),
+            None => (Algorithm::Sha256, s),
+        };
+        let hash = hex::decode(hash).map_err(|err| MerkleError::InvalidHex(err.to_string()))?;
+        Ok(Self::new(algorithm, Digest::try_from(hash)?))
+    }
+}
+
+impl Serialize for MerkleRoot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MerkleRoot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_bare_hex_as_sha256_root() {
+        let root: MerkleRoot = "ff".repeat(32).parse().unwrap();
+        assert_eq!(root, MerkleRoot::new(Algorithm::Sha256, Digest([0xff; 32])));
+        assert_eq!(root.to_string(), format!("sha256:{}", "ff".repeat(32)));
+    }
+
+    #[test]
+    fn should_round_trip_tagged_root() {
+        let root = MerkleRoot::new(Algorithm::Sha3_256, Digest([0xab; 32]));
+        let json = serde_json::to_string(&root).unwrap();
+
+        assert_eq!(json, format!("\"sha3-256:{}\"", "ab".repeat(32)));
+        assert_eq!(serde_json::from_str::<MerkleRoot>(&json).unwrap(), root);
+        assert_eq!(
+            "md5:00ff".parse::<MerkleRoot>(),
+            Err(MerkleError::UnknownAlgorithm("md5".to_string()))
+        );
+        assert_eq!(
+            "sha256:00ff".parse::<MerkleRoot>(),
+            Err(MerkleError::HashLengthMismatch {
+                expected: 32,
+                actual: 2
+            })
+        );
+        assert!(matches!(
+            "sha256:zz".parse::<MerkleRoot>(),
+            Err(MerkleError::InvalidHex(_))
+        ));
+    }
+}
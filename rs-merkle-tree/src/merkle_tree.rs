@@ -1,8 +1,21 @@
+use crate::error::MerkleError;
+use crate::merkle_multi_proof::MerkleMultiProof;
 use crate::merkle_proof::MerkleProof;
-use crate::utils::crypto::{hash, Hash};
-use hex;
+use crate::merkle_range_proof::RangeProof;
+use crate::merkle_root::MerkleRoot;
+use crate::merkle_tree_dump::{self, Dump};
+use crate::merkle_tree_view::{node_count, MerkleTreeView};
+use crate::merkle_update_proof::{LeafUpdate, UpdateProof};
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Algorithm, Digest, Hash, HashMode, MerkleHasher, Sha256};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 
 /// Represents the direction of a node in the Merkle tree.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -12,150 +25,419 @@ pub enum Direction {
 }
 
 /// Represents a node in the Merkle tree.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct MerkleNode {
-    pub hash: Hash,
+    pub hash: Digest,
     pub direction: Direction,
 }
 
-/// Represents a Merkle tree.
-pub struct MerkleTree {
-    pub hashes: Vec<Hash>,
-    levels_indices: Vec<usize>,
+/// Represents a Merkle tree whose nodes are hashed with `H`.
+///
+/// All the nodes are stored in a single flat array, level by level from the
+/// leaves to the root. The last node is the root, unless the root commits to
+/// the leaf count (see `TreeParams`).
+pub struct MerkleTree<H: MerkleHasher = Sha256> {
+    pub hashes: Vec<Digest>,
+    /// Leaves the tree was built from, before `HashMode::hash_leaf` was applied.
+    /// Only kept when it differs from the leaf level, i.e. not in `HashMode::Legacy`.
+    leaves: Vec<Digest>,
+    leaf_count: usize,
+    params: TreeParams,
+    root: Option<Digest>,
+    _hasher: PhantomData<H>,
 }
 
-impl MerkleTree {
-    /// Creates a new Merkle tree from a list of leaf hashes.
+impl MerkleTree<Sha256> {
+    /// Creates a new SHA-256 Merkle tree from a list of leaf hashes, using the
+    /// legacy hashing mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a leaf is not a 32 bytes hash.
     pub fn from_leaves(leaves: Vec<Hash>) -> Self {
-        if leaves.is_empty() {
-            return Self {
-                hashes: Vec::new(),
-                levels_indices: vec![0],
-            };
-        }
+        Self::from_leaves_with_mode(leaves, HashMode::Legacy)
+    }
 
-        let mut tree = Self {
-            hashes: Vec::from(leaves.clone()),
-            levels_indices: vec![0],
+    /// Creates a new SHA-256 Merkle tree from a list of leaf hashes, hashing
+    /// leaves and interior nodes according to `mode`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a leaf is not a 32 bytes hash, see `try_from_leaves_with_mode`.
+    pub fn from_leaves_with_mode(leaves: Vec<Hash>, mode: HashMode) -> Self {
+        Self::try_from_leaves_with_mode(leaves, mode).expect("leaves must be 32 bytes hashes")
+    }
+
+    /// Creates a new SHA-256 Merkle tree from a list of leaf hashes, hashing
+    /// leaves and interior nodes according to `mode`.
+    ///
+    /// Returns `MerkleError::HashLengthMismatch` if a leaf is not a 32 bytes hash.
+    pub fn try_from_leaves_with_mode(
+        leaves: Vec<Hash>,
+        mode: HashMode,
+    ) -> Result<Self, MerkleError> {
+        let leaves = leaves
+            .iter()
+            .map(Digest::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::build(leaves, mode))
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    /// Creates a new Merkle tree from a list of leaf hashes, hashing leaves and
+    /// interior nodes with `H` according to `params`, or to a `HashMode`.
+    ///
+    /// With a `HashMode`, the last node of a level with an odd number of nodes
+    /// is paired with itself.
+    pub fn build(leaves: Vec<Digest>, params: impl Into<TreeParams>) -> Self {
+        let params = params.into();
+        let mode = params.mode;
+        let leaf_count = leaves.len();
+        let (mut hashes, leaves) = match mode {
+            HashMode::Legacy => (leaves, Vec::new()),
+            _ => (
+                leaves.iter().map(|l| mode.hash_leaf::<H>(l)).collect(),
+                leaves,
+            ),
         };
+        hashes.reserve_exact(node_count(leaf_count).saturating_sub(leaf_count));
 
-        fn generate_next_layer(hashes: Vec<Hash>, tree: &mut MerkleTree) -> Vec<Hash> {
-            if hashes.len() == 1 {
-                return hashes;
+        let mut level_start = 0;
+        let mut level_len = leaf_count;
+        while level_len > 1 {
+            for i in (0..level_len).step_by(2) {
+                let left = hashes[level_start + i];
+                let right = match i + 1 < level_len {
+                    true => Some(hashes[level_start + i + 1]),
+                    false => None,
+                };
+                hashes.push(params.parent::<H>(&left, right.as_ref()));
             }
+            level_start += level_len;
+            level_len = level_len.div_ceil(2);
+        }
 
-            let last_even_index = if hashes.len() % 2 == 0 {
-                hashes.len()
-            } else {
-                hashes.len() - 1
-            };
-
-            let mut layer = Vec::new();
-            for i in (0..last_even_index - 1).step_by(2) {
-                let left = hashes[i].clone();
-                let right = hashes[i + 1].clone();
+        Self::from_parts(hashes, leaves, leaf_count, params)
+    }
 
-                layer.push(hash(&[left, right].concat()));
-            }
+    fn from_parts(
+        hashes: Vec<Digest>,
+        leaves: Vec<Digest>,
+        leaf_count: usize,
+        params: TreeParams,
+    ) -> Self {
+        Self {
+            root: hashes.last().map(|top| params.root::<H>(top, leaf_count)),
+            hashes,
+            leaves,
+            leaf_count,
+            params,
+            _hasher: PhantomData,
+        }
+    }
 
-            if last_even_index != hashes.len() {
-                let last = hashes.last().unwrap();
-                layer.push(hash(&[last.clone(), last.clone()].concat()));
-            }
+    /// Creates a new Merkle tree like `build`, hashing the leaves and each
+    /// level of interior nodes on the rayon thread pool.
+    ///
+    /// The tree is identical to the one `build` returns.
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel(leaves: Vec<Digest>, params: impl Into<TreeParams>) -> Self {
+        let params = params.into();
+        let mode = params.mode;
+        let leaf_count = leaves.len();
+        let (mut hashes, leaves) = match mode {
+            HashMode::Legacy => (leaves, Vec::new()),
+            _ => (
+                leaves.par_iter().map(|l| mode.hash_leaf::<H>(l)).collect(),
+                leaves,
+            ),
+        };
+        hashes.resize(node_count(leaf_count), Digest::default());
 
-            tree.hashes.append(&mut layer.clone());
-            tree.levels_indices
-                .push(hashes.len() + tree.levels_indices.last().unwrap());
-            return generate_next_layer(layer, tree);
+        let mut level_start = 0;
+        let mut level_len = leaf_count;
+        while level_len > 1 {
+            let next_len = level_len.div_ceil(2);
+            let (level, next) = hashes[level_start..].split_at_mut(level_len);
+            next[..next_len]
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(i, node)| {
+                    *node = params.parent::<H>(&level[2 * i], level.get(2 * i + 1));
+                });
+            level_start += level_len;
+            level_len = next_len;
         }
 
-        generate_next_layer(leaves, &mut tree);
-        tree.levels_indices.pop();
-        return tree;
+        Self::from_parts(hashes, leaves, leaf_count, params)
     }
 
-    /// Returns the direction (Left or Right) of a node at the given index.
-    fn get_node_direction(&self, index: usize) -> Direction {
-        if index % 2 == 0 {
-            Direction::Right
-        } else {
-            Direction::Left
-        }
+    /// Returns the hashing mode the tree was built with.
+    pub fn mode(&self) -> HashMode {
+        self.params.mode
+    }
+
+    /// Returns the parameters the tree was built with.
+    pub fn params(&self) -> TreeParams {
+        self.params
+    }
+
+    /// Returns the hash algorithm the tree was built with.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns a view borrowing the nodes of the tree, which generates its proofs.
+    pub fn view(&self) -> MerkleTreeView<'_, H> {
+        MerkleTreeView::new(&self.hashes, &self.leaves, self.leaf_count, self.params)
+    }
+
+    /// Writes the tree in a versioned and checksummed binary format, which
+    /// `from_bytes` and `MerkleTreeView::from_bytes` load back without
+    /// rehashing it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        merkle_tree_dump::encode(&Dump {
+            algorithm: H::ALGORITHM,
+            params: self.params,
+            leaf_count: self.leaf_count,
+            leaves: &self.leaves,
+            hashes: &self.hashes,
+        })
+    }
+
+    /// Loads a tree from a binary dump written by `to_bytes`, copying its nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::InvalidDump` if `bytes` is not a valid dump and
+    /// `MerkleError::AlgorithmMismatch` if the tree was not built with `H`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleError> {
+        let view = MerkleTreeView::<H>::from_bytes(bytes)?;
+        Ok(Self::from_parts(
+            view.hashes().to_vec(),
+            view.leaves().to_vec(),
+            view.leaf_count(),
+            view.params(),
+        ))
     }
 
     /// Returns the index of a hash in the list of hashes.
-    fn get_hash_index(&self, hash: &Hash) -> Option<usize> {
+    #[cfg(test)]
+    fn get_hash_index(&self, hash: &Digest) -> Option<usize> {
         self.hashes.iter().position(|h| h == hash)
     }
 
-    /// Generates a Merkle proof for a given hash.
-    // TODO: Move it to MerkleProof
-    pub fn proof(&self, hash: Hash) -> Result<MerkleProof, Box<dyn Error>> {
-        if self.hashes.is_empty() {
-            return Err("Tree is empty".into());
-        }
+    /// Generates a Merkle proof for a given leaf hash.
+    ///
+    /// When several leaves share the same hash, the proof is generated for the
+    /// first one; use `proof_for_index` to target a specific position.
+    pub fn proof(&self, hash: impl AsRef<[u8]>) -> Result<MerkleProof<H>, MerkleError> {
+        self.view().proof(hash)
+    }
 
-        let mut hash_index = self.get_hash_index(&hash).unwrap();
+    /// Generates a Merkle proof for the leaf at position `leaf_index`, walking
+    /// up one node per level.
+    pub fn proof_for_index(&self, leaf_index: usize) -> Result<MerkleProof<H>, MerkleError> {
+        self.view().proof_for_index(leaf_index)
+    }
 
-        let mut proof_elements = vec![MerkleNode {
-            hash: hash,
-            direction: self.get_node_direction(hash_index),
-        }];
+    /// Generates a single proof for all the leaves at the given positions.
+    ///
+    /// See `MerkleTreeView::multi_proof`.
+    pub fn multi_proof(&self, leaf_indices: &[usize]) -> Result<MerkleMultiProof<H>, MerkleError> {
+        self.view().multi_proof(leaf_indices)
+    }
 
-        for level in self.levels_indices.iter() {
-            let direction = self.get_node_direction(hash_index);
-            let index = match direction {
-                Direction::Right => hash_index + 1,
-                Direction::Left => hash_index - 1,
-            };
-            let sibling_node = MerkleNode {
-                hash: self.hashes[level + index].clone(),
-                direction: direction,
+    /// Generates a proof that the leaves at positions `range` are exactly the
+    /// leaves of the tree in this span.
+    ///
+    /// See `MerkleTreeView::range_proof`.
+    pub fn range_proof(&self, range: Range<usize>) -> Result<RangeProof<H>, MerkleError> {
+        self.view().range_proof(range)
+    }
+
+    /// Replaces the leaf at position `leaf_index` with `leaf`, only rehashing
+    /// the nodes on its path to the root, one per level.
+    ///
+    /// Returns the root hashes before and after the update, and the proof
+    /// linking them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` or `MerkleError::IndexOutOfRange` if
+    /// there is no leaf at `leaf_index`.
+    pub fn update_leaf(
+        &mut self,
+        leaf_index: usize,
+        leaf: Digest,
+    ) -> Result<LeafUpdate<H>, MerkleError> {
+        let proof = self.proof_for_index(leaf_index)?;
+        let old_root = *self.root().expect("the tree is not empty");
+
+        let params = self.params;
+        let mut node = match params.mode {
+            HashMode::Legacy => leaf,
+            _ => {
+                self.leaves[leaf_index] = leaf;
+                params.mode.hash_leaf::<H>(&leaf)
+            }
+        };
+        let mut index = leaf_index;
+        let mut level_start = 0;
+        let mut level_len = self.leaf_count;
+        loop {
+            self.hashes[level_start + index] = node;
+            if level_len == 1 {
+                break;
+            }
+            let sibling = index ^ 1;
+            node = match (index % 2, sibling < level_len) {
+                (0, true) => params.parent::<H>(&node, Some(&self.hashes[level_start + sibling])),
+                (0, false) => params.parent::<H>(&node, None),
+                _ => params.parent::<H>(&self.hashes[level_start + sibling], Some(&node)),
             };
-            proof_elements.push(sibling_node);
-            hash_index = hash_index / 2;
+            index /= 2;
+            level_start += level_len;
+            level_len = level_len.div_ceil(2);
+        }
+
+        let new_root = params.root::<H>(&node, self.leaf_count);
+        self.root = Some(new_root);
+        Ok(LeafUpdate {
+            old_root,
+            new_root,
+            proof: UpdateProof {
+                proof,
+                new_leaf: leaf,
+            },
+        })
+    }
+
+    /// Returns the positions of the leaves which differ between the tree and
+    /// `other`, in increasing order.
+    ///
+    /// Both trees are walked from the root, only descending into the subtrees
+    /// whose roots differ: finding `k` differing leaves among `n` compares
+    /// O(k log n) nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::ParamsMismatch` or `MerkleError::LeafCountMismatch`
+    /// if the trees are not built with the same parameters or do not have the
+    /// same number of leaves, as their nodes cannot be compared then.
+    pub fn diff(&self, other: &MerkleTree<H>) -> Result<Vec<usize>, MerkleError> {
+        if self.params != other.params {
+            return Err(MerkleError::ParamsMismatch {
+                expected: self.params,
+                actual: other.params,
+            });
+        }
+        if self.leaf_count != other.leaf_count {
+            return Err(MerkleError::LeafCountMismatch {
+                expected: self.leaf_count,
+                actual: other.leaf_count,
+            });
+        }
+
+        // start and length of each level, from the leaves to the top node
+        let mut levels = Vec::new();
+        let (mut level_start, mut level_len) = (0, self.leaf_count);
+        while level_len > 0 {
+            levels.push((level_start, level_len));
+            if level_len == 1 {
+                break;
+            }
+            level_start += level_len;
+            level_len = level_len.div_ceil(2);
+        }
+        let differs = |level: usize, index: usize| {
+            let node = levels[level].0 + index;
+            self.hashes[node] != other.hashes[node]
+        };
+
+        let mut differing_leaves = Vec::new();
+        let mut stack = match levels.len() {
+            0 => Vec::new(),
+            top => vec![(top - 1, 0)],
+        };
+        stack.retain(|&(level, index)| differs(level, index));
+        while let Some((level, index)) = stack.pop() {
+            if level == 0 {
+                differing_leaves.push(index);
+                continue;
+            }
+            // the right child first, so that leaves are popped from the left
+            for child in [2 * index + 1, 2 * index] {
+                if child < levels[level - 1].1 && differs(level - 1, child) {
+                    stack.push((level - 1, child));
+                }
+            }
         }
-        Ok(MerkleProof::new(proof_elements))
+        Ok(differing_leaves)
     }
 
     /// Returns the root hash of the Merkle tree.
-    pub fn root(&self) -> Option<&Hash> {
-        return self.hashes.last();
+    pub fn root(&self) -> Option<&Digest> {
+        self.root.as_ref()
     }
 
     /// Returns the hexadecimal representation of the root hash of the Merkle tree.
     pub fn root_hex(&self) -> Option<String> {
-        match self.root() {
-            Some(r) => Some(hex::encode(r)),
-            None => None,
-        }
+        self.root().map(hex::encode)
+    }
+
+    /// Returns the root hash of the Merkle tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, *root))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::crypto::{hash, Sha3_256};
 
     #[test]
     fn should_generate_correct_tree_shape() {
         let leaf_values = ["a", "b", "c", "d", "e", "f"];
-        let leaf_hashes: Vec<Hash> = leaf_values
-            .iter()
-            .map(|x| hash(&x.as_bytes().to_vec()))
-            .collect();
+        let leaf_hashes: Vec<Hash> = leaf_values.iter().map(|x| hash(x.as_bytes())).collect();
 
         let tree = MerkleTree::from_leaves(leaf_hashes);
 
         assert_eq!(tree.hashes.len(), 12);
     }
 
+    #[test]
+    fn should_reject_leaves_of_wrong_length() {
+        let leaf_hashes = vec![hash(b"a"), vec![0; 20]];
+
+        let result = MerkleTree::try_from_leaves_with_mode(leaf_hashes, HashMode::Legacy);
+
+        assert_eq!(
+            result.err(),
+            Some(MerkleError::HashLengthMismatch {
+                expected: 32,
+                actual: 20
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "leaves must be 32 bytes hashes")]
+    fn should_panic_on_leaves_of_wrong_length() {
+        MerkleTree::from_leaves(vec![vec![0; 31]]);
+    }
+
     #[test]
     fn should_generate_proof() {
         let leaf_values = ["a", "b", "c", "d", "e", "f"];
-        let leaf_hashes: Vec<Hash> = leaf_values
-            .iter()
-            .map(|x| hash(&x.as_bytes().to_vec()))
-            .collect();
+        let leaf_hashes: Vec<Hash> = leaf_values.iter().map(|x| hash(x.as_bytes())).collect();
 
         let tree = MerkleTree::from_leaves(leaf_hashes.clone());
         let proof = tree.proof(leaf_hashes[0].clone()).unwrap();
@@ -167,4 +449,213 @@ mod tests {
         assert_eq!(tree.get_hash_index(&proof.nodes[2].hash).unwrap(), 7);
         assert_eq!(tree.get_hash_index(&proof.nodes[3].hash).unwrap(), 10);
     }
+    #[test]
+    fn should_prefix_leaves_and_nodes_in_rfc6962_mode() {
+        let leaf_values = ["a", "b", "c", "d", "e", "f"];
+        let leaf_hashes: Vec<Hash> = leaf_values.iter().map(|x| hash(x.as_bytes())).collect();
+
+        let legacy = MerkleTree::from_leaves(leaf_hashes.clone());
+        let tree = MerkleTree::from_leaves_with_mode(leaf_hashes.clone(), HashMode::Rfc6962);
+
+        assert_eq!(tree.hashes.len(), 12);
+        assert_eq!(
+            tree.hashes[0],
+            hash(&[&[0x00], leaf_hashes[0].as_slice()].concat())
+        );
+        assert_ne!(tree.root(), legacy.root());
+
+        let proof = tree.proof(leaf_hashes[0].clone()).unwrap();
+        assert_eq!(proof.mode, HashMode::Rfc6962);
+        assert_eq!(proof.nodes[0].hash, leaf_hashes[0]);
+        assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+    }
+    #[test]
+    fn should_generate_valid_proofs_for_every_index() {
+        for leaf_count in 1..=17 {
+            let leaf_hashes: Vec<Hash> = (0..leaf_count)
+                .map(|i: u32| hash(&i.to_be_bytes()))
+                .collect();
+
+            for mode in [HashMode::Legacy, HashMode::Rfc6962] {
+                let tree = MerkleTree::from_leaves_with_mode(leaf_hashes.clone(), mode);
+                assert_eq!(tree.leaf_count(), leaf_count as usize);
+
+                for (i, leaf) in leaf_hashes.iter().enumerate() {
+                    let proof = tree.proof_for_index(i).unwrap();
+                    assert_eq!(proof.nodes[0].hash, *leaf);
+                    assert_eq!(proof.leaf_index, Some(i));
+                    assert_eq!(proof.leaf_count, Some(leaf_count as usize));
+                    assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_generate_proofs_for_duplicate_leaves() {
+        let leaf_hashes: Vec<Hash> = ["a", "b", "a", "c"]
+            .iter()
+            .map(|x| hash(x.as_bytes()))
+            .collect();
+
+        let tree = MerkleTree::from_leaves(leaf_hashes.clone());
+        let first = tree.proof_for_index(0).unwrap();
+        let second = tree.proof_for_index(2).unwrap();
+
+        assert_eq!(first.nodes[0].hash, second.nodes[0].hash);
+        assert_ne!(first.nodes[1].hash, second.nodes[1].hash);
+        assert_eq!(second.leaf_index, Some(2));
+        assert_eq!(second.compute_root().unwrap(), *tree.root().unwrap());
+        assert_eq!(
+            tree.proof(leaf_hashes[2].clone()).unwrap().leaf_index,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn should_fail_on_unknown_leaf() {
+        let leaf_hashes: Vec<Hash> = ["a", "b", "c"].iter().map(|x| hash(x.as_bytes())).collect();
+        let tree = MerkleTree::from_leaves(leaf_hashes);
+
+        assert_eq!(
+            tree.proof(hash(b"d")).unwrap_err(),
+            MerkleError::LeafNotFound
+        );
+        assert_eq!(
+            tree.proof(*tree.root().unwrap()).unwrap_err(),
+            MerkleError::LeafNotFound
+        );
+        assert_eq!(
+            tree.proof_for_index(3).unwrap_err(),
+            MerkleError::IndexOutOfRange {
+                index: 3,
+                leaf_count: 3
+            }
+        );
+        assert_eq!(
+            MerkleTree::from_leaves(vec![])
+                .proof_for_index(0)
+                .unwrap_err(),
+            MerkleError::EmptyTree
+        );
+    }
+
+    #[test]
+    fn should_update_leaf_along_its_path() {
+        for leaf_count in [1, 2, 3, 6, 17] {
+            let mut leaves: Vec<Digest> = (0..leaf_count)
+                .map(|i: usize| Sha3_256::hash(&i.to_le_bytes()))
+                .collect();
+            for mode in [HashMode::Legacy, HashMode::Rfc6962] {
+                let mut tree = MerkleTree::<Sha3_256>::build(leaves.clone(), mode);
+                for index in 0..leaf_count {
+                    let old_root = *tree.root().unwrap();
+                    let leaf = Sha3_256::hash(format!("{mode} {index}").as_bytes());
+                    let update = tree.update_leaf(index, leaf).unwrap();
+                    leaves[index] = leaf;
+
+                    // the tree is the one built from the updated leaves
+                    let rebuilt = MerkleTree::<Sha3_256>::build(leaves.clone(), mode);
+                    assert_eq!(tree.to_bytes(), rebuilt.to_bytes(), "{leaf_count} leaves");
+                    assert_eq!(update.old_root, old_root);
+                    assert_eq!(update.new_root, *rebuilt.root().unwrap());
+                    assert_eq!(
+                        update.proof.verify(&update.old_root, &update.new_root),
+                        Ok(())
+                    );
+                }
+            }
+        }
+
+        let mut tree = MerkleTree::<Sha3_256>::build(vec![], HashMode::Rfc6962);
+        assert_eq!(
+            tree.update_leaf(0, Digest::default()).unwrap_err(),
+            MerkleError::EmptyTree
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn should_build_same_tree_in_parallel() {
+        for leaf_count in [0, 1, 2, 3, 7, 64, 1000, 4097] {
+            let leaves: Vec<Digest> = (0..leaf_count)
+                .map(|i: usize| Sha3_256::hash(&i.to_le_bytes()))
+                .collect();
+            let promote = TreeParams {
+                mode: HashMode::Rfc6962,
+                odd_node: crate::OddNodeStrategy::Promote,
+                commit_leaf_count: true,
+            };
+            for params in [HashMode::Legacy.into(), HashMode::Rfc6962.into(), promote] {
+                let tree = MerkleTree::<Sha3_256>::build(leaves.clone(), params);
+                let parallel = MerkleTree::<Sha3_256>::build_parallel(leaves.clone(), params);
+                assert_eq!(parallel.hashes, tree.hashes, "{leaf_count} leaves");
+                assert_eq!(parallel.to_bytes(), tree.to_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn should_find_differing_leaves() {
+        let leaves: Vec<Digest> = (0..37u8).map(|i| Sha3_256::hash(&[i])).collect();
+        for odd_node in [
+            crate::OddNodeStrategy::Duplicate,
+            crate::OddNodeStrategy::Promote,
+        ] {
+            let params = TreeParams {
+                mode: HashMode::Rfc6962,
+                odd_node,
+                commit_leaf_count: true,
+            };
+            let tree = MerkleTree::<Sha3_256>::build(leaves.clone(), params);
+            for differing in [vec![], vec![0], vec![36], vec![3, 4, 17, 35, 36]] {
+                let mut altered = leaves.clone();
+                for &index in &differing {
+                    altered[index] = Sha3_256::hash(b"altered");
+                }
+                let other = MerkleTree::<Sha3_256>::build(altered, params);
+                assert_eq!(tree.diff(&other), Ok(differing.clone()));
+                assert_eq!(other.diff(&tree), Ok(differing));
+            }
+        }
+
+        let tree = MerkleTree::<Sha3_256>::build(leaves.clone(), HashMode::Rfc6962);
+        let shorter = MerkleTree::<Sha3_256>::build(leaves[1..].to_vec(), HashMode::Rfc6962);
+        assert_eq!(
+            tree.diff(&shorter),
+            Err(MerkleError::LeafCountMismatch {
+                expected: 37,
+                actual: 36
+            })
+        );
+        let legacy = MerkleTree::<Sha3_256>::build(leaves, HashMode::Legacy);
+        assert!(matches!(
+            tree.diff(&legacy),
+            Err(MerkleError::ParamsMismatch { .. })
+        ));
+        let empty = MerkleTree::<Sha3_256>::build(Vec::new(), HashMode::Legacy);
+        assert_eq!(empty.diff(&empty), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn should_build_tree_with_other_hashers() {
+        let leaf_hashes: Vec<Digest> = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .map(|x| Sha3_256::hash(x.as_bytes()))
+            .collect();
+
+        let tree = MerkleTree::<Sha3_256>::build(leaf_hashes.clone(), HashMode::Rfc6962);
+        let sha256_tree = MerkleTree::<Sha256>::build(leaf_hashes.clone(), HashMode::Rfc6962);
+
+        assert_eq!(tree.algorithm(), Algorithm::Sha3_256);
+        assert_eq!(tree.hashes.len(), 12);
+        assert_ne!(tree.root(), sha256_tree.root());
+        assert_eq!(
+            tree.tagged_root().unwrap().to_string(),
+            format!("sha3-256:{}", tree.root_hex().unwrap())
+        );
+
+        let proof = tree.proof(leaf_hashes[3]).unwrap();
+        assert_eq!(proof.compute_root().unwrap(), *tree.root().unwrap());
+    }
 }
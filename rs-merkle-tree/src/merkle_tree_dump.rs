@@ -0,0 +1,183 @@
+//! Binary dump format of `MerkleTree`.
+//!
+//! All integers are little-endian:
+//!
+//! | Offset | Size | Content                                                 |
+//! | ------ | ---- | ------------------------------------------------------- |
+//! | 0      | 4    | magic bytes `MKTR`                                      |
+//! | 4      | 1    | format version, currently 1                             |
+//! | 5      | 1    | algorithm: 0 SHA-256, 1 SHA-512/256, 2 SHA3-256,        |
+//! |        |      | 3 Keccak-256                                            |
+//! | 6      | 1    | hashing mode: 0 legacy, 1 RFC 6962, 2 sorted pair       |
+//! | 7      | 1    | odd nodes: bits 0-1 strategy, bit 2 leaf count          |
+//! | 8      | 8    | number of leaves                                        |
+//! | 16     | ...  | leaves the tree was built from, unless legacy           |
+//! | ...    | ...  | nodes, level by level from the leaves to the root       |
+//! | ...    | 32   | SHA-256 checksum of all the previous bytes              |
+
+use crate::error::MerkleError;
+use crate::merkle_tree_view::node_count;
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256, DIGEST_SIZE};
+use alloc::vec::Vec;
+
+const MAGIC: &[u8; 4] = b"MKTR";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 16;
+
+/// Contents of a dump, borrowed from its bytes.
+pub(crate) struct Dump<'a> {
+    pub algorithm: Algorithm,
+    pub params: TreeParams,
+    pub leaf_count: usize,
+    pub leaves: &'a [Digest],
+    pub hashes: &'a [Digest],
+}
+
+/// Reinterprets `bytes` as digests, without copying them.
+fn as_digests(bytes: &[u8]) -> &[Digest] {
+    let (digests, _) = bytes.as_chunks::<DIGEST_SIZE>();
+    // SAFETY: `Digest` is a `repr(transparent)` wrapper of `[u8; DIGEST_SIZE]`,
+    // so both slices have the same layout.
+    unsafe { core::slice::from_raw_parts(digests.as_ptr().cast::<Digest>(), digests.len()) }
+}
+
+/// Writes the dump of a tree.
+pub(crate) fn encode(dump: &Dump) -> Vec<u8> {
+    let size = HEADER_SIZE + (dump.leaves.len() + dump.hashes.len() + 1) * DIGEST_SIZE;
+    let mut bytes = Vec::with_capacity(size);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.push(dump.algorithm.id());
+    bytes.push(dump.params.mode.id());
+    bytes.push(dump.params.flags());
+    bytes.extend_from_slice(&(dump.leaf_count as u64).to_le_bytes());
+    for digest in dump.leaves.iter().chain(dump.hashes) {
+        bytes.extend_from_slice(digest.as_bytes());
+    }
+    let checksum = Sha256::hash(&bytes);
+    bytes.extend_from_slice(checksum.as_bytes());
+    bytes
+}
+
+/// Reads the dump of a tree, checking its header, size and checksum.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Dump<'_>, MerkleError> {
+    if bytes.len() < HEADER_SIZE + DIGEST_SIZE || &bytes[..4] != MAGIC {
+        return Err(MerkleError::InvalidDump("not a Merkle tree dump"));
+    }
+    if bytes[4] != VERSION {
+        return Err(MerkleError::InvalidDump("unsupported version"));
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - DIGEST_SIZE);
+    if Sha256::hash(content).as_ref() != checksum {
+        return Err(MerkleError::InvalidDump("checksum mismatch"));
+    }
+
+    let algorithm =
+        Algorithm::from_id(bytes[5]).ok_or(MerkleError::InvalidDump("unknown algorithm"))?;
+    let mode =
+        HashMode::from_id(bytes[6]).ok_or(MerkleError::InvalidDump("unknown hashing mode"))?;
+    let params = TreeParams::from_flags(mode, bytes[7])
+        .ok_or(MerkleError::InvalidDump("unknown odd node strategy"))?;
+    let leaf_count = u64::from_le_bytes(bytes[8..16].try_into().expect("8 bytes"));
+    let body = &content[HEADER_SIZE..];
+    let digest_count = body.len() / DIGEST_SIZE;
+    let leaf_count = match usize::try_from(leaf_count) {
+        Ok(leaf_count) if leaf_count <= digest_count => leaf_count,
+        _ => {
+            return Err(MerkleError::InvalidDump(
+                "size does not match the leaf count",
+            ))
+        }
+    };
+    let stored_leaves = match mode {
+        HashMode::Legacy => 0,
+        _ => leaf_count,
+    };
+    if body.len() != (stored_leaves + node_count(leaf_count)) * DIGEST_SIZE {
+        return Err(MerkleError::InvalidDump(
+            "size does not match the leaf count",
+        ));
+    }
+
+    let (leaves, hashes) = as_digests(body).split_at(stored_leaves);
+    Ok(Dump {
+        algorithm,
+        params,
+        leaf_count,
+        leaves,
+        hashes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle_tree::MerkleTree;
+    use crate::merkle_tree_view::MerkleTreeView;
+    use crate::utils::crypto::{Digest, HashMode, Keccak256, MerkleHasher, Sha256, Sha3_256};
+    use crate::MerkleError;
+
+    fn tree(leaf_count: usize, mode: HashMode) -> MerkleTree<Sha3_256> {
+        let leaves: Vec<Digest> = (0..leaf_count)
+            .map(|i| Sha3_256::hash(&i.to_le_bytes()))
+            .collect();
+        MerkleTree::build(leaves, mode)
+    }
+
+    #[test]
+    fn should_load_dump_without_rehashing() {
+        for leaf_count in [0, 1, 2, 7, 64] {
+            for mode in [HashMode::Legacy, HashMode::Rfc6962, HashMode::SortedPair] {
+                let tree = tree(leaf_count, mode);
+                let bytes = tree.to_bytes();
+
+                let loaded = MerkleTree::<Sha3_256>::from_bytes(&bytes).unwrap();
+                assert_eq!(loaded.hashes, tree.hashes);
+                assert_eq!(loaded.mode(), mode);
+
+                let view = MerkleTreeView::<Sha3_256>::from_bytes(&bytes).unwrap();
+                assert_eq!(view.root(), tree.root());
+                if leaf_count > 0 {
+                    let index = leaf_count / 2;
+                    assert_eq!(
+                        view.proof_for_index(index).unwrap().compute_root().unwrap(),
+                        *tree.root().unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_write_algorithm_and_mode_ids() {
+        let leaves = vec![Keccak256::hash(b"a"), Keccak256::hash(b"b")];
+        let bytes = MerkleTree::<Keccak256>::build(leaves, HashMode::SortedPair).to_bytes();
+        assert_eq!(bytes[5..7], [3, 2]);
+        assert!(MerkleTree::<Keccak256>::from_bytes(&bytes).is_ok());
+    }
+
+    #[test]
+    fn should_reject_corrupted_dumps() {
+        let bytes = tree(5, HashMode::Rfc6962).to_bytes();
+
+        let mut corrupted = bytes.clone();
+        corrupted[40] ^= 1;
+        assert_eq!(
+            MerkleTree::<Sha3_256>::from_bytes(&corrupted).err(),
+            Some(MerkleError::InvalidDump("checksum mismatch"))
+        );
+
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(
+            MerkleTree::<Sha3_256>::from_bytes(&newer).err(),
+            Some(MerkleError::InvalidDump("unsupported version"))
+        );
+
+        assert!(MerkleTree::<Sha3_256>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(matches!(
+            MerkleTree::<Sha256>::from_bytes(&bytes),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+}
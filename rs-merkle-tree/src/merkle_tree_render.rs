@@ -0,0 +1,423 @@
+use crate::error::MerkleError;
+use crate::merkle_tree::MerkleTree;
+use crate::merkle_tree_view::MerkleTreeView;
+use crate::utils::crypto::{Digest, HashMode, MerkleHasher};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Options of the ASCII and Graphviz renderings of a tree.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RenderOptions {
+    /// Number of hexadecimal characters the hashes are truncated to.
+    pub hash_len: usize,
+    /// Position of the leaf whose proof is highlighted: the nodes on its path
+    /// to the root, and their siblings held by the proof.
+    pub highlight: Option<usize>,
+    /// Labels shown next to the leaves, e.g. the names of the files they stand for.
+    pub leaf_labels: Vec<String>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            hash_len: 8,
+            highlight: None,
+            leaf_labels: Vec::new(),
+        }
+    }
+}
+
+/// Role of a node in the highlighted proof.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    None,
+    Path,
+    Sibling,
+}
+
+impl Mark {
+    /// Returns the role of the node at position `index` of `level` in the
+    /// proof of the leaf at position `highlight`.
+    fn of(highlight: Option<usize>, level: usize, index: usize) -> Self {
+        match highlight {
+            Some(leaf) if leaf >> level == index => Mark::Path,
+            Some(leaf) if (leaf >> level) ^ 1 == index => Mark::Sibling,
+            _ => Mark::None,
+        }
+    }
+
+    fn ascii(self) -> &'static str {
+        match self {
+            Mark::None => "",
+            Mark::Path => " [path]",
+            Mark::Sibling => " [proof]",
+        }
+    }
+
+    fn dot(self) -> &'static str {
+        match self {
+            Mark::None => "",
+            Mark::Path => ", style=filled, fillcolor=salmon",
+            Mark::Sibling => ", style=filled, fillcolor=lightblue",
+        }
+    }
+}
+
+/// Returns the first `len` hexadecimal characters of `hash`.
+fn short_hex(hash: &Digest, len: usize) -> String {
+    let mut hex = hex::encode(hash);
+    hex.truncate(len);
+    hex
+}
+
+/// Escapes a string to be quoted in a Graphviz file.
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl<H: MerkleHasher> MerkleTreeView<'_, H> {
+    /// Renders the tree as indented ASCII text, from the root down to the
+    /// leaves, with hashes truncated to 8 hexadecimal characters.
+    pub fn to_ascii(&self) -> String {
+        self.render_ascii(&self.levels(), &RenderOptions::default())
+    }
+
+    /// Renders the tree as indented ASCII text according to `options`. Nodes
+    /// on the path of the highlighted leaf are marked `[path]`, their siblings
+    /// held by its proof `[proof]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` or `MerkleError::IndexOutOfRange` if
+    /// the highlighted leaf is not in the tree.
+    pub fn to_ascii_with(&self, options: &RenderOptions) -> Result<String, MerkleError> {
+        self.check_highlight(options)?;
+        Ok(self.render_ascii(&self.levels(), options))
+    }
+
+    /// Renders the tree as a Graphviz digraph, one rank per level, with hashes
+    /// truncated to 8 hexadecimal characters.
+    pub fn to_dot(&self) -> String {
+        self.render_dot(&self.levels(), &RenderOptions::default())
+    }
+
+    /// Renders the tree as a Graphviz digraph according to `options`. Nodes
+    /// on the path of the highlighted leaf are filled in salmon, their
+    /// siblings held by its proof in light blue.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` or `MerkleError::IndexOutOfRange` if
+    /// the highlighted leaf is not in the tree.
+    pub fn to_dot_with(&self, options: &RenderOptions) -> Result<String, MerkleError> {
+        self.check_highlight(options)?;
+        Ok(self.render_dot(&self.levels(), options))
+    }
+
+    fn check_highlight(&self, options: &RenderOptions) -> Result<(), MerkleError> {
+        match options.highlight {
+            Some(_) if self.leaf_count() == 0 => Err(MerkleError::EmptyTree),
+            Some(index) if index >= self.leaf_count() => Err(MerkleError::IndexOutOfRange {
+                index,
+                leaf_count: self.leaf_count(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{} tree of {} leaves, {}",
+            self.algorithm(),
+            self.leaf_count(),
+            self.params()
+        )
+    }
+
+    /// Returns the text of a node: its hash, followed for the leaves by the
+    /// leaf it was hashed from, unless they are the same.
+    fn node_text(
+        &self,
+        levels: &[&[Digest]],
+        options: &RenderOptions,
+        level: usize,
+        index: usize,
+        separator: &str,
+    ) -> String {
+        let mut text = short_hex(&levels[level][index], options.hash_len);
+        if level == 0 && self.mode() != HashMode::Legacy {
+            let leaf = short_hex(self.leaf(index), options.hash_len);
+            let _ = write!(text, "{separator}leaf {leaf}");
+        }
+        text
+    }
+
+    /// Returns the positions of the children of a node on the level below.
+    fn children(levels: &[&[Digest]], level: usize, index: usize) -> Vec<usize> {
+        match level {
+            0 => Vec::new(),
+            _ => [2 * index, 2 * index + 1]
+                .into_iter()
+                .filter(|child| *child < levels[level - 1].len())
+                .collect(),
+        }
+    }
+
+    fn render_ascii(&self, levels: &[&[Digest]], options: &RenderOptions) -> String {
+        let mut out = self.title();
+        out.push('\n');
+        let top = match levels.len().checked_sub(1) {
+            Some(top) => top,
+            None => return out,
+        };
+        if self.params().commit_leaf_count {
+            let root = self.root().expect("non empty tree");
+            let mark = Mark::of(options.highlight, top, 0);
+            let _ = writeln!(
+                out,
+                "root {}{}",
+                short_hex(root, options.hash_len),
+                mark.ascii()
+            );
+            self.write_ascii_node(&mut out, levels, options, (top, 0), "`-- ", "    ");
+        } else {
+            self.write_ascii_node(&mut out, levels, options, (top, 0), "", "");
+        }
+        out
+    }
+
+    /// Writes the line of a node, prefixed with `prefix`, then the lines of
+    /// its children prefixed with `child_prefix`.
+    fn write_ascii_node(
+        &self,
+        out: &mut String,
+        levels: &[&[Digest]],
+        options: &RenderOptions,
+        (level, index): (usize, usize),
+        prefix: &str,
+        child_prefix: &str,
+    ) {
+        let text = self.node_text(levels, options, level, index, " ");
+        let mark = Mark::of(options.highlight, level, index);
+        let _ = write!(out, "{prefix}{text}{}", mark.ascii());
+        match options.leaf_labels.get(index).filter(|_| level == 0) {
+            Some(label) => {
+                let _ = writeln!(out, "  {label}");
+            }
+            None => out.push('\n'),
+        }
+
+        let children = Self::children(levels, level, index);
+        for (i, child) in children.iter().enumerate() {
+            let (connector, indent) = match i + 1 == children.len() {
+                true => ("`-- ", "    "),
+                false => ("|-- ", "|   "),
+            };
+            self.write_ascii_node(
+                out,
+                levels,
+                options,
+                (level - 1, *child),
+                &format!("{child_prefix}{connector}"),
+                &format!("{child_prefix}{indent}"),
+            );
+        }
+    }
+
+    fn render_dot(&self, levels: &[&[Digest]], options: &RenderOptions) -> String {
+        let mut out = String::from("digraph merkle_tree {\n");
+        let _ = writeln!(out, "    label=\"{}\";", escape_dot(&self.title()));
+        out.push_str("    labelloc=t;\n");
+        out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        let top = match levels.len().checked_sub(1) {
+            Some(top) => top,
+            None => return out + "}\n",
+        };
+
+        let on_path = |level, index| Mark::of(options.highlight, level, index) == Mark::Path;
+        if self.params().commit_leaf_count {
+            let root = short_hex(self.root().expect("non empty tree"), options.hash_len);
+            let mark = Mark::of(options.highlight, top, 0);
+            let _ = writeln!(out, "    root [label=\"root\\n{root}\"{}];", mark.dot());
+            let _ = writeln!(out, "    root -> n{top}_0{};", edge_style(on_path(top, 0)));
+        }
+        for level in (0..=top).rev() {
+            out.push_str("    { rank=same;");
+            for index in 0..levels[level].len() {
+                let mut text = self.node_text(levels, options, level, index, "\n");
+                if let Some(label) = options.leaf_labels.get(index).filter(|_| level == 0) {
+                    text = format!("{text}\n{label}");
+                }
+                let mark = Mark::of(options.highlight, level, index);
+                let _ = write!(
+                    out,
+                    " n{level}_{index} [label=\"{}\"{}];",
+                    escape_dot(&text),
+                    mark.dot()
+                );
+            }
+            out.push_str(" }\n");
+        }
+        for level in (1..=top).rev() {
+            for index in 0..levels[level].len() {
+                for child in Self::children(levels, level, index) {
+                    let highlighted = on_path(level, index) && on_path(level - 1, child);
+                    let _ = writeln!(
+                        out,
+                        "    n{level}_{index} -> n{}_{child}{};",
+                        level - 1,
+                        edge_style(highlighted)
+                    );
+                }
+            }
+        }
+        out + "}\n"
+    }
+}
+
+/// Returns the attributes of an edge, drawn in red on the highlighted path.
+fn edge_style(highlighted: bool) -> &'static str {
+    match highlighted {
+        true => " [color=red, penwidth=2]",
+        false => "",
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    /// Renders the tree as indented ASCII text, see `MerkleTreeView::to_ascii`.
+    pub fn to_ascii(&self) -> String {
+        self.view().to_ascii()
+    }
+
+    /// Renders the tree as indented ASCII text according to `options`, see
+    /// `MerkleTreeView::to_ascii_with`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the highlighted leaf is not in the tree.
+    pub fn to_ascii_with(&self, options: &RenderOptions) -> Result<String, MerkleError> {
+        self.view().to_ascii_with(options)
+    }
+
+    /// Renders the tree as a Graphviz digraph, see `MerkleTreeView::to_dot`.
+    pub fn to_dot(&self) -> String {
+        self.view().to_dot()
+    }
+
+    /// Renders the tree as a Graphviz digraph according to `options`, see
+    /// `MerkleTreeView::to_dot_with`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the highlighted leaf is not in the tree.
+    pub fn to_dot_with(&self, options: &RenderOptions) -> Result<String, MerkleError> {
+        self.view().to_dot_with(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree_params::{OddNodeStrategy, TreeParams};
+    use crate::utils::crypto::Sha3_256;
+
+    fn tree(leaf_count: u8, params: impl Into<TreeParams>) -> MerkleTree<Sha3_256> {
+        let leaves = (0..leaf_count).map(|i| Sha3_256::hash(&[i])).collect();
+        MerkleTree::build(leaves, params)
+    }
+
+    #[test]
+    fn should_render_ascii_tree() {
+        let tree = tree(3, HashMode::Legacy);
+        let hashes: Vec<_> = tree.hashes.iter().map(|h| short_hex(h, 8)).collect();
+        let expected = format!(
+            "sha3-256 tree of 3 leaves, legacy, duplicate odd nodes\n\
+             {}\n\
+             |-- {}\n\
+             |   |-- {}\n\
+             |   `-- {}\n\
+             `-- {}\n    \
+                 `-- {}\n",
+            hashes[5], hashes[3], hashes[0], hashes[1], hashes[4], hashes[2]
+        );
+        assert_eq!(tree.to_ascii(), expected);
+        assert_eq!(
+            tree.to_ascii_with(&RenderOptions::default()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_highlight_proof_path() {
+        let params = TreeParams {
+            mode: HashMode::Rfc6962,
+            odd_node: OddNodeStrategy::Promote,
+            commit_leaf_count: true,
+        };
+        let tree = tree(5, params);
+        let options = RenderOptions {
+            hash_len: 64,
+            highlight: Some(1),
+            leaf_labels: ["a", "b", "c", "d", "e"].map(String::from).to_vec(),
+        };
+        let ascii = tree.to_ascii_with(&options).unwrap();
+        let lines: Vec<_> = ascii.lines().collect();
+        assert_eq!(lines.len(), 2 + 11);
+        assert_eq!(
+            lines[1],
+            format!("root {} [path]", tree.root_hex().unwrap())
+        );
+        let leaf = hex::encode(Sha3_256::hash(&[1]));
+        assert!(lines
+            .iter()
+            .any(|l| l.ends_with(&format!("leaf {leaf} [path]  b"))));
+        assert_eq!(lines.iter().filter(|l| l.contains("[path]")).count(), 5);
+
+        // the proof holds one sibling per marked node, the promoted leaf included
+        let proof = tree.proof_for_index(1).unwrap();
+        let siblings = proof.sibling_hashes();
+        assert_eq!(siblings.len(), 3);
+        for sibling in siblings.iter().map(hex::encode) {
+            assert!(lines
+                .iter()
+                .any(|l| l.contains(&sibling) && l.contains("[proof]")));
+        }
+
+        let dot = tree.to_dot_with(&options).unwrap();
+        assert!(dot.starts_with("digraph merkle_tree {\n"));
+        assert_eq!(dot.matches("rank=same").count(), 4);
+        assert_eq!(dot.matches("fillcolor=salmon").count(), 5);
+        assert_eq!(dot.matches("fillcolor=lightblue").count(), 3);
+        assert_eq!(dot.matches("color=red").count(), 4);
+        assert!(dot.contains(&format!(
+            "n0_1 [label=\"{}\\nleaf {leaf}\\nb\"",
+            hex::encode(tree.hashes[1])
+        )));
+    }
+
+    #[test]
+    fn should_reject_highlighted_leaf_out_of_range() {
+        let options = RenderOptions {
+            highlight: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            tree(3, HashMode::Rfc6962).to_dot_with(&options),
+            Err(MerkleError::IndexOutOfRange {
+                index: 3,
+                leaf_count: 3
+            })
+        );
+        let empty = tree(0, HashMode::Rfc6962);
+        assert_eq!(empty.to_ascii_with(&options), Err(MerkleError::EmptyTree));
+        assert_eq!(
+            empty.to_ascii(),
+            "sha3-256 tree of 0 leaves, rfc6962, duplicate odd nodes\n"
+        );
+        assert!(empty.to_dot().ends_with("];\n}\n"));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::error::MerkleError;
+use crate::merkle_multi_proof::MerkleMultiProof;
+use crate::merkle_proof::MerkleProof;
+use crate::merkle_range_proof::RangeProof;
+use crate::merkle_root::MerkleRoot;
+use crate::merkle_tree::{Direction, MerkleNode};
+use crate::merkle_tree_dump;
+use crate::tree_params::TreeParams;
+use crate::utils::crypto::{Algorithm, Digest, HashMode, MerkleHasher, Sha256};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+/// Returns the number of nodes of a tree built from `leaf_count` leaves.
+pub(crate) fn node_count(leaf_count: usize) -> usize {
+    let mut count = leaf_count;
+    let mut level_len = leaf_count;
+    while level_len > 1 {
+        level_len = level_len.div_ceil(2);
+        count += level_len;
+    }
+    count
+}
+
+/// Returns the index of the first node of each level of a tree built from
+/// `leaf_count` leaves, the root level excepted.
+fn levels_indices(leaf_count: usize) -> Vec<usize> {
+    let mut levels_indices = Vec::new();
+    let mut level_start = 0;
+    let mut level_len = leaf_count;
+    while level_len > 1 {
+        levels_indices.push(level_start);
+        level_start += level_len;
+        level_len = level_len.div_ceil(2);
+    }
+    levels_indices
+}
+
+/// Represents a Merkle tree whose nodes are borrowed, either from a
+/// `MerkleTree` or straight from a binary dump, without copying nor rehashing
+/// them.
+///
+/// It generates the same proofs as the tree it was built from.
+pub struct MerkleTreeView<'a, H: MerkleHasher = Sha256> {
+    hashes: &'a [Digest],
+    leaves: &'a [Digest],
+    leaf_count: usize,
+    levels_indices: Vec<usize>,
+    params: TreeParams,
+    root: Option<Digest>,
+    _hasher: PhantomData<H>,
+}
+
+impl<'a, H: MerkleHasher> MerkleTreeView<'a, H> {
+    pub(crate) fn new(
+        hashes: &'a [Digest],
+        leaves: &'a [Digest],
+        leaf_count: usize,
+        params: TreeParams,
+    ) -> Self {
+        Self {
+            hashes,
+            leaves,
+            leaf_count,
+            levels_indices: levels_indices(leaf_count),
+            params,
+            root: hashes.last().map(|top| params.root::<H>(top, leaf_count)),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Loads a tree from a binary dump written by `MerkleTree::to_bytes`,
+    /// borrowing its nodes from `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::InvalidDump` if `bytes` is not a valid dump and
+    /// `MerkleError::AlgorithmMismatch` if the tree was not built with `H`.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, MerkleError> {
+        let dump = merkle_tree_dump::decode(bytes)?;
+        if dump.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: dump.algorithm,
+            });
+        }
+        Ok(Self::new(
+            dump.hashes,
+            dump.leaves,
+            dump.leaf_count,
+            dump.params,
+        ))
+    }
+
+    /// Returns the nodes of the tree, level by level from the leaves to the root.
+    pub fn hashes(&self) -> &'a [Digest] {
+        self.hashes
+    }
+
+    /// Returns the leaves the tree was built from, if they differ from its
+    /// leaf level.
+    pub(crate) fn leaves(&self) -> &'a [Digest] {
+        self.leaves
+    }
+
+    /// Returns the hashing mode the tree was built with.
+    pub fn mode(&self) -> HashMode {
+        self.params.mode
+    }
+
+    /// Returns the parameters the tree was built with.
+    pub fn params(&self) -> TreeParams {
+        self.params
+    }
+
+    /// Returns the hash algorithm the tree was built with.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns the leaves of the tree in order, as given when building it.
+    pub fn iter_leaves(&self) -> impl Iterator<Item = &'a Digest> + '_ {
+        (0..self.leaf_count).map(|index| self.leaf(index))
+    }
+
+    /// Returns the leaf at position `index`, as given when building the tree.
+    pub(crate) fn leaf(&self, index: usize) -> &'a Digest {
+        match self.params.mode {
+            HashMode::Legacy => &self.hashes[index],
+            _ => &self.leaves[index],
+        }
+    }
+
+    /// Returns the nodes of each level, from the leaves to the top node.
+    pub(crate) fn levels(&self) -> Vec<&'a [Digest]> {
+        if self.hashes.is_empty() {
+            return Vec::new();
+        }
+        let mut levels: Vec<_> = (self.levels_indices.iter().enumerate())
+            .map(|(level, start)| &self.hashes[*start..*start + self.level_len(level)])
+            .collect();
+        levels.push(&self.hashes[self.hashes.len() - 1..]);
+        levels
+    }
+
+    /// Returns the number of nodes on the given level, the leaves being level 0.
+    fn level_len(&self, level: usize) -> usize {
+        let end = match self.levels_indices.get(level + 1) {
+            Some(next) => *next,
+            None => self.hashes.len() - 1,
+        };
+        end - self.levels_indices[level]
+    }
+
+    /// Returns the direction (Left or Right) of a node at the given index.
+    fn get_node_direction(&self, index: usize) -> Direction {
+        if index.is_multiple_of(2) {
+            Direction::Right
+        } else {
+            Direction::Left
+        }
+    }
+
+    /// Returns the index of the first leaf equal to `leaf`.
+    fn get_leaf_index(&self, leaf: &[u8]) -> Option<usize> {
+        (0..self.leaf_count).find(|i| self.leaf(*i).as_ref() == leaf)
+    }
+
+    /// Generates a Merkle proof for a given leaf hash.
+    ///
+    /// When several leaves share the same hash, the proof is generated for the
+    /// first one; use `proof_for_index` to target a specific position.
+    pub fn proof(&self, hash: impl AsRef<[u8]>) -> Result<MerkleProof<H>, MerkleError> {
+        if self.hashes.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+
+        match self.get_leaf_index(hash.as_ref()) {
+            Some(index) => self.proof_for_index(index),
+            None => Err(MerkleError::LeafNotFound),
+        }
+    }
+
+    /// Generates a Merkle proof for the leaf at position `leaf_index`, walking
+    /// up one node per level.
+    pub fn proof_for_index(&self, leaf_index: usize) -> Result<MerkleProof<H>, MerkleError> {
+        if self.hashes.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        if leaf_index >= self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_index,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let mut proof_elements = vec![MerkleNode {
+            hash: *self.leaf(leaf_index),
+            direction: self.get_node_direction(leaf_index),
+        }];
+
+        let mut hash_index = leaf_index;
+        for (level, level_start) in self.levels_indices.iter().enumerate() {
+            let direction = self.get_node_direction(hash_index);
+            let sibling = match direction {
+                Direction::Right if hash_index + 1 == self.level_len(level) => self
+                    .params
+                    .unpaired_sibling(&self.hashes[level_start + hash_index]),
+                Direction::Right => Some(self.hashes[level_start + hash_index + 1]),
+                Direction::Left => Some(self.hashes[level_start + hash_index - 1]),
+            };
+            // a promoted node has no sibling
+            if let Some(hash) = sibling {
+                proof_elements.push(MerkleNode { hash, direction });
+            }
+            hash_index /= 2;
+        }
+        Ok(MerkleProof::with_position(
+            proof_elements,
+            self.params,
+            leaf_index,
+            self.leaf_count(),
+        ))
+    }
+
+    /// Generates a single proof for all the leaves at the given positions.
+    ///
+    /// Indices are sorted and deduplicated; the proof only holds the siblings
+    /// which cannot be computed from the proven leaves.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` if the tree has no leaves,
+    /// `MerkleError::IndexOutOfRange` if one of the indices is not lower than
+    /// the number of leaves and `MerkleError::MalformedProof` if no index is given.
+    pub fn multi_proof(&self, leaf_indices: &[usize]) -> Result<MerkleMultiProof<H>, MerkleError> {
+        if self.hashes.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        let mut indices = leaf_indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        match indices.last() {
+            None => return Err(MerkleError::MalformedProof("no leaf to prove")),
+            Some(index) if *index >= self.leaf_count() => {
+                return Err(MerkleError::IndexOutOfRange {
+                    index: *index,
+                    leaf_count: self.leaf_count(),
+                })
+            }
+            _ => {}
+        }
+
+        let leaves = indices.iter().map(|i| *self.leaf(*i)).collect();
+        let mut siblings = Vec::new();
+        let mut known = indices.clone();
+        for (level, level_start) in self.levels_indices.iter().enumerate() {
+            for (i, index) in known.iter().enumerate() {
+                let sibling = index ^ 1;
+                let is_known = match index % 2 {
+                    0 => known.get(i + 1) == Some(&sibling),
+                    _ => i > 0 && known[i - 1] == sibling,
+                };
+                // the last node of an odd level is paired with itself
+                if !is_known && sibling < self.level_len(level) {
+                    siblings.push(self.hashes[level_start + sibling]);
+                }
+            }
+            known = known.iter().map(|i| i / 2).collect();
+            known.dedup();
+        }
+        Ok(MerkleMultiProof::new(
+            indices,
+            leaves,
+            self.leaf_count(),
+            siblings,
+            self.params,
+        ))
+    }
+
+    /// Generates a proof that the leaves at positions `range` are exactly the
+    /// leaves of the tree in this span, using the boundary paths of the range.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::EmptyTree` if the tree has no leaves,
+    /// `MerkleError::IndexOutOfRange` if the range ends past the last leaf and
+    /// `MerkleError::MalformedProof` if the range is empty.
+    pub fn range_proof(&self, range: Range<usize>) -> Result<RangeProof<H>, MerkleError> {
+        if self.hashes.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        if range.is_empty() {
+            return Err(MerkleError::MalformedProof("empty range"));
+        }
+        if range.end > self.leaf_count() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: range.end - 1,
+                leaf_count: self.leaf_count(),
+            });
+        }
+
+        let leaves = range.clone().map(|i| *self.leaf(i)).collect();
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        let (mut start, mut end) = (range.start, range.end);
+        for (level, level_start) in self.levels_indices.iter().enumerate() {
+            if start % 2 == 1 {
+                left.push(self.hashes[level_start + start - 1]);
+            }
+            // the last node of an odd level has no sibling to prove
+            if end % 2 == 1 && end < self.level_len(level) {
+                right.push(self.hashes[level_start + end]);
+            }
+            start /= 2;
+            end = end.div_ceil(2);
+        }
+        Ok(RangeProof::new(
+            range.start,
+            leaves,
+            self.leaf_count(),
+            left,
+            right,
+            self.params,
+        ))
+    }
+
+    /// Returns the root hash of the Merkle tree.
+    pub fn root(&self) -> Option<&Digest> {
+        self.root.as_ref()
+    }
+
+    /// Returns the hexadecimal representation of the root hash of the Merkle tree.
+    pub fn root_hex(&self) -> Option<String> {
+        self.root().map(hex::encode)
+    }
+
+    /// Returns the root hash of the Merkle tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> Option<MerkleRoot> {
+        self.root().map(|root| MerkleRoot::new(H::ALGORITHM, *root))
+    }
+}
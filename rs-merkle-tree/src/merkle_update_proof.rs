@@ -0,0 +1,115 @@
+use crate::error::MerkleError;
+use crate::merkle_proof::MerkleProof;
+use crate::merkle_tree::Direction;
+use crate::tree_params::OddNodeStrategy;
+use crate::utils::crypto::{Digest, MerkleHasher, Sha256};
+use serde::{Deserialize, Serialize};
+
+/// Represents the proof that a tree was changed into another one by replacing
+/// a single leaf, every other leaf being kept.
+///
+/// Replacing a leaf does not change its siblings, so the proof of the old
+/// leaf leads to the old root, and the same siblings lead to the new root from
+/// the new leaf. The exception is the last node of an odd level: it is paired
+/// with its new hash if it is duplicated.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct UpdateProof<H: MerkleHasher = Sha256> {
+    /// The proof of the old leaf, with its position, in the old tree.
+    pub proof: MerkleProof<H>,
+    pub new_leaf: Digest,
+}
+
+impl<H: MerkleHasher> UpdateProof<H> {
+    /// Returns the position of the replaced leaf.
+    pub fn leaf_index(&self) -> Option<usize> {
+        self.proof.leaf_index
+    }
+
+    /// Returns the replaced leaf.
+    pub fn old_leaf(&self) -> Option<&Digest> {
+        self.proof.nodes.first().map(|node| &node.hash)
+    }
+
+    /// Checks that the proof leads from `old_root` to `new_root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `MerkleProof::compute_root` if the proof is
+    /// malformed, and `MerkleError::RootMismatch` if it does not lead to the
+    /// given roots.
+    pub fn verify(&self, old_root: &Digest, new_root: &Digest) -> Result<(), MerkleError> {
+        let (mut index, mut level_len) = match (self.proof.leaf_index, self.proof.leaf_count) {
+            (Some(leaf_index), Some(leaf_count)) => (leaf_index, leaf_count),
+            _ => return Err(MerkleError::MalformedProof("missing leaf position")),
+        };
+        // checks the siblings against the position, and the algorithm
+        let old = self.proof.compute_root()?;
+
+        let params = self.proof.params();
+        let leaf_count = level_len;
+        let mut siblings = self.proof.nodes.iter().skip(1);
+        let mut new = params.mode.hash_leaf::<H>(&self.new_leaf);
+        while level_len > 1 {
+            if index.is_multiple_of(2) && index + 1 == level_len {
+                // the recorded sibling of a duplicated node is its old hash
+                if params.odd_node != OddNodeStrategy::Promote {
+                    siblings.next();
+                }
+                new = params.parent::<H>(&new, None);
+            } else {
+                // the siblings match the position, checked by `compute_root`
+                let sibling = siblings.next().expect("one sibling per level");
+                new = match sibling.direction {
+                    Direction::Right => params.parent::<H>(&new, Some(&sibling.hash)),
+                    Direction::Left => params.parent::<H>(&sibling.hash, Some(&new)),
+                };
+            }
+            index /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+        let new = params.root::<H>(&new, leaf_count);
+        match old == *old_root && new == *new_root {
+            true => Ok(()),
+            false => Err(MerkleError::RootMismatch),
+        }
+    }
+}
+
+/// The outcome of `MerkleTree::update_leaf`: the roots of the tree before and
+/// after the update, and the proof linking them.
+#[derive(Debug)]
+pub struct LeafUpdate<H: MerkleHasher = Sha256> {
+    pub old_root: Digest,
+    pub new_root: Digest,
+    pub proof: UpdateProof<H>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::{HashMode, Sha3_256};
+
+    #[test]
+    fn should_verify_deserialized_proof() {
+        let leaves: Vec<Digest> = (0..11u8).map(|i| Sha3_256::hash(&[i])).collect();
+        let mut tree = MerkleTree::<Sha3_256>::build(leaves, HashMode::Rfc6962);
+        let update = tree.update_leaf(6, Sha3_256::hash(b"replica")).unwrap();
+
+        let json = serde_json::to_string(&update.proof).unwrap();
+        let proof: UpdateProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(proof.leaf_index(), Some(6));
+        assert_eq!(proof.verify(&update.old_root, &update.new_root), Ok(()));
+        assert_eq!(
+            proof.verify(&update.new_root, &update.old_root),
+            Err(MerkleError::RootMismatch)
+        );
+
+        let proof: UpdateProof<Sha256> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            proof.verify(&update.old_root, &update.new_root),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+}
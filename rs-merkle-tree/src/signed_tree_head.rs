@@ -0,0 +1,187 @@
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use ed25519_dalek::{Signer, Verifier};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+
+/// Prefix of the signed encoding of tree heads, so that their signatures
+/// cannot be mistaken for signatures of other messages of the same key.
+const TREE_HEAD_PREFIX: &[u8] = b"rs-merkle-tree/tree-head/v1";
+
+/// Represents a tree head signed with Ed25519: the root of a tree, which
+/// carries the algorithm it was computed with, its number of leaves and the
+/// time it was signed at, in milliseconds since the Unix epoch.
+///
+/// It is a receipt of what the signer, e.g. a server storing the leaves,
+/// agreed to store: anyone knowing its public key can check it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SignedTreeHead {
+    pub root: MerkleRoot,
+    pub leaf_count: u64,
+    pub timestamp: u64,
+    /// The public key of the signer.
+    #[serde(with = "hex_public_key")]
+    pub public_key: VerifyingKey,
+    #[serde(with = "hex_signature")]
+    pub signature: Signature,
+}
+
+impl SignedTreeHead {
+    /// Signs the head of a tree of `leaf_count` leaves whose root is `root`,
+    /// at `timestamp` in milliseconds since the Unix epoch.
+    pub fn sign(root: MerkleRoot, leaf_count: u64, timestamp: u64, key: &SigningKey) -> Self {
+        let signature = key.sign(&Self::message(&root, leaf_count, timestamp));
+        Self {
+            root,
+            leaf_count,
+            timestamp,
+            public_key: key.verifying_key(),
+            signature,
+        }
+    }
+
+    /// Returns the signed encoding of a tree head: the prefix, the algorithm
+    /// identifier, the root hash, then the leaf count and the timestamp as big
+    /// endian 64 bits integers.
+    fn message(root: &MerkleRoot, leaf_count: u64, timestamp: u64) -> Vec<u8> {
+        let mut message = TREE_HEAD_PREFIX.to_vec();
+        message.push(root.algorithm.id());
+        message.extend_from_slice(&root.hash.0);
+        message.extend_from_slice(&leaf_count.to_be_bytes());
+        message.extend_from_slice(&timestamp.to_be_bytes());
+        message
+    }
+
+    /// Checks that the tree head was signed by the owner of `key`, which must
+    /// come from a trusted source: `self.public_key` is whatever the signer
+    /// claims, and proves nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::InvalidSignature` if the tree head was signed
+    /// with another key or altered since.
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), MerkleError> {
+        if *key != self.public_key {
+            return Err(MerkleError::InvalidSignature);
+        }
+        let message = Self::message(&self.root, self.leaf_count, self.timestamp);
+        key.verify(&message, &self.signature)
+            .map_err(|_| MerkleError::InvalidSignature)
+    }
+}
+
+/// Parses a fixed size array from its hexadecimal encoding.
+fn decode_hex<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let bytes =
+        hex::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| serde::de::Error::custom(format!("expected {N} bytes, got {len} bytes")))
+}
+
+/// (De)serializes Ed25519 public keys as hexadecimal strings.
+mod hex_public_key {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(key: &VerifyingKey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(key.as_bytes()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VerifyingKey, D::Error> {
+        VerifyingKey::from_bytes(&decode_hex(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// (De)serializes Ed25519 signatures as hexadecimal strings.
+mod hex_signature {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        signature: &Signature,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(signature.to_bytes()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Signature, D::Error> {
+        Ok(Signature::from_bytes(&decode_hex(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::{Algorithm, Digest};
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn head() -> SignedTreeHead {
+        let root = MerkleRoot::new(Algorithm::Sha3_256, Digest([0xab; 32]));
+        SignedTreeHead::sign(root, 153, 1_700_000_000_000, &key(1))
+    }
+
+    #[test]
+    fn should_verify_signed_tree_head() {
+        let head = head();
+        assert_eq!(head.verify(&key(1).verifying_key()), Ok(()));
+        assert_eq!(
+            head.verify(&key(2).verifying_key()),
+            Err(MerkleError::InvalidSignature)
+        );
+
+        let mut altered = head.clone();
+        altered.leaf_count += 1;
+        assert_eq!(
+            altered.verify(&head.public_key),
+            Err(MerkleError::InvalidSignature)
+        );
+        let mut altered = head.clone();
+        altered.root.algorithm = Algorithm::Sha256;
+        assert_eq!(
+            altered.verify(&head.public_key),
+            Err(MerkleError::InvalidSignature)
+        );
+        let mut altered = head.clone();
+        altered.timestamp -= 1;
+        assert_eq!(
+            altered.verify(&head.public_key),
+            Err(MerkleError::InvalidSignature)
+        );
+
+        // a signature only holds for the key it was made with
+        let mut forged = SignedTreeHead::sign(head.root.clone(), 153, head.timestamp, &key(2));
+        forged.public_key = head.public_key;
+        assert_eq!(
+            forged.verify(&head.public_key),
+            Err(MerkleError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn should_round_trip_signed_tree_head() {
+        let head = head();
+        let json = serde_json::to_value(&head).unwrap();
+        assert_eq!(json["root"], format!("sha3-256:{}", "ab".repeat(32)));
+        assert_eq!(json["public_key"].as_str().unwrap().len(), 64);
+        assert_eq!(json["signature"].as_str().unwrap().len(), 128);
+
+        let read: SignedTreeHead = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(read, head);
+        assert_eq!(read.verify(&head.public_key), Ok(()));
+
+        let mut truncated = json;
+        truncated["signature"] = "00ff".into();
+        assert!(serde_json::from_value::<SignedTreeHead>(truncated).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+use crate::error::MerkleError;
+use crate::merkle_root::MerkleRoot;
+use crate::utils::crypto::{
+    Algorithm, Digest, HashMode, MerkleHasher, Sha256, DIGEST_SIZE, LEAF_PREFIX,
+};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+/// Number of levels of a sparse Merkle tree: one per bit of the keys.
+pub const SPARSE_DEPTH: usize = DIGEST_SIZE * 8;
+
+/// Hashing mode of the interior nodes of sparse Merkle trees.
+const MODE: HashMode = HashMode::Rfc6962;
+
+/// Returns the bit of `key` deciding which child of a node at `height` it
+/// belongs to, the most significant bit deciding it at the root.
+fn key_bit(key: &Digest, height: usize) -> bool {
+    let index = SPARSE_DEPTH - height;
+    key.0[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+/// Returns the hash of the leaf holding `value` under `key`.
+fn leaf_hash<H: MerkleHasher>(key: &Digest, value: &Digest) -> Digest {
+    H::hash_parts(&[&[LEAF_PREFIX], &key.0, &value.0])
+}
+
+/// Returns the root hashes of empty subtrees, indexed by their height.
+///
+/// An empty leaf hashes to zero, and an empty subtree to the hash of two empty
+/// subtrees one level below.
+fn default_hashes<H: MerkleHasher>() -> Vec<Digest> {
+    let mut defaults = Vec::with_capacity(SPARSE_DEPTH + 1);
+    defaults.push(Digest::default());
+    for height in 0..SPARSE_DEPTH {
+        defaults.push(MODE.hash_nodes::<H>(&defaults[height], &defaults[height]));
+    }
+    defaults
+}
+
+/// Represents a sparse Merkle tree: a Merkle tree with one leaf for each of
+/// the 2^256 possible keys, almost all of them empty.
+///
+/// Keys are hashes, typically `SparseMerkleTree::key` of a path, and values
+/// the hashes stored under them. Only the non-empty leaves are stored: empty
+/// subtrees hash to precomputed default hashes, so the cost of computing a
+/// root or a proof depends on the number of keys, not on the tree size.
+pub struct SparseMerkleTree<H: MerkleHasher = Sha256> {
+    leaves: BTreeMap<Digest, Digest>,
+    defaults: Vec<Digest>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> Default for SparseMerkleTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> SparseMerkleTree<H> {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+            defaults: default_hashes::<H>(),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns the key of `path` in the tree.
+    pub fn key(path: &[u8]) -> Digest {
+        H::hash(path)
+    }
+
+    /// Returns the hash algorithm of the tree.
+    pub fn algorithm(&self) -> Algorithm {
+        H::ALGORITHM
+    }
+
+    /// Returns the number of keys in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns `true` if the tree holds no key.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Stores `value` under `key`, returning the value it replaces.
+    pub fn insert(&mut self, key: Digest, value: Digest) -> Option<Digest> {
+        self.leaves.insert(key, value)
+    }
+
+    /// Removes `key` from the tree, returning its value.
+    pub fn remove(&mut self, key: &Digest) -> Option<Digest> {
+        self.leaves.remove(key)
+    }
+
+    /// Returns the value stored under `key`.
+    pub fn get(&self, key: &Digest) -> Option<&Digest> {
+        self.leaves.get(key)
+    }
+
+    /// Returns the root hash of the tree.
+    pub fn root(&self) -> Digest {
+        let entries: Vec<(Digest, Digest)> = self.leaves.iter().map(|(k, v)| (*k, *v)).collect();
+        self.subtree_root(&entries, SPARSE_DEPTH)
+    }
+
+    /// Returns the root hash of the tree, tagged with its algorithm.
+    pub fn tagged_root(&self) -> MerkleRoot {
+        MerkleRoot::new(H::ALGORITHM, self.root())
+    }
+
+    /// Generates a proof for `key`: an inclusion proof holding its value if
+    /// it is in the tree, an exclusion proof otherwise.
+    pub fn proof(&self, key: &Digest) -> SparseMerkleProof<H> {
+        let entries: Vec<(Digest, Digest)> = self.leaves.iter().map(|(k, v)| (*k, *v)).collect();
+
+        let mut siblings = Vec::new();
+        let mut path = entries.as_slice();
+        for height in (1..=SPARSE_DEPTH).rev() {
+            let split = path.partition_point(|(k, _)| !key_bit(k, height));
+            let (left, right) = path.split_at(split);
+            let (next, sibling) = match key_bit(key, height) {
+                true => (right, left),
+                false => (left, right),
+            };
+            let sibling = self.subtree_root(sibling, height - 1);
+            if sibling != self.defaults[height - 1] {
+                siblings.push(((height - 1) as u8, sibling));
+            }
+            path = next;
+        }
+        siblings.reverse();
+
+        SparseMerkleProof::new(*key, self.get(key).copied(), siblings)
+    }
+
+    /// Computes the root of the subtree at `height` holding the given entries,
+    /// sorted by key.
+    fn subtree_root(&self, entries: &[(Digest, Digest)], height: usize) -> Digest {
+        match entries {
+            [] => self.defaults[height],
+            [(key, value)] if height == 0 => leaf_hash::<H>(key, value),
+            _ => {
+                let split = entries.partition_point(|(k, _)| !key_bit(k, height));
+                MODE.hash_nodes::<H>(
+                    &self.subtree_root(&entries[..split], height - 1),
+                    &self.subtree_root(&entries[split..], height - 1),
+                )
+            }
+        }
+    }
+}
+
+/// Represents a proof that a key of a sparse Merkle tree holds `value`, or is
+/// empty when `value` is `None`.
+///
+/// Only the siblings which are not the root of an empty subtree are stored,
+/// with their height, from the leaf to the root.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SparseMerkleProof<H: MerkleHasher = Sha256> {
+    pub key: Digest,
+    pub value: Option<Digest>,
+    pub siblings: Vec<(u8, Digest)>,
+    pub algorithm: Algorithm,
+    #[serde(skip)]
+    _hasher: PhantomData<H>,
+}
+
+impl<H: MerkleHasher> SparseMerkleProof<H> {
+    pub fn new(key: Digest, value: Option<Digest>, siblings: Vec<(u8, Digest)>) -> Self {
+        Self {
+            key,
+            value,
+            siblings,
+            algorithm: H::ALGORITHM,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the proof shows the key is absent from the tree.
+    pub fn is_exclusion(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Computes the Merkle root hash using the sparse Merkle proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MerkleError::MalformedProof` if the siblings are not sorted by
+    /// height, and `MerkleError::AlgorithmMismatch` if it was built with
+    /// another algorithm than `H`.
+    pub fn compute_root(&self) -> Result<Digest, MerkleError> {
+        if self.algorithm != H::ALGORITHM {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: H::ALGORITHM,
+                actual: self.algorithm,
+            });
+        }
+        if self.siblings.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return Err(MerkleError::MalformedProof("siblings are not sorted"));
+        }
+
+        let defaults = default_hashes::<H>();
+        let mut siblings = self.siblings.iter().peekable();
+        let mut node = match &self.value {
+            Some(value) => leaf_hash::<H>(&self.key, value),
+            None => defaults[0],
+        };
+        for (height, default) in defaults.iter().enumerate().take(SPARSE_DEPTH) {
+            let sibling = match siblings.next_if(|(h, _)| *h as usize == height) {
+                Some((_, sibling)) => sibling,
+                None => default,
+            };
+            node = match key_bit(&self.key, height + 1) {
+                true => MODE.hash_nodes::<H>(sibling, &node),
+                false => MODE.hash_nodes::<H>(&node, sibling),
+            };
+        }
+        Ok(node)
+    }
+
+    /// Computes the Merkle root hash using the sparse Merkle proof, tagged
+    /// with its algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `compute_root` fails.
+    pub fn compute_tagged_root(&self) -> Result<MerkleRoot, MerkleError> {
+        Ok(MerkleRoot::new(H::ALGORITHM, self.compute_root()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::Sha3_256;
+
+    fn tree(paths: &[&str]) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+        for path in paths {
+            let key = SparseMerkleTree::<Sha256>::key(path.as_bytes());
+            tree.insert(key, Sha256::hash(format!("content of {path}").as_bytes()));
+        }
+        tree
+    }
+
+    #[test]
+    fn should_compute_empty_root_from_defaults() {
+        let tree = SparseMerkleTree::<Sha256>::new();
+        assert_eq!(tree.root(), default_hashes::<Sha256>()[SPARSE_DEPTH]);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn should_not_depend_on_insertion_order() {
+        let paths = ["a.txt", "b.txt", "dir/c.txt", "d.txt"];
+        let mut reversed = paths;
+        reversed.reverse();
+
+        assert_eq!(tree(&paths).root(), tree(&reversed).root());
+        assert_ne!(tree(&paths).root(), tree(&paths[..3]).root());
+    }
+
+    #[test]
+    fn should_prove_inclusion_and_exclusion() {
+        let tree = tree(&["a.txt", "b.txt", "dir/c.txt", "d.txt", "e.txt"]);
+        let root = tree.root();
+
+        for path in ["a.txt", "dir/c.txt", "e.txt"] {
+            let proof = tree.proof(&SparseMerkleTree::<Sha256>::key(path.as_bytes()));
+            assert!(!proof.is_exclusion());
+            assert_eq!(proof.compute_root(), Ok(root));
+        }
+        for path in ["secret.txt", "dir", "c.txt"] {
+            let proof = tree.proof(&SparseMerkleTree::<Sha256>::key(path.as_bytes()));
+            assert!(proof.is_exclusion());
+            assert!(proof.siblings.len() < 10);
+            assert_eq!(proof.compute_root(), Ok(root));
+        }
+    }
+
+    #[test]
+    fn should_reject_forged_exclusion() {
+        let tree = tree(&["a.txt", "secret.txt"]);
+        let key = SparseMerkleTree::<Sha256>::key(b"secret.txt");
+
+        let mut proof = tree.proof(&key);
+        proof.value = None;
+        assert_ne!(proof.compute_root(), Ok(tree.root()));
+
+        let mut removed = self::tree(&["a.txt", "secret.txt"]);
+        removed.remove(&key);
+        assert_eq!(removed.proof(&key).compute_root(), Ok(removed.root()));
+        assert_ne!(removed.root(), tree.root());
+    }
+
+    #[test]
+    fn should_serialize_sparse_proof() {
+        let mut tree = SparseMerkleTree::<Sha3_256>::new();
+        tree.insert(Sha3_256::hash(b"a"), Sha3_256::hash(b"content"));
+
+        let json = serde_json::to_string(&tree.proof(&Sha3_256::hash(b"b"))).unwrap();
+        let proof: SparseMerkleProof<Sha3_256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(proof.compute_tagged_root(), Ok(tree.tagged_root()));
+
+        let proof: SparseMerkleProof<Sha256> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            proof.compute_root(),
+            Err(MerkleError::AlgorithmMismatch { .. })
+        ));
+    }
+}
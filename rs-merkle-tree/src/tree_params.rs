@@ -0,0 +1,287 @@
+use crate::error::MerkleError;
+use crate::utils::crypto::{Digest, HashMode, MerkleHasher};
+use alloc::string::ToString;
+use core::fmt;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the hash committing a root to the leaf count, apart from the
+/// leaf and node prefixes of `HashMode::Rfc6962`.
+const LEAF_COUNT_PREFIX: u8 = 0x02;
+
+/// Describes how the last node of a level with an odd number of nodes is
+/// carried to the next level.
+///
+/// `Duplicate` pairs it with itself, which makes `[a, b, c]` and
+/// `[a, b, c, c]` share their root (CVE-2012-2459). `Promote` moves it up
+/// unchanged, and `PadZero` pairs it with a zero hash.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OddNodeStrategy {
+    #[default]
+    Duplicate,
+    Promote,
+    PadZero,
+}
+
+impl OddNodeStrategy {
+    /// Returns the identifier of the strategy in binary encodings.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            OddNodeStrategy::Duplicate => 0,
+            OddNodeStrategy::Promote => 1,
+            OddNodeStrategy::PadZero => 2,
+        }
+    }
+
+    /// Returns the strategy identified by `id` in binary encodings.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(OddNodeStrategy::Duplicate),
+            1 => Some(OddNodeStrategy::Promote),
+            2 => Some(OddNodeStrategy::PadZero),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OddNodeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OddNodeStrategy::Duplicate => write!(f, "duplicate"),
+            OddNodeStrategy::Promote => write!(f, "promote"),
+            OddNodeStrategy::PadZero => write!(f, "pad-zero"),
+        }
+    }
+}
+
+impl FromStr for OddNodeStrategy {
+    type Err = MerkleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "duplicate" => Ok(OddNodeStrategy::Duplicate),
+            "promote" => Ok(OddNodeStrategy::Promote),
+            "pad-zero" => Ok(OddNodeStrategy::PadZero),
+            _ => Err(MerkleError::UnknownOddNodeStrategy(s.to_string())),
+        }
+    }
+}
+
+/// Describes how a tree is hashed: the hashing mode of its leaves and nodes,
+/// how odd levels are handled, and whether the root commits to the number of
+/// leaves.
+///
+/// A root committing to the leaf count is `H(0x02 || count || root)`, `count`
+/// being a little-endian `u64`: trees of different sizes never share a root,
+/// whatever their odd node strategy.
+///
+/// The default parameters, which a `HashMode` converts into, build the same
+/// trees as before they existed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TreeParams {
+    pub mode: HashMode,
+    pub odd_node: OddNodeStrategy,
+    pub commit_leaf_count: bool,
+}
+
+impl TreeParams {
+    /// Returns the hash of the parent of `left` and `right`, or of `left`
+    /// alone if it is the last node of an odd level.
+    pub fn parent<H: MerkleHasher>(&self, left: &Digest, right: Option<&Digest>) -> Digest {
+        match (right, self.odd_node) {
+            (Some(right), _) => self.mode.hash_nodes::<H>(left, right),
+            (None, OddNodeStrategy::Duplicate) => self.mode.hash_nodes::<H>(left, left),
+            (None, OddNodeStrategy::Promote) => *left,
+            (None, OddNodeStrategy::PadZero) => self.mode.hash_nodes::<H>(left, &Digest::default()),
+        }
+    }
+
+    /// Returns the sibling recorded in proofs for `node`, the last node of an
+    /// odd level, if it is paired with one.
+    pub(crate) fn unpaired_sibling(&self, node: &Digest) -> Option<Digest> {
+        match self.odd_node {
+            OddNodeStrategy::Duplicate => Some(*node),
+            OddNodeStrategy::Promote => None,
+            OddNodeStrategy::PadZero => Some(Digest::default()),
+        }
+    }
+
+    /// Returns the root of a tree of `leaf_count` leaves whose top node is
+    /// `top`, committing to the leaf count if required.
+    pub fn root<H: MerkleHasher>(&self, top: &Digest, leaf_count: usize) -> Digest {
+        match self.commit_leaf_count {
+            true => H::hash_parts(&[
+                &[LEAF_COUNT_PREFIX],
+                &(leaf_count as u64).to_le_bytes(),
+                top.as_bytes(),
+            ]),
+            false => *top,
+        }
+    }
+
+    /// Returns the identifier of the odd node strategy and of the leaf count
+    /// commitment in binary encodings, on the bits 0-1 and 2.
+    pub(crate) fn flags(&self) -> u8 {
+        self.odd_node.id() | (self.commit_leaf_count as u8) << 2
+    }
+
+    /// Returns the parameters identified by `mode` and `flags` in binary
+    /// encodings.
+    pub(crate) fn from_flags(mode: HashMode, flags: u8) -> Option<Self> {
+        if flags & !0b111 != 0 {
+            return None;
+        }
+        Some(Self {
+            mode,
+            odd_node: OddNodeStrategy::from_id(flags & 0b11)?,
+            commit_leaf_count: flags & 0b100 != 0,
+        })
+    }
+}
+
+impl From<HashMode> for TreeParams {
+    fn from(mode: HashMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+}
+
+impl fmt::Display for TreeParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {} odd nodes", self.mode, self.odd_node)?;
+        if self.commit_leaf_count {
+            write!(f, ", leaf count committed")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::crypto::Sha3_256;
+
+    fn leaves(values: &[&str]) -> Vec<Digest> {
+        values
+            .iter()
+            .map(|v| Sha3_256::hash(v.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn should_tell_apart_duplicated_last_leaf() {
+        let (odd, even) = (leaves(&["a", "b", "c"]), leaves(&["a", "b", "c", "c"]));
+        let root = |leaves: &Vec<Digest>, params: TreeParams| {
+            *MerkleTree::<Sha3_256>::build(leaves.clone(), params)
+                .root()
+                .unwrap()
+        };
+
+        let duplicate = TreeParams::from(HashMode::Rfc6962);
+        assert_eq!(root(&odd, duplicate), root(&even, duplicate));
+
+        for odd_node in [OddNodeStrategy::Promote, OddNodeStrategy::PadZero] {
+            let params = TreeParams {
+                odd_node,
+                ..duplicate
+            };
+            assert_ne!(root(&odd, params), root(&even, params), "{odd_node}");
+        }
+        let committed = TreeParams {
+            commit_leaf_count: true,
+            ..duplicate
+        };
+        assert_ne!(root(&odd, committed), root(&even, committed));
+    }
+
+    #[test]
+    fn should_prove_leaves_with_every_params() {
+        let mut all_params = Vec::new();
+        for odd_node in [
+            OddNodeStrategy::Duplicate,
+            OddNodeStrategy::Promote,
+            OddNodeStrategy::PadZero,
+        ] {
+            for commit_leaf_count in [false, true] {
+                all_params.push(TreeParams {
+                    mode: HashMode::Rfc6962,
+                    odd_node,
+                    commit_leaf_count,
+                });
+            }
+        }
+
+        for params in all_params {
+            for leaf_count in 1..=13 {
+                let leaves: Vec<Digest> = (0..leaf_count)
+                    .map(|i: usize| Sha3_256::hash(&i.to_le_bytes()))
+                    .collect();
+                let mut tree = MerkleTree::<Sha3_256>::build(leaves, params);
+                let root = *tree.root().unwrap();
+                let context = format!("{params}, {leaf_count} leaves");
+
+                let loaded = MerkleTree::<Sha3_256>::from_bytes(&tree.to_bytes()).unwrap();
+                assert_eq!(loaded.params(), params, "{context}");
+                assert_eq!(loaded.root(), Some(&root), "{context}");
+
+                for index in 0..leaf_count {
+                    let proof = tree.proof_for_index(index).unwrap();
+                    assert_eq!(
+                        proof.compute_root_with_params(params),
+                        Ok(root),
+                        "{context}"
+                    );
+                    let compact = proof.to_compact().decode::<Sha3_256>().unwrap();
+                    assert_eq!(compact.compute_root(), Ok(root), "{context}");
+
+                    let range = tree.range_proof(index..leaf_count).unwrap();
+                    assert_eq!(range.compute_root(), Ok(root), "{context}");
+                }
+                let multi = tree
+                    .multi_proof(&[0, leaf_count / 2, leaf_count - 1])
+                    .unwrap();
+                assert_eq!(multi.compute_root(), Ok(root), "{context}");
+
+                let update = tree
+                    .update_leaf(leaf_count - 1, Sha3_256::hash(b"new"))
+                    .unwrap();
+                assert_eq!(update.old_root, root);
+                assert_eq!(
+                    update.proof.verify(&update.old_root, &update.new_root),
+                    Ok(()),
+                    "{context}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_round_trip_identifiers() {
+        for odd_node in [
+            OddNodeStrategy::Duplicate,
+            OddNodeStrategy::Promote,
+            OddNodeStrategy::PadZero,
+        ] {
+            assert_eq!(odd_node.to_string().parse(), Ok(odd_node));
+            for commit_leaf_count in [false, true] {
+                let params = TreeParams {
+                    mode: HashMode::Rfc6962,
+                    odd_node,
+                    commit_leaf_count,
+                };
+                assert_eq!(
+                    TreeParams::from_flags(HashMode::Rfc6962, params.flags()),
+                    Some(params)
+                );
+            }
+        }
+        assert_eq!(TreeParams::from_flags(HashMode::Legacy, 0b11), None);
+        assert!(matches!(
+            "zero".parse::<OddNodeStrategy>(),
+            Err(MerkleError::UnknownOddNodeStrategy(_))
+        ));
+    }
+}
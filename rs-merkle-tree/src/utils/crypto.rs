@@ -1,6 +1,653 @@
-use sha2::{Digest, Sha256};
+use crate::error::MerkleError;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+/// Variable-length hash, as used by the `Vec<u8>` based API.
 pub type Hash = Vec<u8>;
 
-pub fn hash(value: &Vec<u8>) -> Hash {
-    return Sha256::digest(value).to_vec();
+/// Size in bytes of the digests stored in the trees.
+pub const DIGEST_SIZE: usize = 32;
+
+/// A fixed-size hash, stored inline so trees hold their nodes without any
+/// per-node heap allocation.
+///
+/// It is serialized as a hexadecimal string in human-readable formats such as
+/// JSON, and as raw bytes otherwise. Arrays of 32 numbers, as serialized
+/// before, are still accepted.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Digest(pub [u8; DIGEST_SIZE]);
+
+impl Digest {
+    /// Returns the digest bytes.
+    pub fn as_bytes(&self) -> &[u8; DIGEST_SIZE] {
+        &self.0
+    }
+
+    /// Returns the digest bytes in a newly allocated `Vec<u8>`.
+    pub fn to_vec(&self) -> Hash {
+        self.0.to_vec()
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; DIGEST_SIZE]> for Digest {
+    fn from(bytes: [u8; DIGEST_SIZE]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Digest> for Hash {
+    fn from(digest: Digest) -> Self {
+        digest.to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for Digest {
+    type Error = MerkleError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        match bytes.try_into() {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(_) => Err(MerkleError::HashLengthMismatch {
+                expected: DIGEST_SIZE,
+                actual: bytes.len(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Hash> for Digest {
+    type Error = MerkleError;
+
+    fn try_from(hash: Hash) -> Result<Self, Self::Error> {
+        Self::try_from(hash.as_slice())
+    }
+}
+
+impl TryFrom<&Hash> for Digest {
+    type Error = MerkleError;
+
+    fn try_from(hash: &Hash) -> Result<Self, Self::Error> {
+        Self::try_from(hash.as_slice())
+    }
+}
+
+impl PartialEq<Hash> for Digest {
+    fn eq(&self, other: &Hash) -> bool {
+        self.0.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<Digest> for Hash {
+    fn eq(&self, other: &Digest) -> bool {
+        self.as_slice() == other.0.as_slice()
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.serialize_str(&hex::encode(self.0)),
+            false => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+struct DigestVisitor;
+
+impl<'de> Visitor<'de> for DigestVisitor {
+    type Value = Digest;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a {DIGEST_SIZE} bytes hash")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Digest, E> {
+        let bytes = hex::decode(v).map_err(E::custom)?;
+        Digest::try_from(bytes).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Digest, E> {
+        Digest::try_from(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Digest, A::Error> {
+        let mut bytes = [0; DIGEST_SIZE];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(de::Error::invalid_length(DIGEST_SIZE + 1, &self));
+        }
+        Ok(Digest(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.is_human_readable() {
+            true => deserializer.deserialize_any(DigestVisitor),
+            false => deserializer.deserialize_bytes(DigestVisitor),
+        }
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({})", hex::encode(self.0))
+    }
+}
+
+/// Hashes the concatenation of `parts` with `D`, without concatenating them.
+fn digest_parts<D: sha2::Digest>(parts: &[&[u8]]) -> Digest {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    Digest::try_from(hasher.finalize().as_slice()).expect("256 bits hash function")
+}
+
+/// Prefix prepended to leaves before hashing them in `HashMode::Rfc6962`.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prefix prepended to the concatenation of two children in `HashMode::Rfc6962`.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Hashes `value` with SHA-256.
+pub fn hash(value: &[u8]) -> Hash {
+    Sha256::hash(value).to_vec()
+}
+
+/// Hashes the content of `reader` with SHA-256, without loading it in memory.
+///
+/// # Errors
+///
+/// Returns the error of `reader` if reading fails.
+#[cfg(feature = "std")]
+pub fn hash_reader(reader: impl Read) -> io::Result<Hash> {
+    Ok(Sha256::hash_reader(reader)?.to_vec())
+}
+
+/// Size of the buffer `hash_reader` reads into.
+#[cfg(feature = "std")]
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Hashes data fed in successive chunks, e.g. a file too big to be loaded in
+/// memory. With the `std` feature, it is also an `io::Write`, so a reader can
+/// be copied into it.
+#[derive(Clone)]
+pub struct IncrementalHasher(HasherState);
+
+#[derive(Clone)]
+enum HasherState {
+    Sha256(sha2::Sha256),
+    Sha512_256(sha2::Sha512_256),
+    Sha3_256(sha3::Sha3_256),
+    Keccak256(sha3::Keccak256),
+}
+
+impl IncrementalHasher {
+    /// Creates a hasher computing `algorithm`.
+    pub fn new(algorithm: Algorithm) -> Self {
+        use sha2::Digest as _;
+        Self(match algorithm {
+            Algorithm::Sha256 => HasherState::Sha256(sha2::Sha256::new()),
+            Algorithm::Sha512_256 => HasherState::Sha512_256(sha2::Sha512_256::new()),
+            Algorithm::Sha3_256 => HasherState::Sha3_256(sha3::Sha3_256::new()),
+            Algorithm::Keccak256 => HasherState::Keccak256(sha3::Keccak256::new()),
+        })
+    }
+
+    /// Feeds `data` to the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        use sha2::Digest as _;
+        match &mut self.0 {
+            HasherState::Sha256(hasher) => hasher.update(data),
+            HasherState::Sha512_256(hasher) => hasher.update(data),
+            HasherState::Sha3_256(hasher) => hasher.update(data),
+            HasherState::Keccak256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Feeds the content of `reader` to the hasher, reading it by chunks.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `reader` if reading fails.
+    #[cfg(feature = "std")]
+    pub fn update_reader(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut buffer = vec![0; READ_BUFFER_SIZE];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(len) => self.update(&buffer[..len]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Returns the hash of all the data fed to the hasher.
+    pub fn finalize(self) -> Digest {
+        use sha2::Digest as _;
+        let hash = match self.0 {
+            HasherState::Sha256(hasher) => hasher.finalize().to_vec(),
+            HasherState::Sha512_256(hasher) => hasher.finalize().to_vec(),
+            HasherState::Sha3_256(hasher) => hasher.finalize().to_vec(),
+            HasherState::Keccak256(hasher) => hasher.finalize().to_vec(),
+        };
+        Digest::try_from(hash).expect("256 bits hash function")
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Write for IncrementalHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Evaluates an expression generic over `MerkleHasher` with the hasher of an
+/// `Algorithm` only known at runtime, bound to the given name, e.g.
+/// `with_hasher!(algorithm, |H| MerkleTree::<H>::build(leaves, mode).root())`.
+#[macro_export]
+macro_rules! with_hasher {
+    ($algorithm:expr, |$hasher:ident| $body:expr) => {
+        match $algorithm {
+            $crate::utils::crypto::Algorithm::Sha256 => {
+                type $hasher = $crate::utils::crypto::Sha256;
+                $body
+            }
+            $crate::utils::crypto::Algorithm::Sha512_256 => {
+                type $hasher = $crate::utils::crypto::Sha512_256;
+                $body
+            }
+            $crate::utils::crypto::Algorithm::Sha3_256 => {
+                type $hasher = $crate::utils::crypto::Sha3_256;
+                $body
+            }
+            $crate::utils::crypto::Algorithm::Keccak256 => {
+                type $hasher = $crate::utils::crypto::Keccak256;
+                $body
+            }
+        }
+    };
+}
+
+/// Identifies the hash function a tree, a proof or a root was computed with.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Algorithm {
+    #[default]
+    #[serde(rename = "sha256")]
+    Sha256,
+    #[serde(rename = "sha512/256")]
+    Sha512_256,
+    #[serde(rename = "sha3-256")]
+    Sha3_256,
+    #[serde(rename = "keccak256")]
+    Keccak256,
+}
+
+impl Algorithm {
+    /// Returns the identifier of the algorithm in binary encodings.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Algorithm::Sha256 => 0,
+            Algorithm::Sha512_256 => 1,
+            Algorithm::Sha3_256 => 2,
+            Algorithm::Keccak256 => 3,
+        }
+    }
+
+    /// Returns the algorithm identified by `id` in binary encodings.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Algorithm::Sha256),
+            1 => Some(Algorithm::Sha512_256),
+            2 => Some(Algorithm::Sha3_256),
+            3 => Some(Algorithm::Keccak256),
+            _ => None,
+        }
+    }
+
+    /// Hashes `data` with the algorithm, for callers which only know it at runtime.
+    pub fn hash(&self, data: &[u8]) -> Digest {
+        with_hasher!(self, |H| H::hash(data))
+    }
+
+    /// Hashes the content of `reader` with the algorithm, reading it by chunks
+    /// so memory use does not depend on its size.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `reader` if reading fails.
+    #[cfg(feature = "std")]
+    pub fn hash_reader(&self, reader: impl Read) -> io::Result<Digest> {
+        let mut hasher = IncrementalHasher::new(*self);
+        hasher.update_reader(reader)?;
+        Ok(hasher.finalize())
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Sha256 => write!(f, "sha256"),
+            Algorithm::Sha512_256 => write!(f, "sha512/256"),
+            Algorithm::Sha3_256 => write!(f, "sha3-256"),
+            Algorithm::Keccak256 => write!(f, "keccak256"),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = MerkleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Algorithm::Sha256),
+            "sha512/256" => Ok(Algorithm::Sha512_256),
+            "sha3-256" => Ok(Algorithm::Sha3_256),
+            "keccak256" => Ok(Algorithm::Keccak256),
+            _ => Err(MerkleError::UnknownAlgorithm(s.to_string())),
+        }
+    }
+}
+
+/// A 256 bits hash function usable to build Merkle trees and proofs.
+pub trait MerkleHasher {
+    /// Identifier of the algorithm, carried in serialized proofs and roots.
+    const ALGORITHM: Algorithm;
+
+    /// Hashes the concatenation of `parts`.
+    fn hash_parts(parts: &[&[u8]]) -> Digest;
+
+    /// Hashes `data`.
+    fn hash(data: &[u8]) -> Digest {
+        Self::hash_parts(&[data])
+    }
+
+    /// Hashes the content of `reader` by chunks, see `Algorithm::hash_reader`.
+    #[cfg(feature = "std")]
+    fn hash_reader(reader: impl Read) -> io::Result<Digest> {
+        Self::ALGORITHM.hash_reader(reader)
+    }
+}
+
+/// SHA-256, the algorithm used by vaults committed before hashers were pluggable.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Sha256;
+
+impl MerkleHasher for Sha256 {
+    const ALGORITHM: Algorithm = Algorithm::Sha256;
+
+    fn hash_parts(parts: &[&[u8]]) -> Digest {
+        digest_parts::<sha2::Sha256>(parts)
+    }
+}
+
+/// SHA-512 truncated to 256 bits.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Sha512_256;
+
+impl MerkleHasher for Sha512_256 {
+    const ALGORITHM: Algorithm = Algorithm::Sha512_256;
+
+    fn hash_parts(parts: &[&[u8]]) -> Digest {
+        digest_parts::<sha2::Sha512_256>(parts)
+    }
+}
+
+/// SHA3-256, as standardized in FIPS 202.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Sha3_256;
+
+impl MerkleHasher for Sha3_256 {
+    const ALGORITHM: Algorithm = Algorithm::Sha3_256;
+
+    fn hash_parts(parts: &[&[u8]]) -> Digest {
+        digest_parts::<sha3::Sha3_256>(parts)
+    }
+}
+
+/// Keccak-256, the original Keccak submission SHA-3 was standardized from, as
+/// used by Ethereum. It differs from `Sha3_256` by its padding only.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Keccak256;
+
+impl MerkleHasher for Keccak256 {
+    const ALGORITHM: Algorithm = Algorithm::Keccak256;
+
+    fn hash_parts(parts: &[&[u8]]) -> Digest {
+        digest_parts::<sha3::Keccak256>(parts)
+    }
+}
+
+/// Describes how leaves and interior nodes are hashed.
+///
+/// `Legacy` uses the leaves as-is and hashes interior nodes as
+/// `H(left || right)`, which makes an interior node indistinguishable
+/// from a leaf. `Rfc6962` separates both domains the way Certificate
+/// Transparency does: leaves are hashed as `H(0x00 || leaf)` and interior
+/// nodes as `H(0x01 || left || right)`. `SortedPair` uses the leaves as-is and
+/// hashes interior nodes as `H(min || max)`, the smallest child first, as
+/// OpenZeppelin's `MerkleProof` does: the root of a leaf does not depend on the
+/// side of its siblings.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HashMode {
+    #[default]
+    Legacy,
+    Rfc6962,
+    SortedPair,
+}
+
+impl HashMode {
+    /// Returns the identifier of the mode in binary encodings.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            HashMode::Legacy => 0,
+            HashMode::Rfc6962 => 1,
+            HashMode::SortedPair => 2,
+        }
+    }
+
+    /// Returns the mode identified by `id` in binary encodings.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(HashMode::Legacy),
+            1 => Some(HashMode::Rfc6962),
+            2 => Some(HashMode::SortedPair),
+            _ => None,
+        }
+    }
+
+    /// Returns the hash stored at the leaf level of the tree for `leaf`.
+    pub fn hash_leaf<H: MerkleHasher>(&self, leaf: &Digest) -> Digest {
+        match self {
+            HashMode::Legacy | HashMode::SortedPair => *leaf,
+            HashMode::Rfc6962 => H::hash_parts(&[&[LEAF_PREFIX], &leaf.0]),
+        }
+    }
+
+    /// Returns the hash of the interior node whose children are `left` and `right`.
+    pub fn hash_nodes<H: MerkleHasher>(&self, left: &Digest, right: &Digest) -> Digest {
+        match self {
+            HashMode::Legacy => H::hash_parts(&[&left.0, &right.0]),
+            HashMode::Rfc6962 => H::hash_parts(&[&[NODE_PREFIX], &left.0, &right.0]),
+            HashMode::SortedPair => H::hash_parts(&[&left.min(right).0, &left.max(right).0]),
+        }
+    }
+}
+
+impl fmt::Display for HashMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashMode::Legacy => write!(f, "legacy"),
+            HashMode::Rfc6962 => write!(f, "rfc6962"),
+            HashMode::SortedPair => write!(f, "sorted-pair"),
+        }
+    }
+}
+
+impl FromStr for HashMode {
+    type Err = MerkleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "legacy" => Ok(HashMode::Legacy),
+            "rfc6962" => Ok(HashMode::Rfc6962),
+            "sorted-pair" => Ok(HashMode::SortedPair),
+            _ => Err(MerkleError::UnknownMode(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_dispatch_to_the_hasher_of_the_algorithm() {
+        for algorithm in [
+            Algorithm::Sha256,
+            Algorithm::Sha512_256,
+            Algorithm::Sha3_256,
+            Algorithm::Keccak256,
+        ] {
+            assert_eq!(with_hasher!(algorithm, |H| H::ALGORITHM), algorithm);
+        }
+    }
+
+    #[test]
+    fn should_separate_leaf_and_node_domains() {
+        let a = Sha256::hash(b"a");
+        let b = Sha256::hash(b"b");
+
+        assert_eq!(HashMode::Legacy.hash_leaf::<Sha256>(&a), a);
+        assert_ne!(HashMode::Rfc6962.hash_leaf::<Sha256>(&a), a);
+        assert_ne!(
+            HashMode::Legacy.hash_nodes::<Sha256>(&a, &b),
+            HashMode::Rfc6962.hash_nodes::<Sha256>(&a, &b)
+        );
+        assert_eq!(
+            HashMode::SortedPair.hash_nodes::<Sha256>(&a, &b),
+            HashMode::SortedPair.hash_nodes::<Sha256>(&b, &a)
+        );
+        assert_eq!("rfc6962".parse::<HashMode>(), Ok(HashMode::Rfc6962));
+        assert_eq!("sorted-pair".parse::<HashMode>(), Ok(HashMode::SortedPair));
+    }
+
+    #[test]
+    fn should_serialize_digest_as_hex() {
+        let digest = Digest([0xab; DIGEST_SIZE]);
+        let json = serde_json::to_string(&digest).unwrap();
+
+        assert_eq!(json, format!("\"{}\"", "ab".repeat(DIGEST_SIZE)));
+        assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
+        let array = serde_json::to_string(&[0xabu8; DIGEST_SIZE]).unwrap();
+        assert_eq!(serde_json::from_str::<Digest>(&array).unwrap(), digest);
+        assert!(serde_json::from_str::<Digest>("\"abab\"").is_err());
+
+        let bytes = bincode::serialize(&digest).unwrap();
+        assert_eq!(bytes.len(), 8 + DIGEST_SIZE);
+        assert_eq!(bincode::deserialize::<Digest>(&bytes).unwrap(), digest);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn should_hash_readers_like_slices() {
+        // larger than the read buffer, and not a multiple of its size
+        let data: Vec<u8> = (0..READ_BUFFER_SIZE * 3 + 17).map(|i| i as u8).collect();
+
+        assert_eq!(
+            Sha256::hash_reader(data.as_slice()).unwrap(),
+            Sha256::hash(&data)
+        );
+        assert_eq!(hash_reader(data.as_slice()).unwrap(), hash(&data));
+        for algorithm in [
+            Algorithm::Sha512_256,
+            Algorithm::Sha3_256,
+            Algorithm::Keccak256,
+        ] {
+            assert_eq!(
+                algorithm.hash_reader(data.as_slice()).unwrap(),
+                algorithm.hash(&data)
+            );
+        }
+
+        let mut hasher = IncrementalHasher::new(Algorithm::Sha3_256);
+        for chunk in data.chunks(1000) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), Sha3_256::hash(&data));
+    }
+
+    #[test]
+    fn should_hash_with_known_vectors() {
+        assert_eq!(
+            hex::encode(Sha256::hash(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex::encode(Sha512_256::hash(b"abc")),
+            "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"
+        );
+        assert_eq!(
+            hex::encode(Sha3_256::hash(b"abc")),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+        assert_eq!(
+            hex::encode(Keccak256::hash(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert_eq!(Algorithm::Sha3_256.hash(b"abc"), Sha3_256::hash(b"abc"));
+    }
+
+    #[test]
+    fn should_convert_digests_from_and_to_vec() {
+        let hash = hash(b"abc");
+        let digest = Digest::try_from(&hash).unwrap();
+
+        assert_eq!(digest, Sha256::hash(b"abc"));
+        assert_eq!(digest, hash);
+        assert_eq!(Hash::from(digest), hash);
+        assert_eq!(
+            Digest::try_from(&hash[1..]),
+            Err(MerkleError::HashLengthMismatch {
+                expected: 32,
+                actual: 31
+            })
+        );
+    }
+
+    #[test]
+    fn should_parse_algorithm_identifiers() {
+        for algorithm in [
+            Algorithm::Sha256,
+            Algorithm::Sha512_256,
+            Algorithm::Sha3_256,
+            Algorithm::Keccak256,
+        ] {
+            assert_eq!(algorithm.to_string().parse::<Algorithm>(), Ok(algorithm));
+        }
+        assert!("md5".parse::<Algorithm>().is_err());
+    }
 }
@@ -0,0 +1,27 @@
+//! Builds the `#![no_std]` crate of `tests/no_std`, which fails to build if
+//! the library links `std` without its `std` feature.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn should_build_without_std() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .arg("build")
+        .arg("--offline")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("tests/no_std/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std"));
+    if cfg!(feature = "signing") {
+        cargo.args(["--features", "signing"]);
+    }
+    let output = cargo.output().expect("Unable to run cargo");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
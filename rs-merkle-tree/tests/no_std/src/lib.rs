@@ -0,0 +1,52 @@
+//! Verifies proofs with the library built without its `std` feature, as
+//! firmware would. The panic handler below conflicts with the one of `std`, so
+//! the crate does not build if the library, or one of its dependencies, links
+//! `std`.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use rs_merkle_tree::utils::crypto::{HashMode, MerkleHasher, Sha3_256};
+use rs_merkle_tree::{MerkleProof, MerkleRoot, MerkleTree, OddNodeStrategy, TreeParams};
+
+/// Checks that `proof` proves a leaf under `root`, given as `<algorithm>:<hex>`.
+pub fn verify(proof: &MerkleProof<Sha3_256>, root: &str) -> bool {
+    match (proof.compute_tagged_root(), root.parse::<MerkleRoot>()) {
+        (Ok(computed), Ok(root)) => computed == root,
+        _ => false,
+    }
+}
+
+/// Builds a tree of `leaf_count` leaves and verifies a proof of each of them.
+pub fn verify_tree(leaf_count: u8) -> bool {
+    let leaves: Vec<_> = (0..leaf_count).map(|i| Sha3_256::hash(&[i])).collect();
+    let params = TreeParams {
+        mode: HashMode::Rfc6962,
+        odd_node: OddNodeStrategy::Promote,
+        commit_leaf_count: true,
+    };
+    let tree = MerkleTree::<Sha3_256>::build(leaves, params);
+    let root = match tree.tagged_root() {
+        Some(root) => alloc::format!("{root}"),
+        None => return false,
+    };
+    (0..tree.leaf_count()).all(|index| match tree.proof_for_index(index) {
+        Ok(proof) => verify(&proof, &root),
+        Err(_) => false,
+    })
+}
+
+/// Checks a signed tree head against the key of its signer.
+#[cfg(feature = "signing")]
+pub fn verify_tree_head(
+    head: &rs_merkle_tree::SignedTreeHead,
+    key: &rs_merkle_tree::VerifyingKey,
+) -> bool {
+    head.verify(key).is_ok()
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,269 @@
+//! Property tests of the invariants of trees and proofs, over trees of up to
+//! 10,000 leaves built with every combination of tree parameters.
+//!
+//! Roots are compared against `reference`, a naive implementation hashing
+//! each level into the next one with the `sha2` crate, which shares no code
+//! with the library.
+
+use proptest::prelude::*;
+use rs_merkle_tree::utils::crypto::{Digest, HashMode, MerkleHasher, Sha256};
+use rs_merkle_tree::{MerkleError, MerkleTree, OddNodeStrategy, TreeParams};
+
+const MAX_LEAVES: usize = 10_000;
+
+mod reference {
+    use rs_merkle_tree::utils::crypto::HashMode;
+    use rs_merkle_tree::{OddNodeStrategy, TreeParams};
+    use sha2::{Digest, Sha256};
+
+    type Node = [u8; 32];
+
+    fn sha256(parts: &[&[u8]]) -> Node {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hash_leaf(mode: HashMode, leaf: &Node) -> Node {
+        match mode {
+            HashMode::Rfc6962 => sha256(&[&[0x00], leaf]),
+            _ => *leaf,
+        }
+    }
+
+    fn hash_nodes(mode: HashMode, left: &Node, right: &Node) -> Node {
+        match mode {
+            HashMode::Legacy => sha256(&[left, right]),
+            HashMode::Rfc6962 => sha256(&[&[0x01], left, right]),
+            HashMode::SortedPair if left <= right => sha256(&[left, right]),
+            HashMode::SortedPair => sha256(&[right, left]),
+        }
+    }
+
+    /// Returns the root of the SHA-256 tree of `leaves`, built level by level.
+    pub fn root(leaves: &[Node], params: TreeParams) -> Option<Node> {
+        let mode = params.mode;
+        let mut level: Vec<Node> = leaves.iter().map(|l| hash_leaf(mode, l)).collect();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match (pair, params.odd_node) {
+                    ([left, right], _) => hash_nodes(mode, left, right),
+                    ([last], OddNodeStrategy::Duplicate) => hash_nodes(mode, last, last),
+                    ([last], OddNodeStrategy::Promote) => *last,
+                    ([last], OddNodeStrategy::PadZero) => hash_nodes(mode, last, &[0; 32]),
+                    _ => unreachable!("chunks of one or two nodes"),
+                })
+                .collect();
+        }
+        let top = level.pop()?;
+        Some(match params.commit_leaf_count {
+            true => sha256(&[&[0x02], &(leaves.len() as u64).to_le_bytes(), &top]),
+            false => top,
+        })
+    }
+}
+
+/// Returns `count` distinct leaves, which depend on `seed`.
+fn leaves(count: usize, seed: u64) -> Vec<Digest> {
+    (0..count)
+        .map(|i| Sha256::hash_parts(&[&seed.to_le_bytes(), &(i as u64).to_le_bytes()]))
+        .collect()
+}
+
+/// Returns the number of levels above the leaves, i.e. `ceil(log2(leaf_count))`.
+fn depth(leaf_count: usize) -> usize {
+    match leaf_count {
+        0 | 1 => 0,
+        _ => ((leaf_count - 1).ilog2() + 1) as usize,
+    }
+}
+
+fn params() -> impl Strategy<Value = TreeParams> {
+    (
+        prop_oneof![
+            Just(HashMode::Legacy),
+            Just(HashMode::Rfc6962),
+            Just(HashMode::SortedPair),
+        ],
+        prop_oneof![
+            Just(OddNodeStrategy::Duplicate),
+            Just(OddNodeStrategy::Promote),
+            Just(OddNodeStrategy::PadZero),
+        ],
+        any::<bool>(),
+    )
+        .prop_map(|(mode, odd_node, commit_leaf_count)| TreeParams {
+            mode,
+            odd_node,
+            commit_leaf_count,
+        })
+}
+
+/// Returns the leaf count, leaf seed and parameters of a tree of up to
+/// `MAX_LEAVES` leaves, small trees and trees around a power of two being
+/// picked more often.
+fn tree() -> impl Strategy<Value = (usize, u64, TreeParams)> {
+    let leaf_count = prop_oneof![
+        0..=17usize,
+        (1..14u32).prop_flat_map(|exp| (1usize << exp) - 1..=(1 << exp) + 1),
+        0..=MAX_LEAVES,
+    ];
+    (leaf_count, any::<u64>(), params())
+}
+
+fn non_empty_tree() -> impl Strategy<Value = (usize, u64, TreeParams)> {
+    tree().prop_filter("empty tree", |(leaf_count, _, _)| *leaf_count > 0)
+}
+
+fn build(leaf_count: usize, seed: u64, params: TreeParams) -> MerkleTree<Sha256> {
+    MerkleTree::build(leaves(leaf_count, seed), params)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn root_should_match_reference((leaf_count, seed, params) in tree()) {
+        let tree = build(leaf_count, seed, params);
+        let leaves: Vec<_> = leaves(leaf_count, seed).iter().map(|l| l.0).collect();
+        let expected = reference::root(&leaves, params).map(Digest);
+        prop_assert_eq!(tree.root(), expected.as_ref());
+    }
+
+    #[test]
+    fn tree_size_should_match_formulas((leaf_count, seed, params) in tree()) {
+        let tree = build(leaf_count, seed, params);
+        // each level of an odd number of nodes, the top one excepted, adds a
+        // node to the 2n - 1 nodes of a perfect tree
+        let odd_levels = (0..depth(leaf_count))
+            .filter(|level| leaf_count.div_ceil(1 << level) % 2 == 1)
+            .count();
+        let node_count = match leaf_count {
+            0 => 0,
+            _ => 2 * leaf_count - 1 + odd_levels,
+        };
+        prop_assert_eq!(tree.view().hashes().len(), node_count);
+
+        if leaf_count > 0 {
+            let last = tree.proof_for_index(leaf_count - 1).unwrap();
+            let siblings = last.nodes.len() - 1;
+            match params.odd_node {
+                // the last node is promoted on each odd level
+                OddNodeStrategy::Promote => prop_assert_eq!(siblings, depth(leaf_count) - odd_levels),
+                _ => prop_assert_eq!(siblings, depth(leaf_count)),
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_proof_should_fail(
+        (leaf_count, seed, params) in non_empty_tree(),
+        index in any::<prop::sample::Index>(),
+        node in any::<prop::sample::Index>(),
+        bit in 0..256usize,
+    ) {
+        let tree = build(leaf_count, seed, params);
+        let root = *tree.root().unwrap();
+        let index = index.index(leaf_count);
+
+        let mut proof = tree.proof_for_index(index).unwrap();
+        let node = node.index(proof.nodes.len());
+        proof.nodes[node].hash.0[bit / 8] ^= 1 << (bit % 8);
+        prop_assert_ne!(proof.compute_root(), Ok(root));
+
+        let mut proof = tree.proof_for_index(index).unwrap();
+        if proof.nodes.len() > 1 {
+            // directions cannot be named, the first siblings of the first two
+            // leaves are on the right and on the left
+            let direction = |leaf| tree.proof_for_index(leaf).unwrap().nodes.swap_remove(1).direction;
+            let sibling = node.max(1);
+            proof.nodes[sibling].direction = match proof.nodes[sibling].direction == direction(0) {
+                true => direction(1),
+                false => direction(0),
+            };
+            prop_assert!(matches!(proof.compute_root(), Err(MerkleError::MalformedProof(_))));
+
+            let mut proof = tree.proof_for_index(index).unwrap();
+            proof.nodes.remove(sibling);
+            prop_assert!(matches!(proof.compute_root(), Err(MerkleError::MalformedProof(_))));
+        }
+    }
+
+    #[test]
+    fn multi_and_range_proofs_should_verify(
+        (leaf_count, seed, params) in non_empty_tree(),
+        indices in prop::collection::vec(any::<prop::sample::Index>(), 1..32),
+    ) {
+        let tree = build(leaf_count, seed, params);
+        let root = tree.root().unwrap();
+        let indices: Vec<_> = indices.iter().map(|i| i.index(leaf_count)).collect();
+        prop_assert_eq!(tree.multi_proof(&indices).unwrap().compute_root(), Ok(*root));
+
+        let (start, end) = (indices[0], indices[indices.len() - 1]);
+        let range = start.min(end)..start.max(end) + 1;
+        prop_assert_eq!(tree.range_proof(range).unwrap().compute_root(), Ok(*root));
+    }
+}
+
+proptest! {
+    // proves each of up to 10,000 leaves
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn every_leaf_proof_should_verify((leaf_count, seed, params) in non_empty_tree()) {
+        let tree = build(leaf_count, seed, params);
+        let root = tree.root().unwrap();
+        for (index, leaf) in tree.view().iter_leaves().enumerate() {
+            let proof = tree.proof_for_index(index).unwrap();
+            prop_assert_eq!(&proof.nodes[0].hash, leaf);
+            prop_assert!(proof.nodes.len() - 1 <= depth(leaf_count));
+            prop_assert_eq!(proof.compute_root_with_params(params), Ok(*root), "leaf {}", index);
+        }
+    }
+}
+
+#[test]
+fn every_leaf_proof_of_small_trees_should_verify() {
+    let modes = [HashMode::Legacy, HashMode::Rfc6962, HashMode::SortedPair];
+    let odd_nodes = [
+        OddNodeStrategy::Duplicate,
+        OddNodeStrategy::Promote,
+        OddNodeStrategy::PadZero,
+    ];
+    for leaf_count in 1..=65 {
+        let leaves = leaves(leaf_count, leaf_count as u64);
+        for (mode, odd_node, commit_leaf_count) in modes
+            .iter()
+            .flat_map(|m| odd_nodes.iter().map(move |o| (*m, *o)))
+            .flat_map(|(m, o)| [(m, o, false), (m, o, true)])
+        {
+            let params = TreeParams {
+                mode,
+                odd_node,
+                commit_leaf_count,
+            };
+            let tree = MerkleTree::<Sha256>::build(leaves.clone(), params);
+            let raw: Vec<_> = leaves.iter().map(|l| l.0).collect();
+            let root = Digest(reference::root(&raw, params).unwrap());
+            assert_eq!(tree.root(), Some(&root), "{leaf_count} leaves, {params}");
+            for index in 0..leaf_count {
+                let proof = tree.proof_for_index(index).unwrap();
+                assert_eq!(
+                    proof.compute_root(),
+                    Ok(root),
+                    "leaf {index} of {leaf_count}, {params}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_tree_should_have_no_root_nor_proof() {
+    let tree = MerkleTree::<Sha256>::build(Vec::new(), TreeParams::default());
+    assert_eq!(tree.root(), None);
+    assert_eq!(tree.proof_for_index(0).unwrap_err(), MerkleError::EmptyTree);
+}
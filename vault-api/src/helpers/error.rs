@@ -0,0 +1,86 @@
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::serde::json::{json, Value};
+use rs_merkle_tree::MerkleError;
+use std::error::Error;
+use std::io;
+
+/// Returns the HTTP status to answer with when the Merkle library fails.
+pub fn merkle_error_status(err: &MerkleError) -> Status {
+    match err {
+        MerkleError::EmptyTree => Status::UnprocessableEntity,
+        MerkleError::LeafNotFound => Status::NotFound,
+        MerkleError::IndexOutOfRange { .. } => Status::NotFound,
+        MerkleError::MalformedProof(_) => Status::InternalServerError,
+        MerkleError::HashLengthMismatch { .. } => Status::InternalServerError,
+        MerkleError::AlgorithmMismatch { .. } => Status::InternalServerError,
+        MerkleError::ModeMismatch { .. } => Status::InternalServerError,
+        MerkleError::UnknownAlgorithm(_) => Status::BadRequest,
+        MerkleError::UnknownMode(_) => Status::BadRequest,
+        MerkleError::InvalidHex(_) => Status::BadRequest,
+        MerkleError::RootMismatch => Status::InternalServerError,
+        MerkleError::InvalidDump(_) => Status::InternalServerError,
+        MerkleError::ChunkMismatch { .. } => Status::InternalServerError,
+        MerkleError::UnknownOddNodeStrategy(_) => Status::BadRequest,
+        MerkleError::ParamsMismatch { .. } => Status::InternalServerError,
+        MerkleError::LeafCountMismatch { .. } => Status::InternalServerError,
+        MerkleError::InvalidPath(_) => Status::BadRequest,
+        MerkleError::InvalidSignature => Status::InternalServerError,
+    }
+}
+
+/// Builds the failure response matching a Merkle library error.
+pub fn merkle_error_response(err: MerkleError) -> Custom<Value> {
+    Custom(
+        merkle_error_status(&err),
+        json!({
+            "success": false,
+            "message": err.to_string(),
+        }),
+    )
+}
+
+/// Error of the helpers building the trees of a vault, which read its files.
+#[derive(Debug)]
+pub enum TreeError {
+    Merkle(MerkleError),
+    Io(io::Error),
+}
+
+impl From<MerkleError> for TreeError {
+    fn from(err: MerkleError) -> Self {
+        TreeError::Merkle(err)
+    }
+}
+
+impl From<io::Error> for TreeError {
+    fn from(err: io::Error) -> Self {
+        TreeError::Io(err)
+    }
+}
+
+/// Builds the failure response matching an error of the tree helpers.
+pub fn tree_error_response(err: TreeError) -> Custom<Value> {
+    match err {
+        TreeError::Merkle(err) => merkle_error_response(err),
+        TreeError::Io(err) => Custom(
+            Status::InternalServerError,
+            json!({
+                "success": false,
+                "message": format!("Failed to read the vault files: {err}"),
+            }),
+        ),
+    }
+}
+
+/// Builds the failure response to a request on a vault which does not exist or
+/// whose id is invalid, see `get_existing_vault_dir`.
+pub fn vault_not_found_response(err: Box<dyn Error>) -> Custom<Value> {
+    Custom(
+        Status::NotFound,
+        json!({
+            "success": false,
+            "message": err.to_string(),
+        }),
+    )
+}
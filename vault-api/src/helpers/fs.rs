@@ -1,31 +1,310 @@
-use std::{error::Error, fs, io, path::Path};
+use rs_merkle_tree::utils::crypto::Algorithm;
+use rs_merkle_tree::{SigningKey, TreeParams};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Component, Path};
+use std::{error::Error, fs, io, str::FromStr};
 
-pub fn list_files_in_vault(vault_id: &String) -> Vec<String> {
-    let vault_dir = get_existing_vault_dir(vault_id).unwrap();
+/// Default folder holding the vaults, their files and the dumps of their trees.
+pub const DEFAULT_FILES_DIR: &str = "./FILES";
 
-    fs::read_dir(vault_dir)
+/// Folder holding the vaults, managed by the server. It is set with
+/// `ROCKET_FILES_DIR`, and defaults to `./FILES`.
+pub struct FilesDir(pub String);
+
+/// Returns the paths of the files of the vault, walking its folders
+/// recursively. They are sorted by their path in the vault, compared folder
+/// by folder as in a `DirectoryTree`, which is the order of the leaves of the
+/// vault Merkle tree.
+pub fn list_files_in_vault(files: &FilesDir, vault_id: &String) -> Vec<String> {
+    fn walk(dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, files)?;
+            } else if path.is_file() {
+                files.push(path.to_str().unwrap().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    let vault_dir = get_existing_vault_dir(files, vault_id).unwrap();
+    let mut files = Vec::new();
+    walk(Path::new(&vault_dir), &mut files).unwrap();
+    // paths are ordered by their components: `a/b.txt` comes before `a.txt`
+    files.sort_by(|a, b| Path::new(a).cmp(Path::new(b)));
+    files
+}
+
+/// Returns the name of a file of the vault given its path, i.e. its `/`
+/// separated path relative to the vault folder, e.g. `photos/2023/cat.png`.
+pub fn vault_file_name(files: &FilesDir, vault_id: &String, path: &str) -> String {
+    let vault_dir = format!("{}/{vault_id}", files.0);
+    Path::new(path)
+        .strip_prefix(vault_dir)
         .unwrap()
-        .filter(|f| f.as_ref().unwrap().path().is_file())
-        .map(|res| {
-            res.map(|e| {
-                Path::new(e.path().to_str().unwrap())
-                    .to_str()
-                    .unwrap()
-                    .to_string()
-            })
+        .components()
+        .map(|c| c.as_os_str().to_str().unwrap())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+/// Returns `name` as a `/` separated relative path which cannot escape the
+/// vault folder, or `None` if it has no normal component or has a `..` one.
+/// Root and current directory components are dropped.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let mut components = Vec::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_str()?),
+            Component::RootDir | Component::CurDir => {}
+            Component::Prefix(_) | Component::ParentDir => return None,
+        }
+    }
+    match components.is_empty() {
+        true => None,
+        false => Some(components.join("/")),
+    }
+}
+
+/// Returns the names of the files of the vault, sorted by path.
+pub fn list_file_names_in_vault(files: &FilesDir, vault_id: &String) -> Vec<String> {
+    list_files_in_vault(files, vault_id)
+        .iter()
+        .map(|f| vault_file_name(files, vault_id, f))
+        .collect()
+}
+
+/// Returns the position of `filename` among the files of the vault, which is
+/// also the index of its leaf in the vault Merkle tree.
+pub fn get_file_index_in_vault(
+    files: &FilesDir,
+    vault_id: &String,
+    filename: &String,
+) -> Option<usize> {
+    get_existing_vault_dir(files, vault_id).ok()?;
+    list_file_names_in_vault(files, vault_id)
+        .iter()
+        .position(|f| f == filename)
+}
+
+/// Returns the positions of `filenames` among the files of the vault, or the
+/// first filename which is not in the vault.
+pub fn get_file_indices_in_vault(
+    files: &FilesDir,
+    vault_id: &String,
+    filenames: &[String],
+) -> Result<Vec<usize>, String> {
+    let files: Vec<String> = match get_existing_vault_dir(files, vault_id) {
+        Ok(_) => list_file_names_in_vault(files, vault_id),
+        Err(_) => Vec::new(),
+    };
+    filenames
+        .iter()
+        .map(|filename| {
+            files
+                .iter()
+                .position(|f| f == filename)
+                .ok_or(filename.clone())
         })
-        .collect::<Result<Vec<_>, io::Error>>()
-        .unwrap()
+        .collect()
 }
 
-pub fn get_existing_vault_dir(vault_id: &String) -> Result<String, Box<dyn Error>> {
-    if vault_id != Path::new(vault_id).file_name().unwrap().to_str().unwrap() {
+pub fn get_existing_vault_dir(
+    files: &FilesDir,
+    vault_id: &String,
+) -> Result<String, Box<dyn Error>> {
+    // `.` and `..` have no file name, and would reach the folders around vaults
+    if Path::new(vault_id)
+        .file_name()
+        .and_then(|name| name.to_str())
+        != Some(vault_id)
+    {
         return Err("Provided vault_id is invalid".into());
     };
-    let vault_dir = format!("./FILES/{vault_id}");
+    let vault_dir = format!("{}/{vault_id}", files.0);
     if Path::new(&vault_dir).is_dir() {
         Ok(vault_dir.to_owned())
     } else {
         Err("Vault does not exists.".into())
     }
 }
+
+/// Returns the path of the file holding the tree parameters of a vault.
+/// It lives next to the vault folder so it is not mistaken for a vaulted file.
+fn vault_params_file(files: &FilesDir, vault_id: &String) -> String {
+    format!("{}/{vault_id}.params", files.0)
+}
+
+/// Persists the tree parameters, the algorithm and the chunk size the vault
+/// has been finalized with.
+pub fn save_vault_params(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+    chunk_size: Option<usize>,
+) -> io::Result<()> {
+    let chunk_size = chunk_size.map(|c| c.to_string()).unwrap_or_default();
+    fs::write(
+        vault_params_file(files, vault_id),
+        format!(
+            "{}\n{algorithm}\n{chunk_size}\n{}\n{}\n",
+            params.mode, params.odd_node, params.commit_leaf_count
+        ),
+    )
+}
+
+/// Returns the tree parameters and algorithm the vault has been finalized
+/// with. Vaults finalized before they were recorded use the default
+/// `TreeParams`, i.e. `HashMode::Legacy`, and `Algorithm::Sha256`.
+pub fn get_vault_params(files: &FilesDir, vault_id: &String) -> (TreeParams, Algorithm) {
+    fn parse<T: FromStr + Default>(lines: &[&str], line: usize) -> T {
+        lines
+            .get(line)
+            .and_then(|l| l.parse().ok())
+            .unwrap_or_default()
+    }
+
+    let params = fs::read_to_string(vault_params_file(files, vault_id)).unwrap_or_default();
+    let lines: Vec<&str> = params.lines().collect();
+    let params = TreeParams {
+        mode: parse(&lines, 0),
+        odd_node: parse(&lines, 3),
+        commit_leaf_count: parse(&lines, 4),
+    };
+    (params, parse(&lines, 1))
+}
+
+/// Returns the size of the chunks the files of the vault are split into, if
+/// the leaf of each file is the root of its chunk tree rather than its hash.
+pub fn get_vault_chunk_size(files: &FilesDir, vault_id: &String) -> Option<usize> {
+    let params = fs::read_to_string(vault_params_file(files, vault_id)).unwrap_or_default();
+    params.lines().nth(2).and_then(|c| c.parse().ok())
+}
+
+/// Removes the tree parameters file of a vault, if any.
+pub fn delete_vault_params(files: &FilesDir, vault_id: &String) {
+    let _ = fs::remove_file(vault_params_file(files, vault_id));
+}
+
+/// Returns the path of the file holding the binary dump of the vault Merkle tree.
+fn vault_tree_file(files: &FilesDir, vault_id: &String) -> String {
+    format!("{}/{vault_id}.tree", files.0)
+}
+
+/// Persists the binary dump of the vault Merkle tree.
+pub fn save_vault_tree(files: &FilesDir, vault_id: &String, dump: &[u8]) -> io::Result<()> {
+    fs::write(vault_tree_file(files, vault_id), dump)
+}
+
+/// Returns the binary dump of the vault Merkle tree.
+pub fn load_vault_tree(files: &FilesDir, vault_id: &String) -> io::Result<Vec<u8>> {
+    fs::read(vault_tree_file(files, vault_id))
+}
+
+/// Removes the binary dump of the vault Merkle tree, if any.
+pub fn delete_vault_tree(files: &FilesDir, vault_id: &String) {
+    let _ = fs::remove_file(vault_tree_file(files, vault_id));
+}
+
+/// Returns the path of the file holding the entries of the sparse Merkle tree
+/// of the vault.
+fn vault_sparse_file(files: &FilesDir, vault_id: &String) -> String {
+    format!("{}/{vault_id}.sparse", files.0)
+}
+
+/// Persists the entries of the sparse Merkle tree of the vault, each one being
+/// its key followed by its value.
+pub fn save_vault_sparse_entries(
+    files: &FilesDir,
+    vault_id: &String,
+    entries: &[u8],
+) -> io::Result<()> {
+    fs::write(vault_sparse_file(files, vault_id), entries)
+}
+
+/// Returns the entries of the sparse Merkle tree of the vault.
+pub fn load_vault_sparse_entries(files: &FilesDir, vault_id: &String) -> io::Result<Vec<u8>> {
+    fs::read(vault_sparse_file(files, vault_id))
+}
+
+/// Removes the entries of the sparse Merkle tree of the vault, if any.
+pub fn delete_vault_sparse_entries(files: &FilesDir, vault_id: &String) {
+    let _ = fs::remove_file(vault_sparse_file(files, vault_id));
+}
+
+/// Returns the path of the folder holding the binary dumps of the chunk trees
+/// of the vault files.
+fn vault_chunks_dir(files: &FilesDir, vault_id: &String) -> String {
+    format!("{}/{vault_id}.chunks", files.0)
+}
+
+/// Persists the binary dump of the chunk tree of a vault file.
+pub fn save_chunk_tree(
+    files: &FilesDir,
+    vault_id: &String,
+    filename: &str,
+    dump: &[u8],
+) -> io::Result<()> {
+    let path = format!("{}/{filename}", vault_chunks_dir(files, vault_id));
+    fs::create_dir_all(Path::new(&path).parent().unwrap())?;
+    fs::write(path, dump)
+}
+
+/// Returns the binary dump of the chunk tree of a vault file.
+pub fn load_chunk_tree(files: &FilesDir, vault_id: &String, filename: &str) -> io::Result<Vec<u8>> {
+    fs::read(format!("{}/{filename}", vault_chunks_dir(files, vault_id)))
+}
+
+/// Removes the binary dump of the chunk tree of a vault file, if any.
+pub fn delete_chunk_tree(files: &FilesDir, vault_id: &String, filename: &str) {
+    let _ = fs::remove_file(format!("{}/{filename}", vault_chunks_dir(files, vault_id)));
+}
+
+/// Removes the binary dumps of the chunk trees of the vault files, if any.
+pub fn delete_chunk_trees(files: &FilesDir, vault_id: &String) {
+    let _ = fs::remove_dir_all(vault_chunks_dir(files, vault_id));
+}
+
+/// Default path of the file holding the Ed25519 key the server signs tree heads
+/// with. It must stay out of the vaults folder, whose files are served.
+pub const DEFAULT_SIGNING_KEY_FILE: &str = "./server.key";
+
+/// Returns the key the server signs tree heads with, stored in `path`,
+/// generating and saving a new one on first launch. It is stored as a
+/// hexadecimal seed, readable by its owner only. A key stored by older
+/// versions in the vaults folder is moved to `path`, keeping the same server
+/// identity.
+pub fn load_or_create_signing_key(files: &FilesDir, path: &Path) -> io::Result<SigningKey> {
+    let legacy_path = Path::new(&files.0).join("server.key");
+    if !path.exists() && legacy_path.is_file() {
+        fs::rename(legacy_path, path)?;
+    }
+    if let Ok(seed) = fs::read_to_string(path) {
+        let seed: [u8; 32] = hex::decode(seed.trim())
+            .ok()
+            .and_then(|seed| seed.try_into().ok())
+            .ok_or(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid signing key",
+            ))?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+
+    let mut seed = [0; 32];
+    getrandom::getrandom(&mut seed).map_err(io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options
+        .open(path)?
+        .write_all(hex::encode(seed).as_bytes())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
@@ -1 +1,4 @@
+pub mod error;
 pub mod fs;
+pub mod range;
+pub mod tree;
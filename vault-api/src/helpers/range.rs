@@ -0,0 +1,101 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::tokio::fs::File;
+use rocket::tokio::io::{AsyncReadExt, AsyncSeekExt};
+use std::io::{self, SeekFrom};
+use std::path::Path;
+
+/// Represents the `Range: bytes=<first>-[<last>]` header of a request, the
+/// single kind of ranges served. Other ranges, such as suffix or multiple
+/// ranges, are ignored and the whole file is sent.
+pub struct ByteRange {
+    first: u64,
+    last: Option<u64>,
+}
+
+impl ByteRange {
+    fn parse(header: &str) -> Option<Self> {
+        let (first, last) = header.strip_prefix("bytes=")?.split_once('-')?;
+        let first = first.trim().parse().ok()?;
+        let last = match last.trim() {
+            "" => None,
+            last => Some(last.parse().ok()?),
+        };
+        match last {
+            Some(last) if last < first => None,
+            _ => Some(Self { first, last }),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ByteRange {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        match req.headers().get_one("Range").and_then(ByteRange::parse) {
+            Some(range) => request::Outcome::Success(range),
+            None => request::Outcome::Forward(()),
+        }
+    }
+}
+
+/// Responds with a file, or with the part of it asked by a `ByteRange`.
+pub struct RangedFile {
+    file: File,
+    content_type: Option<ContentType>,
+    file_size: u64,
+    range: Option<(u64, u64)>,
+}
+
+impl RangedFile {
+    /// Opens the file at `path`, positioned at the start of `range` if any.
+    pub async fn open(path: impl AsRef<Path>, range: Option<ByteRange>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = File::open(path).await?;
+        let file_size = file.metadata().await?.len();
+        let range = range.map(|range| {
+            let end = file_size.saturating_sub(1);
+            (range.first, range.last.map_or(end, |last| last.min(end)))
+        });
+        if let Some((first, _)) = range.filter(|&(first, _)| first < file_size) {
+            file.seek(SeekFrom::Start(first)).await?;
+        }
+        Ok(Self {
+            file,
+            content_type: path
+                .extension()
+                .and_then(|ext| ContentType::from_extension(ext.to_str()?)),
+            file_size,
+            range,
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for RangedFile {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response.header(Header::new("Accept-Ranges", "bytes"));
+        if let Some(content_type) = self.content_type {
+            response.header(content_type);
+        }
+        match self.range {
+            None => response.sized_body(None, self.file),
+            Some((first, _)) if first >= self.file_size => response
+                .status(Status::RangeNotSatisfiable)
+                .header(Header::new(
+                    "Content-Range",
+                    format!("bytes */{}", self.file_size),
+                )),
+            Some((first, last)) => response
+                .status(Status::PartialContent)
+                .header(Header::new(
+                    "Content-Range",
+                    format!("bytes {first}-{last}/{}", self.file_size),
+                ))
+                .streamed_body(self.file.take(last - first + 1)),
+        };
+        response.ok()
+    }
+}
@@ -0,0 +1,363 @@
+use crate::helpers::error::TreeError;
+use crate::helpers::fs::{
+    get_vault_chunk_size, get_vault_params, list_file_names_in_vault, list_files_in_vault,
+    load_chunk_tree, load_vault_sparse_entries, load_vault_tree, save_chunk_tree,
+    save_vault_sparse_entries, save_vault_tree, vault_file_name, FilesDir,
+};
+use rayon::prelude::*;
+use rocket::serde::json::{json, Value};
+use rs_merkle_tree::utils::crypto::{Algorithm, Digest, MerkleHasher, DIGEST_SIZE};
+use rs_merkle_tree::{
+    with_hasher, ChunkTree, DirectoryTree, MerkleError, MerkleRoot, MerkleTree, MerkleTreeView,
+    SparseMerkleTree, TreeParams,
+};
+use std::fs::File;
+use std::io;
+
+/// Builds the chunk tree of a vault file and saves its binary dump.
+fn build_chunk_tree<H: MerkleHasher>(
+    files: &FilesDir,
+    vault_id: &String,
+    path: &str,
+    params: TreeParams,
+    chunk_size: usize,
+) -> io::Result<ChunkTree<H>> {
+    let tree = ChunkTree::<H>::from_reader(File::open(path)?, chunk_size, params)?;
+    // the dump is only a cache of the tree, it is rebuilt on the next request
+    // if it could not be saved
+    let _ = save_chunk_tree(
+        files,
+        vault_id,
+        &vault_file_name(files, vault_id, path),
+        &tree.tree().to_bytes(),
+    );
+    Ok(tree)
+}
+
+/// Computes the leaf of every file of the vault, in listing order, on the rayon
+/// thread pool: the hash of the file, or the root of its chunk tree if the
+/// vault has a `chunk_size`.
+fn vault_leaves<H: MerkleHasher>(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    chunk_size: Option<usize>,
+) -> io::Result<Vec<Digest>> {
+    list_files_in_vault(files, vault_id)
+        .par_iter()
+        .map(|f| match chunk_size {
+            Some(chunk_size) => {
+                Ok(build_chunk_tree::<H>(files, vault_id, f, params, chunk_size)?.root())
+            }
+            None => H::hash_reader(File::open(f)?),
+        })
+        .collect()
+}
+
+/// Builds the Merkle tree of the vault files and returns its tagged root and
+/// number of leaves, along with its binary dump.
+pub fn compute_vault_tree(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+    chunk_size: Option<usize>,
+) -> Result<(MerkleRoot, usize, Vec<u8>), TreeError> {
+    fn tree<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        params: TreeParams,
+        chunk_size: Option<usize>,
+    ) -> Result<(MerkleRoot, usize, Vec<u8>), TreeError> {
+        let leaves = vault_leaves::<H>(files, vault_id, params, chunk_size)?;
+        let tree = MerkleTree::<H>::build_parallel(leaves, params);
+        let root = tree.tagged_root().ok_or(MerkleError::EmptyTree)?;
+        Ok((root, tree.leaf_count(), tree.to_bytes()))
+    }
+
+    with_hasher!(algorithm, |H| tree::<H>(
+        files, vault_id, params, chunk_size
+    ))
+}
+
+/// Returns the binary dump of the vault Merkle tree written at finalize time.
+/// The tree of vaults finalized before dumps were written, or modified since,
+/// is rebuilt and dumped.
+pub fn vault_tree_dump(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<Vec<u8>, TreeError> {
+    if let Ok(dump) = load_vault_tree(files, vault_id) {
+        return Ok(dump);
+    }
+    let chunk_size = get_vault_chunk_size(files, vault_id);
+    let (_, _, dump) = compute_vault_tree(files, vault_id, params, algorithm, chunk_size)?;
+    // the dump is only a cache of the tree, it is rebuilt on the next request
+    // if it could not be saved
+    let _ = save_vault_tree(files, vault_id, &dump);
+    Ok(dump)
+}
+
+/// Loads the Merkle tree of the vault from its dump and returns the JSON proof
+/// for the leaf at position `leaf_index`, as a base64 string of its compact
+/// encoding if `compact` is set.
+pub fn compute_vault_proof(
+    files: &FilesDir,
+    vault_id: &String,
+    leaf_index: usize,
+    params: TreeParams,
+    algorithm: Algorithm,
+    compact: bool,
+) -> Result<Value, TreeError> {
+    fn proof<H: MerkleHasher>(
+        dump: &[u8],
+        leaf_index: usize,
+        compact: bool,
+    ) -> Result<Value, TreeError> {
+        let proof = MerkleTreeView::<H>::from_bytes(dump)?.proof_for_index(leaf_index)?;
+        Ok(match compact {
+            true => json!(proof.to_compact()),
+            false => json!(proof),
+        })
+    }
+
+    let dump = vault_tree_dump(files, vault_id, params, algorithm)?;
+    with_hasher!(algorithm, |H| proof::<H>(&dump, leaf_index, compact))
+}
+
+/// Loads the Merkle tree of the vault from its dump and returns the JSON
+/// multi-proof for the leaves at positions `leaf_indices`.
+pub fn compute_vault_multi_proof(
+    files: &FilesDir,
+    vault_id: &String,
+    leaf_indices: &[usize],
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<Value, TreeError> {
+    fn proof<H: MerkleHasher>(dump: &[u8], leaf_indices: &[usize]) -> Result<Value, TreeError> {
+        Ok(json!(
+            MerkleTreeView::<H>::from_bytes(dump)?.multi_proof(leaf_indices)?
+        ))
+    }
+
+    let dump = vault_tree_dump(files, vault_id, params, algorithm)?;
+    with_hasher!(algorithm, |H| proof::<H>(&dump, leaf_indices))
+}
+
+/// Loads the Merkle trees of the vault and of the chunks of `filename` from
+/// their dumps, and returns the JSON proof of the file leaf at position
+/// `leaf_index` along with the JSON range proof of its chunks `chunks`,
+/// up to the last one if no end is given. The trees are hashed with the
+/// parameters and the algorithm the vault has been finalized with.
+pub fn compute_file_chunks_proof(
+    files: &FilesDir,
+    vault_id: &String,
+    filename: &String,
+    leaf_index: usize,
+    chunks: (usize, Option<usize>),
+    chunk_size: usize,
+) -> Result<Value, TreeError> {
+    fn proof<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        filename: &String,
+        leaf_index: usize,
+        (start, end): (usize, Option<usize>),
+        params: TreeParams,
+        chunk_size: usize,
+    ) -> Result<Value, TreeError> {
+        let vault_dump = vault_tree_dump(files, vault_id, params, H::ALGORITHM)?;
+        let file_proof =
+            MerkleTreeView::<H>::from_bytes(&vault_dump)?.proof_for_index(leaf_index)?;
+
+        let chunks_dump = match load_chunk_tree(files, vault_id, filename) {
+            Ok(dump) => dump,
+            Err(_) => {
+                let path = format!("{}/{vault_id}/{filename}", files.0);
+                build_chunk_tree::<H>(files, vault_id, &path, params, chunk_size)?
+                    .tree()
+                    .to_bytes()
+            }
+        };
+        let chunks = MerkleTreeView::<H>::from_bytes(&chunks_dump)?;
+        let end = end.unwrap_or(chunks.leaf_count());
+        Ok(json!({
+            "chunk_size": chunk_size,
+            "file_proof": file_proof,
+            "chunks": chunks.range_proof(start..end)?,
+        }))
+    }
+
+    let (params, algorithm) = get_vault_params(files, vault_id);
+    with_hasher!(algorithm, |H| proof::<H>(
+        files, vault_id, filename, leaf_index, chunks, params, chunk_size
+    ))
+}
+
+/// Builds the directory tree of the vault, mirroring its folders, from the
+/// leaves of the vault Merkle tree dump paired with the files in listing order.
+fn build_vault_directory_tree<H: MerkleHasher>(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+) -> Result<DirectoryTree<H>, TreeError> {
+    let dump = vault_tree_dump(files, vault_id, params, H::ALGORITHM)?;
+    let tree = MerkleTreeView::<H>::from_bytes(&dump)?;
+    let entries = list_file_names_in_vault(files, vault_id)
+        .into_iter()
+        .zip(tree.iter_leaves().copied());
+    Ok(DirectoryTree::build(entries, params)?)
+}
+
+/// Builds the directory tree of the vault and returns its tagged root.
+pub fn compute_vault_directory_root(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<MerkleRoot, TreeError> {
+    fn root<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        params: TreeParams,
+    ) -> Result<MerkleRoot, TreeError> {
+        Ok(build_vault_directory_tree::<H>(files, vault_id, params)?
+            .tagged_root()
+            .ok_or(MerkleError::EmptyTree)?)
+    }
+
+    with_hasher!(algorithm, |H| root::<H>(files, vault_id, params))
+}
+
+/// Builds the directory tree of the vault and returns the JSON proof of the
+/// file or the folder at `path`, along with the root of the tree.
+pub fn compute_vault_directory_proof(
+    files: &FilesDir,
+    vault_id: &String,
+    path: &str,
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<Value, TreeError> {
+    fn proof<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        path: &str,
+        params: TreeParams,
+    ) -> Result<Value, TreeError> {
+        let tree = build_vault_directory_tree::<H>(files, vault_id, params)?;
+        let root = tree.tagged_root().ok_or(MerkleError::EmptyTree)?;
+        Ok(json!({
+            "proof": tree.proof(path)?,
+            "directory_root": root.to_string(),
+        }))
+    }
+
+    with_hasher!(algorithm, |H| proof::<H>(files, vault_id, path, params))
+}
+
+/// Returns the entries of the sparse Merkle tree of the vault files, sorted by
+/// key: the key of the name of each file and its leaf in the vault Merkle tree.
+/// They are saved at finalize time; those of vaults finalized before they were,
+/// or modified since, are rebuilt from the vault tree dump and saved.
+fn vault_sparse_entries<H: MerkleHasher>(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+) -> Result<Vec<(Digest, Digest)>, TreeError> {
+    if let Ok(bytes) = load_vault_sparse_entries(files, vault_id) {
+        if bytes.len() % (2 * DIGEST_SIZE) == 0 {
+            return Ok(bytes
+                .chunks_exact(2 * DIGEST_SIZE)
+                .map(|entry| {
+                    let (key, value) = entry.split_at(DIGEST_SIZE);
+                    (
+                        Digest::try_from(key).unwrap(),
+                        Digest::try_from(value).unwrap(),
+                    )
+                })
+                .collect());
+        }
+    }
+
+    let dump = vault_tree_dump(files, vault_id, params, H::ALGORITHM)?;
+    let tree = MerkleTreeView::<H>::from_bytes(&dump)?;
+    let mut entries: Vec<(Digest, Digest)> = list_file_names_in_vault(files, vault_id)
+        .iter()
+        .zip(tree.iter_leaves())
+        .map(|(name, leaf)| (SparseMerkleTree::<H>::key(name.as_bytes()), *leaf))
+        .collect();
+    entries.sort_unstable();
+    let bytes: Vec<u8> = entries
+        .iter()
+        .flat_map(|(key, value)| key.0.into_iter().chain(value.0))
+        .collect();
+    // the entries are only a cache, they are rebuilt on the next request if
+    // they could not be saved
+    let _ = save_vault_sparse_entries(files, vault_id, &bytes);
+    Ok(entries)
+}
+
+/// Builds the sparse Merkle tree of the vault files, keyed by file name, from
+/// its saved entries.
+fn build_vault_sparse_tree<H: MerkleHasher>(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+) -> Result<SparseMerkleTree<H>, TreeError> {
+    let mut tree = SparseMerkleTree::new();
+    for (key, value) in vault_sparse_entries::<H>(files, vault_id, params)? {
+        tree.insert(key, value);
+    }
+    Ok(tree)
+}
+
+/// Builds the sparse Merkle tree of the vault files, saving its entries, and
+/// returns its tagged root.
+pub fn compute_vault_sparse_root(
+    files: &FilesDir,
+    vault_id: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<MerkleRoot, TreeError> {
+    fn root<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        params: TreeParams,
+    ) -> Result<MerkleRoot, TreeError> {
+        Ok(build_vault_sparse_tree::<H>(files, vault_id, params)?.tagged_root())
+    }
+
+    with_hasher!(algorithm, |H| root::<H>(files, vault_id, params))
+}
+
+/// Builds the sparse Merkle tree of the vault files and returns the JSON
+/// proof for `filename`, which is an exclusion proof if it is not in the vault,
+/// along with the root the proof leads to.
+pub fn compute_vault_sparse_proof(
+    files: &FilesDir,
+    vault_id: &String,
+    filename: &String,
+    params: TreeParams,
+    algorithm: Algorithm,
+) -> Result<Value, TreeError> {
+    fn proof<H: MerkleHasher>(
+        files: &FilesDir,
+        vault_id: &String,
+        filename: &String,
+        params: TreeParams,
+    ) -> Result<Value, TreeError> {
+        let tree = build_vault_sparse_tree::<H>(files, vault_id, params)?;
+        let proof = tree.proof(&SparseMerkleTree::<H>::key(filename.as_bytes()));
+        // the proof is built from the tree: the root it leads to is the root
+        // of the tree, computed from the leaf up rather than over every key
+        let root = proof.compute_tagged_root()?;
+        Ok(json!({
+            "proof": proof,
+            "sparse_root": root.to_string(),
+        }))
+    }
+
+    with_hasher!(algorithm, |H| proof::<H>(files, vault_id, filename, params))
+}
@@ -1,12 +1,19 @@
 #[macro_use]
 extern crate rocket;
+use rocket::figment::Figment;
 use rocket::serde::json::{json, Value};
+use rocket::{Build, Rocket};
+use std::path::Path;
 
 mod helpers;
 mod routes;
+use helpers::fs::{
+    load_or_create_signing_key, FilesDir, DEFAULT_FILES_DIR, DEFAULT_SIGNING_KEY_FILE,
+};
 use routes::{
-    create_vault, delete_vault, download_file, download_proof, finalize_vault, list_vault_files,
-    upload_file,
+    create_vault, delete_vault, download_absence_proof, download_chunks_proof,
+    download_directory_proof, download_file, download_multi_proof, download_proof, download_tree,
+    finalize_vault, list_vault_files, public_key, upload_file,
 };
 
 #[get("/")]
@@ -17,19 +24,114 @@ fn index() -> Value {
     })
 }
 
+/// Default size limit of uploaded files, above the 1 MiB of Rocket so that
+/// large files can be vaulted. It can be changed with `ROCKET_LIMITS`.
+const UPLOAD_LIMIT: &str = "4 GiB";
+
 #[launch]
 fn rocket() -> _ {
-    rocket::build().mount(
-        "/",
-        routes![
-            index,
-            create_vault,
-            upload_file,
-            finalize_vault,
-            list_vault_files,
-            delete_vault,
-            download_file,
-            download_proof,
-        ],
-    )
+    build(rocket::Config::figment())
+}
+
+/// Builds the server from its configuration: the Rocket one, along with the
+/// folder holding the vaults and the path of the signing key.
+fn build(figment: Figment) -> Rocket<Build> {
+    let figment = figment
+        .join(("limits.file", UPLOAD_LIMIT))
+        .join(("limits.data-form", UPLOAD_LIMIT));
+    // set with `ROCKET_FILES_DIR`
+    let files_dir = FilesDir(
+        figment
+            .extract_inner("files_dir")
+            .unwrap_or(DEFAULT_FILES_DIR.to_string()),
+    );
+    // set with `ROCKET_SIGNING_KEY`, outside of the served files folder
+    let signing_key_file: String = figment
+        .extract_inner("signing_key")
+        .unwrap_or(DEFAULT_SIGNING_KEY_FILE.to_string());
+    let signing_key = load_or_create_signing_key(&files_dir, Path::new(&signing_key_file))
+        .expect("Failed to load the signing key");
+    rocket::custom(figment)
+        .manage(signing_key)
+        .manage(files_dir)
+        .mount(
+            "/",
+            routes![
+                index,
+                public_key,
+                create_vault,
+                upload_file,
+                finalize_vault,
+                list_vault_files,
+                delete_vault,
+                download_file,
+                download_proof,
+                download_multi_proof,
+                download_absence_proof,
+                download_chunks_proof,
+                download_tree,
+                download_directory_proof,
+            ],
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use rocket::http::Status;
+    use rocket::local::blocking::Client;
+    use rocket::serde::json::Value;
+    use std::fs;
+
+    #[test]
+    fn should_not_serve_signing_key() {
+        // the vaults and the key live in a folder removed with the test
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("FILES");
+        let key_file = dir.path().join("server.key");
+        fs::create_dir_all(&files_dir).unwrap();
+        // a key left in the vaults folder by an older version is moved out of it
+        fs::write(files_dir.join("server.key"), "11".repeat(32)).unwrap();
+
+        let figment = rocket::Config::figment()
+            .merge(("files_dir", files_dir.to_str().unwrap()))
+            .merge(("signing_key", key_file.to_str().unwrap()));
+        let client = Client::tracked(build(figment)).unwrap();
+        assert!(!files_dir.join("server.key").exists());
+        assert_eq!(fs::read_to_string(&key_file).unwrap(), "11".repeat(32));
+
+        let vault_id = client
+            .post("/new-vault")
+            .dispatch()
+            .into_json::<Value>()
+            .unwrap()["vault_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(files_dir.join(&vault_id).is_dir());
+        for uri in [
+            "/./server.key".to_string(),
+            "/%2E/server.key".to_string(),
+            "/%2E%2E/server.key".to_string(),
+            format!("/{vault_id}/..%2Fserver.key"),
+            format!("/{vault_id}/%2E%2E%2F%2E%2E%2Fserver.key"),
+        ] {
+            let response = client.get(uri.clone()).dispatch();
+            assert_ne!(response.status(), Status::Ok, "{uri}");
+            assert!(
+                !response.into_string().unwrap_or_default().contains("1111"),
+                "{uri}"
+            );
+        }
+
+        for uri in ["/%2E/list-files", "/%2E%2E/tree", "/unknown/file/proof"] {
+            let response = client.get(uri).dispatch();
+            assert_eq!(
+                response.into_json::<Value>().unwrap()["success"],
+                false,
+                "{uri}"
+            );
+        }
+        dir.close().unwrap();
+    }
 }
@@ -1,42 +1,247 @@
-use crate::helpers::fs::list_files_in_vault;
-use rocket::fs::NamedFile;
-use rocket::serde::json::{json, Value};
-use rs_merkle_tree::{utils::crypto::hash, MerkleTree};
-use std::{fs, path::Path};
+use crate::helpers::error::{tree_error_response, vault_not_found_response};
+use crate::helpers::fs::{
+    get_existing_vault_dir, get_file_index_in_vault, get_file_indices_in_vault,
+    get_vault_chunk_size, get_vault_params, sanitize_file_name, FilesDir,
+};
+use crate::helpers::range::{ByteRange, RangedFile};
+use crate::helpers::tree::{
+    compute_file_chunks_proof, compute_vault_directory_proof, compute_vault_multi_proof,
+    compute_vault_proof, compute_vault_sparse_proof, vault_tree_dump,
+};
+use rocket::http::{ContentType, Status};
+use rocket::response::status::Custom;
+use rocket::serde::json::{json, Json, Value};
+use rocket::serde::Deserialize;
+use rocket::State;
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProofRequest {
+    files: Vec<String>,
+}
 
 #[get("/<vault_id>/<file>")]
-pub async fn download_file(vault_id: String, file: String) -> Option<NamedFile> {
-    NamedFile::open(Path::new("FILES/").join(vault_id).join(file))
+pub async fn download_file(
+    files: &State<FilesDir>,
+    vault_id: String,
+    file: String,
+    range: Option<ByteRange>,
+) -> Option<RangedFile> {
+    get_existing_vault_dir(files, &vault_id).ok()?;
+    // files in folders of the vault are requested with an encoded `/`
+    let file = sanitize_file_name(&file)?;
+    RangedFile::open(Path::new(&files.0).join(vault_id).join(file), range)
         .await
         .ok()
 }
 
-#[get("/<vault_id>/<file>/proof")]
-pub async fn download_proof(vault_id: String, file: String) -> Value {
-    let file_hash = match NamedFile::open(Path::new("FILES/").join(&vault_id).join(file)).await {
-        Ok(file) => hash(&fs::read(file.path()).unwrap()),
-        Err(_) => {
-            return json!({
+#[get("/<vault_id>/tree")]
+pub async fn download_tree(
+    files: &State<FilesDir>,
+    vault_id: String,
+) -> Result<(ContentType, Vec<u8>), Custom<Value>> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return Err(vault_not_found_response(err));
+    }
+    let (params, algorithm) = get_vault_params(files, &vault_id);
+    match vault_tree_dump(files, &vault_id, params, algorithm) {
+        Ok(dump) => Ok((ContentType::Binary, dump)),
+        Err(err) => Err(tree_error_response(err)),
+    }
+}
+
+#[get("/<vault_id>/directory-proof?<path>")]
+pub async fn download_directory_proof(
+    files: &State<FilesDir>,
+    vault_id: String,
+    path: Option<String>,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+
+    let (params, algorithm) = get_vault_params(files, &vault_id);
+    let path = path.unwrap_or_default();
+    match compute_vault_directory_proof(files, &vault_id, &path, params, algorithm) {
+        Ok(mut proof) => {
+            proof["success"] = json!(true);
+            Custom(Status::Ok, proof)
+        }
+        Err(err) => tree_error_response(err),
+    }
+}
+
+#[get("/<vault_id>/<file>/proof?<format>")]
+pub async fn download_proof(
+    files: &State<FilesDir>,
+    vault_id: String,
+    file: String,
+    format: Option<&str>,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+    let compact = match format {
+        None | Some("json") => false,
+        Some("compact") => true,
+        Some(format) => {
+            return Custom(
+                Status::BadRequest,
+                json!({
+                    "success": false,
+                    "message": format!("Unknown proof format `{format}`"),
+                }),
+            )
+        }
+    };
+    let (params, algorithm) = get_vault_params(files, &vault_id);
+    let leaf_index = match get_file_index_in_vault(files, &vault_id, &file) {
+        Some(index) => index,
+        None => {
+            return Custom(
+                Status::NotFound,
+                json!({
+                    "success": false,
+                    "message": "File not found",
+                }),
+            )
+        }
+    };
+
+    match compute_vault_proof(files, &vault_id, leaf_index, params, algorithm, compact) {
+        Ok(proof) => Custom(
+            Status::Ok,
+            json!({
+                "success": true,
+                "proof": proof,
+            }),
+        ),
+        Err(err) => tree_error_response(err),
+    }
+}
+
+#[get("/<vault_id>/<file>/chunks?<start>&<end>")]
+pub async fn download_chunks_proof(
+    files: &State<FilesDir>,
+    vault_id: String,
+    file: String,
+    start: Option<usize>,
+    end: Option<usize>,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+    let leaf_index = match get_file_index_in_vault(files, &vault_id, &file) {
+        Some(index) => index,
+        None => {
+            return Custom(
+                Status::NotFound,
+                json!({
+                    "success": false,
+                    "message": "File not found",
+                }),
+            )
+        }
+    };
+    let chunk_size = match get_vault_chunk_size(files, &vault_id) {
+        Some(chunk_size) => chunk_size,
+        None => {
+            return Custom(
+                Status::Conflict,
+                json!({
+                    "success": false,
+                    "message": "Vault files are not split into chunks",
+                }),
+            )
+        }
+    };
+    let file_size = Path::new(&files.0)
+        .join(&vault_id)
+        .join(&file)
+        .metadata()
+        .map_or(0, |m| m.len());
+
+    let chunks = (start.unwrap_or(0), end);
+    match compute_file_chunks_proof(files, &vault_id, &file, leaf_index, chunks, chunk_size) {
+        Ok(mut proof) => {
+            proof["success"] = json!(true);
+            proof["file_size"] = json!(file_size);
+            Custom(Status::Ok, proof)
+        }
+        Err(err) => tree_error_response(err),
+    }
+}
+
+#[post("/<vault_id>/proof", format = "json", data = "<request>")]
+pub async fn download_multi_proof(
+    files: &State<FilesDir>,
+    vault_id: String,
+    request: Json<ProofRequest>,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+    if request.files.is_empty() {
+        return Custom(
+            Status::BadRequest,
+            json!({
                 "success": false,
-                "message": "File not found",
-            })
+                "message": "No file to prove",
+            }),
+        );
+    }
+    let (params, algorithm) = get_vault_params(files, &vault_id);
+    let leaf_indices = match get_file_indices_in_vault(files, &vault_id, &request.files) {
+        Ok(indices) => indices,
+        Err(file) => {
+            return Custom(
+                Status::NotFound,
+                json!({
+                    "success": false,
+                    "message": format!("File not found: {file}"),
+                }),
+            )
         }
     };
 
-    // TODO: retrieve tree from dump instead of rebuilding it
-
-    let files_hashes: Vec<Vec<u8>> = list_files_in_vault(&vault_id)
-        .iter()
-        .map(|f| {
-            let file = fs::read(f).unwrap();
-            hash(&file)
-        })
-        .collect();
-
-    let tree = MerkleTree::from_leaves(files_hashes);
-    let proof = tree.proof(file_hash).unwrap();
-    return json!({
-        "success": true,
-        "proof": proof,
-    });
+    match compute_vault_multi_proof(files, &vault_id, &leaf_indices, params, algorithm) {
+        Ok(proof) => Custom(
+            Status::Ok,
+            json!({
+                "success": true,
+                "proof": proof,
+            }),
+        ),
+        Err(err) => tree_error_response(err),
+    }
+}
+
+#[get("/<vault_id>/<file>/absence-proof")]
+pub async fn download_absence_proof(
+    files: &State<FilesDir>,
+    vault_id: String,
+    file: String,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+    if get_file_index_in_vault(files, &vault_id, &file).is_some() {
+        return Custom(
+            Status::Conflict,
+            json!({
+                "success": false,
+                "message": "File is in the vault",
+            }),
+        );
+    }
+
+    let (params, algorithm) = get_vault_params(files, &vault_id);
+    match compute_vault_sparse_proof(files, &vault_id, &file, params, algorithm) {
+        Ok(mut proof) => {
+            proof["success"] = json!(true);
+            Custom(Status::Ok, proof)
+        }
+        Err(err) => tree_error_response(err),
+    }
 }
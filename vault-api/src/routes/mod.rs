@@ -2,6 +2,9 @@ mod download;
 mod upload;
 mod vault;
 
-pub use download::{download_file, download_proof};
+pub use download::{
+    download_absence_proof, download_chunks_proof, download_directory_proof, download_file,
+    download_multi_proof, download_proof, download_tree,
+};
 pub use upload::{finalize_vault, upload_file};
-pub use vault::{create_vault, delete_vault, list_vault_files};
+pub use vault::{create_vault, delete_vault, list_vault_files, public_key};
@@ -1,17 +1,34 @@
-use crate::helpers::fs::{get_existing_vault_dir, list_files_in_vault};
+use crate::helpers::error::{merkle_error_response, tree_error_response, vault_not_found_response};
+use crate::helpers::fs::{
+    delete_chunk_tree, delete_vault_sparse_entries, delete_vault_tree, get_existing_vault_dir,
+    sanitize_file_name, save_vault_params, save_vault_tree, FilesDir,
+};
+use crate::helpers::tree::{
+    compute_vault_directory_root, compute_vault_sparse_root, compute_vault_tree,
+};
 use rocket::form::Form;
 use rocket::fs::TempFile;
+use rocket::http::Status;
+use rocket::response::status::Custom;
 use rocket::serde::json::{json, Value};
-use rs_merkle_tree::{utils::crypto::hash, MerkleTree};
-use std::{fs, path::Path};
+use rocket::State;
+use rs_merkle_tree::utils::crypto::{Algorithm, HashMode};
+use rs_merkle_tree::{OddNodeStrategy, SignedTreeHead, SigningKey, TreeParams};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(FromForm)]
 pub struct Upload<'f> {
     file: TempFile<'f>,
 }
 #[post("/<vault_id>/upload", data = "<form>")]
-pub async fn upload_file(vault_id: String, mut form: Form<Upload<'_>>) -> Value {
-    let vault_dir = match get_existing_vault_dir(&vault_id) {
+pub async fn upload_file(
+    files: &State<FilesDir>,
+    vault_id: String,
+    mut form: Form<Upload<'_>>,
+) -> Value {
+    let vault_dir = match get_existing_vault_dir(files, &vault_id) {
         Ok(dir) => dir,
         Err(err) => {
             return json!({
@@ -23,18 +40,35 @@ pub async fn upload_file(vault_id: String, mut form: Form<Upload<'_>>) -> Value
 
     let file = &mut form.file;
     let rname = file.raw_name().unwrap().dangerous_unsafe_unsanitized_raw();
-    let name = Path::new(rname.as_str())
-        .file_name()
-        .unwrap()
-        .to_str()
-        .unwrap();
+    // files may be uploaded into folders of the vault, e.g. `photos/cat.png`
+    let name = match sanitize_file_name(rname.as_str()) {
+        Some(name) => name,
+        None => {
+            return json!({
+                "success": false,
+                "message": format!("Invalid file name `{rname}`"),
+            })
+        }
+    };
     let filename = format!("{vault_dir}/{name}");
+    if let Err(err) = fs::create_dir_all(Path::new(&filename).parent().unwrap()) {
+        return json!({
+            "success": false,
+            "message": format!("Failed to upload the file: {}", err.to_string()),
+        });
+    }
 
     match file.persist_to(filename).await {
-        Ok(_) => json!({
-            "success": true,
-            "message": format!("File uploaded to `{vault_id}`"),
-        }),
+        Ok(_) => {
+            // the tree dumped at finalize time no longer matches the vault
+            delete_vault_tree(files, &vault_id);
+            delete_vault_sparse_entries(files, &vault_id);
+            delete_chunk_tree(files, &vault_id, &name);
+            json!({
+                "success": true,
+                "message": format!("File uploaded to `{vault_id}`"),
+            })
+        }
         Err(err) => {
             println!("ERR upload");
             json!({
@@ -45,21 +79,107 @@ pub async fn upload_file(vault_id: String, mut form: Form<Upload<'_>>) -> Value
     }
 }
 
-#[post("/<vault_id>/finalize")]
-pub fn finalize_vault(vault_id: String) -> Value {
-    let files_hashes: Vec<Vec<u8>> = list_files_in_vault(&vault_id)
-        .into_iter()
-        .map(|f| {
-            let file = fs::read(f).unwrap();
-            hash(&file)
-        })
-        .collect();
+/// The tree parameters a vault is finalized with, as query parameters.
+#[derive(FromForm)]
+pub struct TreeQuery<'r> {
+    mode: Option<&'r str>,
+    odd_node: Option<&'r str>,
+    commit_leaf_count: Option<bool>,
+}
+
+#[post("/<vault_id>/finalize?<algorithm>&<chunk_size>&<tree..>")]
+pub fn finalize_vault(
+    files: &State<FilesDir>,
+    vault_id: String,
+    tree: TreeQuery<'_>,
+    algorithm: Option<&str>,
+    chunk_size: Option<usize>,
+    key: &State<SigningKey>,
+) -> Custom<Value> {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return vault_not_found_response(err);
+    }
+    let mode = match tree.mode.map(str::parse::<HashMode>).transpose() {
+        Ok(mode) => mode.unwrap_or_default(),
+        Err(err) => return merkle_error_response(err),
+    };
 
-    let tree = MerkleTree::from_leaves(files_hashes);
+    let odd_node = match tree.odd_node.map(str::parse::<OddNodeStrategy>).transpose() {
+        Ok(odd_node) => odd_node.unwrap_or_default(),
+        Err(err) => return merkle_error_response(err),
+    };
+    let params = TreeParams {
+        mode,
+        odd_node,
+        commit_leaf_count: tree.commit_leaf_count.unwrap_or_default(),
+    };
 
-    json!({
-        "success": true,
-        "message": format!("Finalizing {vault_id}"),
-        "tree_root": tree.root_hex().unwrap()
-    })
+    let algorithm = match algorithm.map(str::parse::<Algorithm>).transpose() {
+        Ok(algorithm) => algorithm.unwrap_or_default(),
+        Err(err) => return merkle_error_response(err),
+    };
+
+    if chunk_size == Some(0) {
+        return Custom(
+            Status::BadRequest,
+            json!({
+                "success": false,
+                "message": "Chunks must not be empty",
+            }),
+        );
+    }
+
+    let (tree_root, leaf_count, tree_dump) =
+        match compute_vault_tree(files, &vault_id, params, algorithm, chunk_size) {
+            Ok(tree) => tree,
+            Err(err) => return tree_error_response(err),
+        };
+    if let Err(err) = save_vault_tree(files, &vault_id, &tree_dump) {
+        return Custom(
+            Status::InternalServerError,
+            json!({
+                "success": false,
+                "message": format!("Failed to save the vault tree: {}", err.to_string()),
+            }),
+        );
+    }
+    if let Err(err) = save_vault_params(files, &vault_id, params, algorithm, chunk_size) {
+        return Custom(
+            Status::InternalServerError,
+            json!({
+                "success": false,
+                "message": format!("Failed to save the vault tree parameters: {}", err.to_string()),
+            }),
+        );
+    }
+
+    let directory_root = match compute_vault_directory_root(files, &vault_id, params, algorithm) {
+        Ok(root) => root,
+        Err(err) => return tree_error_response(err),
+    };
+    // saves the entries of the sparse tree, from which absence proofs are served
+    delete_vault_sparse_entries(files, &vault_id);
+    let sparse_root = match compute_vault_sparse_root(files, &vault_id, params, algorithm) {
+        Ok(root) => root,
+        Err(err) => return tree_error_response(err),
+    };
+
+    // the receipt of what the server agreed to store
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let tree_head = SignedTreeHead::sign(tree_root.clone(), leaf_count as u64, timestamp, key);
+
+    Custom(
+        Status::Ok,
+        json!({
+            "success": true,
+            "message": format!("Finalizing {vault_id}"),
+            "signed_tree_head": tree_head,
+            "tree_root": tree_root.to_string(),
+            "directory_root": directory_root.to_string(),
+            "sparse_root": sparse_root.to_string(),
+        }),
+    )
 }
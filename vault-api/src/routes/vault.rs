@@ -1,12 +1,25 @@
-use crate::helpers::fs::{get_existing_vault_dir, list_files_in_vault};
+use crate::helpers::fs::{
+    delete_chunk_trees, delete_vault_params, delete_vault_sparse_entries, delete_vault_tree,
+    get_existing_vault_dir, list_file_names_in_vault, FilesDir,
+};
 use rocket::serde::json::{json, Value};
-use std::{fs, path::Path};
+use rocket::State;
+use rs_merkle_tree::SigningKey;
+use std::fs;
 use uuid::Uuid;
 
+#[get("/public-key")]
+pub fn public_key(key: &State<SigningKey>) -> Value {
+    json!({
+        "success": true,
+        "public_key": hex::encode(key.verifying_key().as_bytes()),
+    })
+}
+
 #[post("/new-vault")]
-pub fn create_vault() -> Value {
+pub fn create_vault(files: &State<FilesDir>) -> Value {
     let vault_id = Uuid::new_v4();
-    match fs::create_dir_all(format!("./FILES/{vault_id}")) {
+    match fs::create_dir_all(format!("{}/{vault_id}", files.0)) {
         Ok(_) => json!({
             "success": true,
             "message": "FS created.",
@@ -20,8 +33,8 @@ pub fn create_vault() -> Value {
 }
 
 #[delete("/<vault_id>")]
-pub fn delete_vault(vault_id: String) -> Value {
-    let vault_dir = match get_existing_vault_dir(&vault_id) {
+pub fn delete_vault(files: &State<FilesDir>, vault_id: String) -> Value {
+    let vault_dir = match get_existing_vault_dir(files, &vault_id) {
         Ok(dir) => dir,
         Err(err) => {
             return json!({
@@ -32,6 +45,10 @@ pub fn delete_vault(vault_id: String) -> Value {
     };
 
     fs::remove_dir_all(vault_dir).unwrap();
+    delete_vault_params(files, &vault_id);
+    delete_vault_tree(files, &vault_id);
+    delete_vault_sparse_entries(files, &vault_id);
+    delete_chunk_trees(files, &vault_id);
 
     json!({
         "success": true,
@@ -40,17 +57,15 @@ pub fn delete_vault(vault_id: String) -> Value {
 }
 
 #[get("/<vault_id>/list-files")]
-pub fn list_vault_files(vault_id: String) -> Value {
+pub fn list_vault_files(files: &State<FilesDir>, vault_id: String) -> Value {
+    if let Err(err) = get_existing_vault_dir(files, &vault_id) {
+        return json!({
+            "success": false,
+            "message": err.to_string(),
+        });
+    }
     json!({
         "success": true,
-        "files": list_files_in_vault(&vault_id)
-            .iter().map(|f| {
-                    Path::new(f)
-                        .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .to_string()
-                }).collect::<Vec<String>>()
+        "files": list_file_names_in_vault(files, &vault_id),
     })
 }
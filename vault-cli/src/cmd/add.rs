@@ -1,27 +1,37 @@
 use crate::config::Config;
-use crate::vault::get_staged_files;
+use crate::utils::fs::list_files_recursively;
+use crate::vault::{get_staged_files, staged_entry_line};
 use log::info;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
 
-/// Add file or directory content to the staging area
+/// Add file or directory content to the staging area. The files of a directory
+/// are staged with their path relative to it, so that its subdirectories are
+/// mirrored in the vault.
 pub fn add(path: String) {
     info!("Staging {path}");
 
-    let files: Vec<String> = if Path::new(&path).is_dir() {
-        fs::read_dir(path)
+    let files: Vec<(String, String)> = if Path::new(&path).is_dir() {
+        let dir = fs::canonicalize(path).unwrap();
+        list_files_recursively(&dir)
             .unwrap()
-            .filter(|f| f.as_ref().unwrap().path().is_file())
+            .into_iter()
             .map(|f| {
-                fs::canonicalize(f.unwrap().path())
+                let name = f
+                    .strip_prefix(&dir)
                     .unwrap()
-                    .display()
-                    .to_string()
+                    .components()
+                    .map(|c| c.as_os_str().to_str().unwrap())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (f.display().to_string(), name)
             })
             .collect::<Vec<_>>()
     } else if Path::new(&path).is_file() {
-        vec![fs::canonicalize(path).unwrap().display().to_string()]
+        let f = fs::canonicalize(path).unwrap();
+        let name = f.file_name().unwrap().to_str().unwrap().to_string();
+        vec![(f.display().to_string(), name)]
     } else {
         eprintln!("`{path}` is neither a file nor a directory. Aborting.");
         return;
@@ -30,9 +40,9 @@ pub fn add(path: String) {
     // remove already staged files from the selection
     let staged = get_staged_files();
     let files_to_add: Vec<String> = files
-        .clone()
         .into_iter()
-        .filter(|f| !staged.contains(f))
+        .filter(|(f, _)| !staged.contains(f))
+        .map(|(f, name)| staged_entry_line(&f, &name))
         .collect();
     if files_to_add.is_empty() {
         println!("File(s) already staged. Nothing to do");
@@ -1,66 +1,242 @@
 use crate::config::Config;
-use crate::utils::api::delete_vault;
-use crate::vault::{clear_staging, get_staged_files, save_vault_root_hash};
+use crate::utils::api::{delete_vault, fetch_files_in_vault, fetch_server_key, fetch_vault_tree};
+use crate::utils::error::{exit_with_error, merkle_exit_code};
+use crate::vault::{
+    clear_staging, get_staged_entries, get_trusted_server_key, save_trusted_server_key,
+    save_vault_root_hash, save_vault_tree_head,
+};
 use crate::CliArgs;
 use dialoguer::Confirm;
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
 use log::{error, info};
-use rs_merkle_tree::utils::crypto::{hash, Hash};
-use rs_merkle_tree::MerkleTree;
+use rayon::prelude::*;
+use rs_merkle_tree::utils::crypto::{Digest, HashMode, MerkleHasher, Sha3_256};
+use rs_merkle_tree::{
+    ChunkTree, DirectoryTree, MerkleError, MerkleTree, OddNodeStrategy, SignedTreeHead,
+    SparseMerkleTree, TreeParams, VerifyingKey, DEFAULT_CHUNK_SIZE,
+};
 use serde::Deserialize;
+use std::error::Error;
 use std::fmt::Write;
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write as ioWrite;
 use std::process::exit;
 
+/// Tree parameters used for newly committed vaults: odd nodes are promoted and
+/// the root commits to the number of files, so that no two vaults of different
+/// files share a root.
+const TREE_PARAMS: TreeParams = TreeParams {
+    mode: HashMode::Rfc6962,
+    odd_node: OddNodeStrategy::Promote,
+    commit_leaf_count: true,
+};
+
+/// Hash algorithm used for newly committed vaults.
+type VaultHasher = Sha3_256;
+
+/// Size of the chunks the files of newly committed vaults are split into, so
+/// that downloads are checked chunk by chunk and can be resumed.
+const CHUNK_SIZE: usize = DEFAULT_CHUNK_SIZE;
+
+/// Number of times the files are uploaded before giving up on a vault whose
+/// remote root hash does not match the local one.
+const UPLOAD_ATTEMPTS: usize = 3;
+
 #[derive(Deserialize)]
 struct Response {
     success: bool,
     message: String,
     tree_root: Option<String>,
+    directory_root: Option<String>,
+    sparse_root: Option<String>,
+    signed_tree_head: Option<SignedTreeHead>,
+}
+
+/// The roots of a finalized vault, as computed by the server, and its receipt
+/// of the vault.
+struct FinalizedVault {
+    tree_root: String,
+    directory_root: String,
+    sparse_root: String,
+    signed_tree_head: SignedTreeHead,
 }
 
 /// Commit the vault: Upload all staged files to the server and delete them
 pub fn commit(conf: &CliArgs) {
-    let files = get_staged_files();
-    if files.is_empty() {
+    let entries = get_staged_entries();
+    if entries.is_empty() {
         println!("Nothing to commit. Add files to staging with the `add` command.");
         exit(0);
     }
 
     println!("The following files will be uploaded :");
-    for f in &files {
-        println!("\t{}", f);
+    for (f, name) in &entries {
+        println!("\t{} -> {}", f, name);
     }
     if !(conf.no_interaction || Confirm::new().with_prompt("Continue?").interact().unwrap()) {
         println!("Aborting.");
         return;
     }
 
-    let new_vault_id = create_new_vault(conf);
-    upload_files(&files, &new_vault_id, conf);
-    let remote_root_hash = finalize_upload(&new_vault_id, conf);
+    let files: Vec<String> = entries.iter().map(|(f, _)| f.clone()).collect();
+    let files_hashes = hash_local_files(&files, conf);
+    let local_root_hash =
+        MerkleTree::<VaultHasher>::build_parallel(files_hashes.clone(), TREE_PARAMS)
+            .tagged_root()
+            .unwrap()
+            .to_string();
+    // the vault mirrors the staged folders, whose names must not conflict
+    let names = entries.iter().map(|(_, name)| name.clone());
+    let local_directory_root =
+        match DirectoryTree::<VaultHasher>::build(names.zip(files_hashes.clone()), TREE_PARAMS) {
+            Ok(tree) => tree.tagged_root().unwrap().to_string(),
+            Err(err) => exit_with_error("Invalid staged files", &err),
+        };
+    // the sparse tree of the vault, keyed by name, holds the leaf of each file
+    let mut sparse_tree = SparseMerkleTree::<VaultHasher>::new();
+    for ((_, name), leaf) in entries.iter().zip(&files_hashes) {
+        sparse_tree.insert(SparseMerkleTree::<VaultHasher>::key(name.as_bytes()), *leaf);
+    }
+    let local_sparse_root = sparse_tree.tagged_root().to_string();
 
-    let local_root_hash = compute_local_root(&files, conf);
+    let server_key = trusted_server_key(conf);
+    let new_vault_id = create_new_vault(conf);
+    upload_files(&entries, &new_vault_id, conf);
+    let mut remote = finalize_upload(&new_vault_id, conf);
 
     info!("Local root hash:  {local_root_hash}");
-    info!("Remote root hash: {remote_root_hash}");
-    if remote_root_hash != local_root_hash {
+    info!("Remote root hash: {}", remote.tree_root);
+    let mut attempt = 1;
+    while remote.tree_root != local_root_hash {
         error!("Remote FS seems corrupted.");
+        if attempt == UPLOAD_ATTEMPTS {
+            abort_gracefully(&new_vault_id, conf);
+        }
+        let corrupted = match find_corrupted_files(&entries, &files_hashes, &new_vault_id, conf) {
+            Ok(corrupted) => corrupted,
+            Err(err) => {
+                error!("Couldn't locate the corrupted files: {err}");
+                abort_gracefully(&new_vault_id, conf);
+                unreachable!()
+            }
+        };
+        for (_, name) in &corrupted {
+            error!("Corrupted on the remote FS: {name}");
+        }
+        info!("Uploading {} files again", corrupted.len());
+        upload_files(&corrupted, &new_vault_id, conf);
+        remote = finalize_upload(&new_vault_id, conf);
+        info!("Remote root hash: {}", remote.tree_root);
+        attempt += 1;
+    }
+
+    info!("Local directory root:  {local_directory_root}");
+    info!("Remote directory root: {}", remote.directory_root);
+    if remote.directory_root != local_directory_root {
+        error!("Remote folders do not match the local ones.");
+        abort_gracefully(&new_vault_id, conf);
+    }
+
+    info!("Local sparse root:  {local_sparse_root}");
+    info!("Remote sparse root: {}", remote.sparse_root);
+    if remote.sparse_root != local_sparse_root {
+        error!("Remote sparse tree does not match the local one.");
+        abort_gracefully(&new_vault_id, conf);
+    }
+
+    let tree_head = remote.signed_tree_head;
+    if let Err(err) = check_tree_head(&tree_head, &server_key, &local_root_hash, files.len()) {
+        error!("Invalid receipt from the server: {err}");
         abort_gracefully(&new_vault_id, conf);
     }
 
-    if let Err(err) = save_vault_root_hash(&new_vault_id, &local_root_hash) {
+    if let Err(err) = save_vault_root_hash(
+        &new_vault_id,
+        &local_root_hash,
+        TREE_PARAMS,
+        CHUNK_SIZE,
+        &local_directory_root,
+        &local_sparse_root,
+    ) {
         error!("Failed to save new Vault's new root hash: {err}");
         abort_gracefully(&new_vault_id, conf);
     }
+    if let Err(err) = save_vault_tree_head(&new_vault_id, &tree_head) {
+        error!("Failed to save new Vault's signed tree head: {err}");
+        abort_gracefully(&new_vault_id, conf);
+    }
 
     remove_files(&files);
     clear_staging();
 }
 
-fn compute_local_root(files: &Vec<String>, conf: &CliArgs) -> String {
+/// Returns the key the server signs tree heads with. It is trusted on first
+/// use: pinned in the known servers file the first time a vault is committed
+/// to the server, a server whose key changed since is refused.
+fn trusted_server_key(conf: &CliArgs) -> VerifyingKey {
+    let key = match fetch_server_key(conf) {
+        Ok(key) => key,
+        Err(err) => {
+            error!("Couldn't fetch the server key: {err}");
+            exit(-1);
+        }
+    };
+    match get_trusted_server_key(&conf.api_endpoint) {
+        Ok(Some(trusted)) if trusted != key => {
+            error!("The server key changed since it was trusted, refusing to commit.");
+            error!("Trusted key: {trusted}");
+            error!("Server key:  {key}");
+            exit(merkle_exit_code(&MerkleError::InvalidSignature));
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            info!("Trusting the key of {}: {key}", conf.api_endpoint);
+            if let Err(err) = save_trusted_server_key(&conf.api_endpoint, &key) {
+                error!("Failed to save the server key: {err}");
+                exit(-1);
+            }
+        }
+        Err(err) => {
+            error!("Failed to read the trusted server keys: {err}");
+            exit(-1);
+        }
+    }
+    match parse_public_key(&key) {
+        Ok(key) => key,
+        Err(err) => exit_with_error("Invalid server key", err.as_ref()),
+    }
+}
+
+/// Returns the Ed25519 public key encoded in hexadecimal by `key`.
+fn parse_public_key(key: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+    let bytes: [u8; 32] = hex::decode(key)?.try_into().or(Err("expected 32 bytes"))?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Checks that the tree head was signed with the trusted key of the server and
+/// covers the local root of the vault and its number of files.
+fn check_tree_head(
+    tree_head: &SignedTreeHead,
+    server_key: &VerifyingKey,
+    local_root_hash: &String,
+    file_count: usize,
+) -> Result<(), MerkleError> {
+    tree_head.verify(server_key)?;
+    if tree_head.root.to_string() != *local_root_hash {
+        return Err(MerkleError::RootMismatch);
+    }
+    if tree_head.leaf_count != file_count as u64 {
+        return Err(MerkleError::LeafCountMismatch {
+            expected: file_count,
+            actual: tree_head.leaf_count as usize,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the leaf of each file: the root of the tree of its chunks.
+fn hash_local_files(files: &Vec<String>, conf: &CliArgs) -> Vec<Digest> {
     let pb = conf
         .term_ctx
         .add(ProgressBar::new(files.len().clone().try_into().unwrap()));
@@ -75,24 +251,63 @@ fn compute_local_root(files: &Vec<String>, conf: &CliArgs) -> String {
         .progress_chars("#>-"),
     );
 
-    let files_hashes: Vec<Hash> = files
-        .into_iter()
-        .enumerate()
-        .map(|(i, f)| {
-            let file = fs::read(f).unwrap();
-            pb.set_position(i.try_into().unwrap());
+    let files_hashes: Vec<Digest> = files
+        .par_iter()
+        .map(|f| {
+            let file = fs::File::open(f).unwrap();
+            let chunks =
+                ChunkTree::<VaultHasher>::from_reader(file, CHUNK_SIZE, TREE_PARAMS).unwrap();
+            pb.inc(1);
             pb.set_message(f.clone());
-            hash(&file)
+            chunks.root()
         })
         .collect();
 
     pb.finish_with_message("all hashed computed");
+    files_hashes
+}
 
-    let tree = MerkleTree::from_leaves(files_hashes);
-    tree.root_hex().unwrap()
+/// Returns the files which were not uploaded as they are locally, comparing
+/// the tree of the remote files with the local tree, both sorted by vault
+/// name: only the mismatching subtrees are walked.
+///
+/// Files missing from the remote FS are returned alone, as trees of different
+/// sizes cannot be compared.
+fn find_corrupted_files(
+    entries: &[(String, String)],
+    files_hashes: &[Digest],
+    vault_id: &String,
+    conf: &CliArgs,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let remote_files = fetch_files_in_vault(vault_id, conf);
+    let missing: Vec<(String, String)> = entries
+        .iter()
+        .filter(|(_, name)| !remote_files.contains(name))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Ok(missing);
+    }
+    if let Some(name) = remote_files
+        .iter()
+        .find(|name| !entries.iter().any(|(_, n)| n == *name))
+    {
+        return Err(format!("unexpected remote file `{name}`").into());
+    }
+
+    let local_tree = MerkleTree::<VaultHasher>::build_parallel(files_hashes.to_vec(), TREE_PARAMS);
+    let remote_tree = fetch_vault_tree::<VaultHasher>(vault_id, conf)?;
+
+    Ok(local_tree
+        .diff(&remote_tree)?
+        .into_iter()
+        .map(|index| entries[index].clone())
+        .collect())
 }
 
-fn upload_files(files: &Vec<String>, collection: &String, conf: &CliArgs) {
+/// Uploads each file under its name in the vault, i.e. its path relative to the
+/// staged folder.
+fn upload_files(files: &[(String, String)], collection: &String, conf: &CliArgs) {
     let pb = conf
         .term_ctx
         .add(ProgressBar::new(files.len().clone().try_into().unwrap()));
@@ -107,10 +322,11 @@ fn upload_files(files: &Vec<String>, collection: &String, conf: &CliArgs) {
         .progress_chars("#>-"),
     );
 
-    for (i, f) in files.clone().iter().enumerate() {
-        let form = reqwest::blocking::multipart::Form::new()
-            .file("file", f.clone())
-            .unwrap();
+    for (i, (f, name)) in files.iter().enumerate() {
+        let part = reqwest::blocking::multipart::Part::file(f)
+            .unwrap()
+            .file_name(name.clone());
+        let form = reqwest::blocking::multipart::Form::new().part("file", part);
         let upload = conf
             .http
             .post(format!("{}/{collection}/upload", conf.api_endpoint))
@@ -166,15 +382,42 @@ fn create_new_vault(conf: &CliArgs) -> String {
     exit(-1);
 }
 
-fn finalize_upload(collection: &String, conf: &CliArgs) -> String {
+/// Finalizes the vault and returns its root hashes and the tree head signed by
+/// the server.
+fn finalize_upload(collection: &String, conf: &CliArgs) -> FinalizedVault {
     let remote_files = conf
         .http
         .post(format!("{}/{collection}/finalize", conf.api_endpoint))
+        .query(&[
+            ("mode", TREE_PARAMS.mode.to_string()),
+            ("odd_node", TREE_PARAMS.odd_node.to_string()),
+            (
+                "commit_leaf_count",
+                TREE_PARAMS.commit_leaf_count.to_string(),
+            ),
+            ("algorithm", VaultHasher::ALGORITHM.to_string()),
+            ("chunk_size", CHUNK_SIZE.to_string()),
+        ])
         .send();
     match remote_files.unwrap().json::<Response>() {
         Ok(res) => {
-            if let Some(root_hash) = res.tree_root {
-                return root_hash;
+            if let (
+                Some(tree_root),
+                Some(directory_root),
+                Some(sparse_root),
+                Some(signed_tree_head),
+            ) = (
+                res.tree_root,
+                res.directory_root,
+                res.sparse_root,
+                res.signed_tree_head,
+            ) {
+                return FinalizedVault {
+                    tree_root,
+                    directory_root,
+                    sparse_root,
+                    signed_tree_head,
+                };
             } else {
                 error!("Finalization failed: {}", res.message);
                 abort_gracefully(collection, conf);
@@ -0,0 +1,84 @@
+use super::download::local_root;
+use crate::utils::api::{fetch_files_in_vault, fetch_vault_tree};
+use crate::utils::error::{exit_with_error, merkle_exit_code};
+use crate::vault::get_all_vaults;
+use crate::CliArgs;
+use rs_merkle_tree::utils::crypto::MerkleHasher;
+use rs_merkle_tree::{with_hasher, MerkleError, MerkleRoot, RenderOptions};
+use std::error::Error;
+use std::process::exit;
+
+/// Renders the Merkle tree kept by the server for a vault, its leaves labelled
+/// with the files of the vault, then compares its root with the one saved
+/// locally at commit.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault whose tree to render.
+/// * `file` - The file whose proof path to highlight, if any.
+/// * `dot` - Whether to render a Graphviz digraph instead of ASCII text.
+/// * `hash_len` - The number of hexadecimal characters hashes are truncated to.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+pub fn debug_tree(
+    vault_id: &String,
+    file: Option<String>,
+    dot: bool,
+    hash_len: usize,
+    conf: &CliArgs,
+) {
+    if !get_all_vaults().contains(vault_id) {
+        eprintln!("Vault {vault_id} does not exist.");
+        exit(-1);
+    }
+
+    let (local_root_hash, _) = local_root(vault_id);
+    let rendered = with_hasher!(local_root_hash.algorithm, |H| {
+        render_tree::<H>(vault_id, file, dot, hash_len, conf)
+    });
+    let (rendering, remote_root_hash) = match rendered {
+        Ok(rendered) => rendered,
+        Err(err) => exit_with_error("Couldn't render the vault tree", err.as_ref()),
+    };
+    print!("{rendering}");
+
+    // on stderr, so that the rendering can be piped to Graphviz
+    eprintln!("Local root:  {local_root_hash}");
+    match &remote_root_hash {
+        Some(remote_root_hash) => eprintln!("Remote root: {remote_root_hash}"),
+        None => eprintln!("Remote root: none"),
+    }
+    if remote_root_hash.as_ref() != Some(&local_root_hash) {
+        eprintln!("ERROR: The remote tree does not match the local root hash.");
+        exit(merkle_exit_code(&MerkleError::RootMismatch));
+    }
+}
+
+/// Fetches the tree of a vault and renders it, returning the rendering and the
+/// root of the tree.
+fn render_tree<H: MerkleHasher>(
+    vault_id: &String,
+    file: Option<String>,
+    dot: bool,
+    hash_len: usize,
+    conf: &CliArgs,
+) -> Result<(String, Option<MerkleRoot>), Box<dyn Error>> {
+    let tree = fetch_vault_tree::<H>(vault_id, conf)?;
+    let files = fetch_files_in_vault(vault_id, conf);
+    let highlight = match file {
+        Some(file) => match files.iter().position(|f| *f == file) {
+            Some(index) => Some(index),
+            None => return Err(format!("file `{file}` not found in vault").into()),
+        },
+        None => None,
+    };
+    let options = RenderOptions {
+        hash_len,
+        highlight,
+        leaf_labels: files,
+    };
+    let rendering = match dot {
+        true => tree.to_dot_with(&options)?,
+        false => tree.to_ascii_with(&options)?,
+    };
+    Ok((rendering, tree.tagged_root()))
+}
@@ -1,77 +1,405 @@
-use crate::utils::api::{download_file, fetch_files_in_vault, fetch_proof_for_file};
-use crate::vault::{get_all_vaults, get_root_hash_for_vault};
+use crate::utils::api::{
+    download_file, download_file_from, fetch_chunks_proof_for_file, fetch_directory_proof,
+    fetch_files_in_vault, fetch_multi_proof_for_files, fetch_proof_for_file,
+};
+use crate::utils::error::exit_with_error;
+use crate::utils::fs::is_safe_file_name;
+use crate::vault::{
+    get_all_vaults, get_chunk_size_for_vault, get_directory_root_for_vault,
+    get_root_hash_for_vault, get_tree_params_for_vault,
+};
 use crate::CliArgs;
 use log::info;
+use rs_merkle_tree::utils::crypto::MerkleHasher;
+use rs_merkle_tree::{
+    with_hasher, ChunkTree, ChunkVerifier, EntryKind, MerkleError, MerkleRoot, TreeParams,
+};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::process::exit;
-use std::vec;
 
-/// Download file from any Vault
-pub fn download(filename: &String, vault: Option<String>, conf: &CliArgs) {
-    let files_uri = retrieve_remote_matching_files(filename, vault, conf);
+/// Size of the pieces downloaded files are streamed in.
+const BUFFER_SIZE: usize = 64 * 1024;
 
-    if files_uri.len() == 0 {
-        eprintln!("File {filename} not found in the remote vaults");
-        eprintln!("(use `vault list` to list your remote files)");
-        exit(-1);
+/// Download files from any Vault
+pub fn download(filenames: &Vec<String>, vault: Option<String>, conf: &CliArgs) {
+    let listings = fetch_remote_listings(vault, conf);
+
+    // files to download, grouped by vault so each vault is checked with a single proof
+    let mut vaults: Vec<(String, Vec<String>)> = Vec::new();
+    for filename in filenames {
+        let files_uri = retrieve_remote_matching_files(filename, &listings);
+
+        if files_uri.is_empty() {
+            eprintln!("File {filename} not found in the remote vaults");
+            eprintln!("(use `vault list` to list your remote files)");
+            exit(-1);
+        }
+        if files_uri.len() > 1 {
+            println!("Multiple files are matching {filename}:");
+            for (f, v) in files_uri {
+                eprintln!("\t{f} in {v}");
+            }
+            exit(-1);
+        }
+
+        let (vault_id, uri) = files_uri.into_iter().next().unwrap();
+        // the name comes from the server, it must not write outside of the
+        // current folder
+        if !is_safe_file_name(&uri) {
+            eprintln!("Refusing to download {uri}: invalid file name");
+            exit(-1);
+        }
+        match vaults.iter_mut().find(|(v, _)| *v == vault_id) {
+            Some((_, files)) if files.contains(&uri) => {}
+            Some((_, files)) => files.push(uri),
+            None => vaults.push((vault_id, vec![uri])),
+        }
     }
-    if files_uri.len() > 1 {
-        println!("Multiple files are matching {filename}:");
-        for (f, v) in files_uri {
-            eprintln!("\t{f} in {v}");
+
+    for (vault_id, files) in &vaults {
+        let chunk_size = match get_chunk_size_for_vault(vault_id) {
+            Ok(chunk_size) => chunk_size,
+            Err(err) => exit_with_error("Couldn't read local chunk size", err.as_ref()),
+        };
+        match chunk_size {
+            Some(chunk_size) => download_chunked_files(vault_id, files, chunk_size, conf),
+            None => {
+                for filename in files {
+                    if let Err(err) = download_file(vault_id, filename, &part_file(filename), conf)
+                    {
+                        remove_part_files(files);
+                        eprintln!("Something went wrong while downloading the file: {err}");
+                        exit(-1);
+                    }
+                }
+                check_downloaded_files(vault_id, files, conf);
+            }
+        }
+        check_directory_proofs(vault_id, files, chunk_size, conf);
+        // the files are only moved in place once every proof is checked
+        for filename in files {
+            if let Err(err) = fs::rename(part_file(filename), filename) {
+                exit_with_error("Couldn't save the downloaded file", &err);
+            }
+            info!("'{filename}' downloaded successfully.");
         }
-        exit(-1);
     }
+}
 
-    let (vault_id, filename) = &files_uri[0];
-    if let Err(err) = download_file(&vault_id, &filename, conf) {
-        eprintln!("Something went wrong while downloading the file: {err}");
-        exit(-1);
+/// Returns the path a file is downloaded to, next to `filename`, until its
+/// proofs are checked.
+fn part_file(filename: &str) -> String {
+    format!("{filename}.part")
+}
+
+/// Removes the part files of downloaded files which are not split into chunks:
+/// unlike chunks, they are not checked as they are received and cannot be
+/// resumed.
+fn remove_part_files(filenames: &[String]) {
+    for filename in filenames {
+        let _ = fs::remove_file(part_file(filename));
+    }
+}
+
+/// Returns the root hash saved locally at commit for a vault, and the tree
+/// parameters it was computed with, exiting if they cannot be read.
+pub(super) fn local_root(vault_id: &String) -> (MerkleRoot, TreeParams) {
+    let local_root_hash: MerkleRoot = match get_root_hash_for_vault(vault_id) {
+        Ok(root) => match root.parse() {
+            Ok(root) => root,
+            Err(err) => exit_with_error("Invalid local root hash", &err),
+        },
+        Err(err) => exit_with_error("Couldn't read local root hash", err.as_ref()),
+    };
+    let params = match get_tree_params_for_vault(vault_id) {
+        Ok(params) => params,
+        Err(err) => exit_with_error("Couldn't read local tree parameters", err.as_ref()),
+    };
+    (local_root_hash, params)
+}
+
+/// Downloads files of a vault whose files are split into chunks of
+/// `chunk_size`, checking each chunk against the root hash saved locally at
+/// commit as it is received, and exiting at the first altered chunk.
+fn download_chunked_files(
+    vault_id: &String,
+    filenames: &[String],
+    chunk_size: usize,
+    conf: &CliArgs,
+) {
+    let (local_root_hash, params) = local_root(vault_id);
+    for filename in filenames {
+        let downloaded = with_hasher!(local_root_hash.algorithm, |H| {
+            download_chunked_file::<H>(
+                vault_id,
+                filename,
+                chunk_size,
+                &local_root_hash,
+                params,
+                conf,
+            )
+        });
+        if let Err(err) = downloaded {
+            if err.downcast_ref::<MerkleError>().is_some() {
+                eprintln!("ERROR: File alteration detected.");
+            }
+            exit_with_error(
+                "Something went wrong while downloading the file",
+                err.as_ref(),
+            );
+        }
+    }
+}
+
+/// Downloads a file split into chunks to `<filename>.part`, checking every
+/// chunk. It is renamed to `filename` once its directory proof is checked too.
+///
+/// The download resumes from the chunks already checked in `<filename>.part`
+/// by a previous attempt: only the rest of the file is requested, with a
+/// `Range` header. The part file is truncated to the checked chunks when the
+/// download fails, e.g. on an altered chunk.
+fn download_chunked_file<H: MerkleHasher>(
+    vault_id: &String,
+    filename: &String,
+    chunk_size: usize,
+    local_root_hash: &MerkleRoot,
+    params: TreeParams,
+    conf: &CliArgs,
+) -> Result<(), Box<dyn Error>> {
+    let part = part_file(filename);
+    if let Some(dir) = Path::new(filename).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // the chunk holding the last downloaded byte is downloaded again, so that
+    // a complete part file still has a chunk to check
+    let downloaded = fs::metadata(&part).map_or(0, |m| m.len());
+    let start = (downloaded.saturating_sub(1) / chunk_size as u64) as usize;
+
+    let proof = fetch_chunks_proof_for_file::<H>(vault_id, filename, start, conf)?;
+    if proof.chunk_size != chunk_size {
+        return Err("the proof does not match the local chunk size".into());
+    }
+    let root = MerkleRoot::new(
+        H::ALGORITHM,
+        proof.file_proof.compute_root_with_params(params)?,
+    );
+    if root != *local_root_hash {
+        return Err(MerkleError::RootMismatch.into());
     }
+    // the chunk count is not covered by the proof hashes: the proof must
+    // also cover the requested chunks up to the end of the file
+    if proof.chunks.start != start || proof.chunks.range()?.end != proof.chunks.leaf_count {
+        return Err("the proof does not cover the rest of the file".into());
+    }
+    let file_root = proof
+        .file_proof
+        .nodes
+        .first()
+        .ok_or(MerkleError::EmptyTree)?;
+    let mut verifier = ChunkVerifier::<H>::new(&proof.chunks, &file_root.hash, chunk_size)?;
+
+    let offset = start as u64 * chunk_size as u64;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&part)?;
+    file.set_len(offset)?;
+    file.seek(SeekFrom::End(0))?;
+
+    let mut res = download_file_from(vault_id, filename, offset, conf)?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let received = loop {
+        let len = match res.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => break Err(err.into()),
+        };
+        if let Err(err) = verifier.update(&buffer[..len]) {
+            break Err(err.into());
+        }
+        file.write_all(&buffer[..len])?;
+    };
+    let verified_len = offset + verifier.verified_len();
+    if let Err(err) = received.and_then(|_| Ok(verifier.finalize()?)) {
+        file.set_len(verified_len)?;
+        return Err(err);
+    }
+    Ok(())
+}
 
-    let proof = match fetch_proof_for_file(&vault_id, &filename, conf) {
-        Ok(proof) => proof,
+/// Checks the downloaded files of a vault against the root hash saved locally
+/// at commit, removing them and exiting if they do not match.
+fn check_downloaded_files(vault_id: &String, filenames: &[String], conf: &CliArgs) {
+    let (local_root_hash, params) = local_root(vault_id);
+    let remote_root_hash = with_hasher!(local_root_hash.algorithm, |H| {
+        compute_remote_root::<H>(vault_id, filenames, params, conf)
+    });
+    let remote_root_hash = match remote_root_hash {
+        Ok(root) => root,
         Err(err) => {
-            eprintln!("Something went wrong while fetching proof: {err}");
-            exit(-1);
+            remove_part_files(filenames);
+            exit_with_error(
+                "Something went wrong while checking the proof",
+                err.as_ref(),
+            )
         }
     };
-    let local_root_hash = get_root_hash_for_vault(&vault_id).unwrap();
-    if proof.compute_root_hex().unwrap() != local_root_hash {
+    if remote_root_hash != local_root_hash {
+        remove_part_files(filenames);
         eprintln!("ERROR: File alteration detected.");
         exit(-1);
     }
+}
+
+/// Fetches the Merkle proof of downloaded files, checks that it proves these
+/// very files and computes the root it leads to. A single file is checked
+/// with a single-leaf proof, several files with one multi-proof.
+fn compute_remote_root<H: MerkleHasher>(
+    vault_id: &String,
+    filenames: &[String],
+    params: TreeParams,
+    conf: &CliArgs,
+) -> Result<MerkleRoot, Box<dyn Error>> {
+    if let [filename] = filenames {
+        let proof = fetch_proof_for_file::<H>(vault_id, filename, conf)?;
+        let leaf = H::hash_reader(File::open(part_file(filename))?)?;
+        if proof.nodes.first().map(|node| &node.hash) != Some(&leaf) {
+            return Err("the proof does not prove the downloaded file".into());
+        }
+        return Ok(MerkleRoot::new(
+            H::ALGORITHM,
+            proof.compute_root_with_params(params)?,
+        ));
+    }
 
-    info!("'{filename}' downloaded successfully.");
+    let proof = fetch_multi_proof_for_files::<H>(vault_id, filenames, conf)?;
+    if proof.leaves.len() != filenames.len() {
+        return Err("the proof does not prove the downloaded files".into());
+    }
+    for filename in filenames {
+        let leaf = H::hash_reader(File::open(part_file(filename))?)?;
+        if !proof.leaves.contains(&leaf) {
+            return Err(format!("the proof does not prove the downloaded file {filename}").into());
+        }
+    }
+    Ok(MerkleRoot::new(
+        H::ALGORITHM,
+        proof.compute_root_with_params(params)?,
+    ))
 }
 
-fn retrieve_remote_matching_files(
-    filename: &String,
-    vault: Option<String>,
+/// Checks the downloaded files of a vault against the root of its directory
+/// tree saved locally at commit, with proofs chaining each file through the
+/// folders holding it, and exits if they do not match, removing the files
+/// which are not split into chunks. Vaults committed before folders were
+/// mirrored are not checked.
+fn check_directory_proofs(
+    vault_id: &String,
+    filenames: &[String],
+    chunk_size: Option<usize>,
     conf: &CliArgs,
-) -> Vec<(String, String)> {
-    match vault {
-        Some(vault_id) => {
-            if let Some(uri) = fetch_files_in_vault(&vault_id, conf).iter().find(|f| {
-                info!("\t- {f}");
-                Path::new(f).file_name().unwrap().to_str() == Some(filename)
-            }) {
-                return vec![(vault_id, uri.clone())];
+) {
+    let local_directory_root: MerkleRoot = match get_directory_root_for_vault(vault_id) {
+        Ok(Some(root)) => match root.parse() {
+            Ok(root) => root,
+            Err(err) => exit_with_error("Invalid local directory root", &err),
+        },
+        Ok(None) => return,
+        Err(err) => exit_with_error("Couldn't read local directory root", err.as_ref()),
+    };
+    let (_, params) = local_root(vault_id);
+    for filename in filenames {
+        let directory_root = with_hasher!(local_directory_root.algorithm, |H| {
+            compute_directory_root::<H>(vault_id, filename, chunk_size, params, conf)
+        });
+        let err = match directory_root {
+            Ok(root) if root == local_directory_root => continue,
+            Ok(_) => None,
+            Err(err) => Some(err),
+        };
+        // chunks are checked as they are received: their part files are kept
+        // to resume the download
+        if chunk_size.is_none() {
+            remove_part_files(filenames);
+        }
+        match err {
+            None => {
+                eprintln!("ERROR: File alteration detected.");
+                exit(-1);
             }
-            return Vec::<(String, String)>::new();
+            Some(err) => exit_with_error(
+                "Something went wrong while checking the directory proof",
+                err.as_ref(),
+            ),
         }
-        None => {
-            let mut matches = Vec::<(String, String)>::new();
-            for vault_id in get_all_vaults() {
-                info!("Searching in vault {vault_id}");
-                if let Some(uri) = fetch_files_in_vault(&vault_id, conf).iter().find(|f| {
-                    info!("\t- {f}");
-                    Path::new(f).file_name().unwrap().to_str() == Some(filename)
-                }) {
-                    matches.push((vault_id.clone(), uri.clone()));
-                }
+    }
+}
+
+/// Fetches the directory proof of a downloaded file, checks that it proves
+/// this very file and computes the root it leads to from the leaf of the file:
+/// the root of its chunk tree if the vault has a `chunk_size`, its hash otherwise.
+fn compute_directory_root<H: MerkleHasher>(
+    vault_id: &String,
+    filename: &String,
+    chunk_size: Option<usize>,
+    params: TreeParams,
+    conf: &CliArgs,
+) -> Result<MerkleRoot, Box<dyn Error>> {
+    let file = File::open(part_file(filename))?;
+    let leaf = match chunk_size {
+        Some(chunk_size) => ChunkTree::<H>::from_reader(file, chunk_size, params)?.root(),
+        None => H::hash_reader(file)?,
+    };
+    let proof = fetch_directory_proof::<H>(vault_id, filename, conf)?;
+    if proof.path != *filename || proof.kind != EntryKind::File {
+        return Err("the proof does not prove the downloaded file".into());
+    }
+    Ok(MerkleRoot::new(
+        H::ALGORITHM,
+        proof.compute_root_with_params(&leaf, params)?,
+    ))
+}
+
+/// Fetches the list of files of the given vault, or of all the known vaults.
+fn fetch_remote_listings(vault: Option<String>, conf: &CliArgs) -> Vec<(String, Vec<String>)> {
+    let vaults = match vault {
+        Some(vault_id) => vec![vault_id],
+        None => get_all_vaults(),
+    };
+    vaults
+        .into_iter()
+        .map(|vault_id| {
+            info!("Searching in vault {vault_id}");
+            let files = fetch_files_in_vault(&vault_id, conf);
+            for f in &files {
+                info!("\t- {f}");
             }
-            return matches;
+            (vault_id, files)
+        })
+        .collect()
+}
+
+fn retrieve_remote_matching_files(
+    filename: &String,
+    listings: &Vec<(String, Vec<String>)>,
+) -> Vec<(String, String)> {
+    let mut matches = Vec::<(String, String)>::new();
+    for (vault_id, files) in listings {
+        // files are requested by their path in the vault, or by their name
+        for uri in files.iter().filter(|f| {
+            *f == filename || Path::new(f).file_name().unwrap().to_str() == Some(filename)
+        }) {
+            matches.push((vault_id.clone(), uri.clone()));
         }
     }
+    // a file requested by its path is preferred to files of the same name in folders
+    if matches.iter().any(|(_, uri)| uri == filename) {
+        matches.retain(|(_, uri)| uri == filename);
+    }
+    matches
 }
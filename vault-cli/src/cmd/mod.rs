@@ -1,6 +1,7 @@
 pub use add::add;
 pub use clear::clear;
 pub use commit::commit;
+pub use debug::debug_tree;
 pub use delete::delete;
 pub use download::download;
 pub use list::list;
@@ -10,6 +11,7 @@ pub use status::status;
 mod add;
 mod clear;
 mod commit;
+mod debug;
 mod delete;
 mod download;
 mod list;
@@ -1,5 +1,6 @@
 use crate::config::Config;
-use crate::vault::get_staged_files;
+use crate::utils::fs::list_files_recursively;
+use crate::vault::{get_staged_entries, staged_entry_line};
 use log::info;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
@@ -10,15 +11,10 @@ pub fn remove(path: String) {
     info!("Unstaging {path}");
 
     let files: Vec<String> = if Path::new(&path).is_dir() {
-        fs::read_dir(path)
+        list_files_recursively(fs::canonicalize(path).unwrap())
             .unwrap()
-            .filter(|f| f.as_ref().unwrap().path().is_file())
-            .map(|f| {
-                fs::canonicalize(f.unwrap().path())
-                    .unwrap()
-                    .display()
-                    .to_string()
-            })
+            .into_iter()
+            .map(|f| f.display().to_string())
             .collect::<Vec<_>>()
     } else if Path::new(&path).is_file() {
         vec![fs::canonicalize(path).unwrap().display().to_string()]
@@ -27,11 +23,11 @@ pub fn remove(path: String) {
     };
 
     // remove selected files from the staging
-    let staged = get_staged_files();
+    let staged = get_staged_entries();
     let new_staging: Vec<String> = staged
-        .clone()
         .into_iter()
-        .filter(|f| !files.contains(f))
+        .filter(|(f, _)| !files.contains(f))
+        .map(|(f, name)| staged_entry_line(&f, &name))
         .collect();
 
     let mut staging_conf_file = OpenOptions::new()
@@ -1,18 +1,17 @@
-use crate::config::Config;
-use crate::utils::fs::lines_from_file;
+use crate::vault::get_staged_entries;
 use std::process::exit;
 
 /// List staged files to be commited to the Vault
 pub fn status() {
-    let files = lines_from_file(Config::staging_file()).unwrap();
+    let files = get_staged_entries();
     if files.is_empty() {
         println!("Nothing to commit. Add files to staging with the `vault add <path>` command.");
         exit(0);
     }
 
     println!("Staged files to be commited:");
-    for f in &files {
-        println!("\t{}", f);
+    for (f, name) in &files {
+        println!("\t{} -> {}", f, name);
     }
     println!("\n(use `vault remove <file>` or `vault clear` to unstage)\n");
 }
@@ -32,4 +32,15 @@ impl Config {
         }
         return path;
     }
+
+    /// Returns path to the file of the keys trusted for each server, pinned on
+    /// first use: each line holds a server endpoint and its public key.
+    /// Will create the file if doesn't exists.
+    pub fn known_servers_file() -> PathBuf {
+        let path = Self::config_dir().join("known_servers");
+        if !(Path::new(&path).is_file()) {
+            fs::File::create(&path).unwrap();
+        }
+        path
+    }
 }
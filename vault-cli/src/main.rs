@@ -7,7 +7,7 @@ mod cmd;
 mod config;
 mod utils;
 mod vault;
-use cmd::{add, clear, commit, delete, download, list, remove, status};
+use cmd::{add, clear, commit, debug_tree, delete, download, list, remove, status};
 
 use indicatif_log_bridge::LogWrapper;
 
@@ -52,9 +52,10 @@ enum Commands {
     /// List all files from all vaults
     List {},
 
-    /// Download a file from any vault.
+    /// Download files from any vault.
     Download {
-        file: String,
+        #[arg(required = true)]
+        files: Vec<String>,
 
         /// Specify vault id (needed on duplicates between vaults)
         #[arg(short, long)]
@@ -63,6 +64,32 @@ enum Commands {
 
     /// Delete a given vault
     Delete { vault_id: String },
+
+    /// Inspect vaults to debug integrity failures
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DebugCommands {
+    /// Render the Merkle tree of a vault, its leaves labelled with its files
+    Tree {
+        vault_id: String,
+
+        /// Highlight the proof path of a file
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// Render a Graphviz digraph instead of ASCII text
+        #[arg(long, action)]
+        dot: bool,
+
+        /// Number of hexadecimal characters hashes are truncated to
+        #[arg(long, default_value_t = 8)]
+        hash_len: usize,
+    },
 }
 
 fn main() {
@@ -90,7 +117,15 @@ fn main() {
         Commands::Clear {} => clear(),
         Commands::Commit {} => commit(&conf),
         Commands::List {} => list(&conf),
-        Commands::Download { file, vault_id } => download(&file, vault_id, &conf),
+        Commands::Download { files, vault_id } => download(&files, vault_id, &conf),
         Commands::Delete { vault_id } => delete(&vault_id, &conf),
+        Commands::Debug { command } => match command {
+            DebugCommands::Tree {
+                vault_id,
+                file,
+                dot,
+                hash_len,
+            } => debug_tree(&vault_id, file, dot, hash_len, &conf),
+        },
     }
 }
@@ -1,9 +1,15 @@
 use crate::CliArgs;
 use log::error;
-use rs_merkle_tree::MerkleProof;
-use serde::Deserialize;
+use reqwest::blocking::Response;
+use reqwest::header::RANGE;
+use reqwest::{StatusCode, Url};
+use rs_merkle_tree::utils::crypto::MerkleHasher;
+use rs_merkle_tree::{
+    CompactProof, DirectoryProof, MerkleMultiProof, MerkleProof, MerkleTree, RangeProof,
+};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::io::Cursor;
+use std::path::Path;
 use std::process::exit;
 
 #[derive(Deserialize)]
@@ -14,6 +20,37 @@ struct ListFilesResponse {
     files: Option<Vec<String>>,
 }
 
+/// Returns the URL of a file of a vault, followed by the `rest` segments.
+///
+/// Files in folders of the vault are named by their `/` separated path, which
+/// is percent-encoded into a single segment.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault where the file is stored.
+/// * `filename` - The name of the file.
+/// * `rest` - The segments following the name of the file, e.g. `proof`.
+/// * `conf` - The CLI configuration containing the API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the URL, or an error if the API endpoint is not a valid base URL.
+fn file_url(
+    vault_id: &str,
+    filename: &str,
+    rest: &[&str],
+    conf: &CliArgs,
+) -> Result<Url, Box<dyn Error>> {
+    let mut url = Url::parse(&conf.api_endpoint)?;
+    url.path_segments_mut()
+        .or(Err("Invalid API endpoint"))?
+        .pop_if_empty()
+        .push(vault_id)
+        .push(filename)
+        .extend(rest);
+    Ok(url)
+}
+
 /// Fetches a list of files stored in a vault.
 ///
 /// # Arguments
@@ -45,12 +82,38 @@ pub fn fetch_files_in_vault(vault_id: &String, conf: &CliArgs) -> Vec<String> {
     exit(-1);
 }
 
+/// Fetches the Merkle tree of a vault, from the binary dump kept by the server.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault whose tree to fetch.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the tree, whose leaves are in the order of
+/// `fetch_files_in_vault`, or an error if there's an issue fetching or loading it.
+pub fn fetch_vault_tree<H: MerkleHasher>(
+    vault_id: &String,
+    conf: &CliArgs,
+) -> Result<MerkleTree<H>, Box<dyn Error>> {
+    let res = conf
+        .http
+        .get(format!("{}/{vault_id}/tree", conf.api_endpoint))
+        .send()?;
+    if !res.status().is_success() {
+        return Err(format!("unexpected response status {}", res.status()).into());
+    }
+    Ok(MerkleTree::from_bytes(&res.bytes()?)?)
+}
+
 /// Downloads a file from a vault and saves it locally.
 ///
 /// # Arguments
 ///
 /// * `vault_id` - The ID of the vault where the file is stored.
 /// * `filename` - The name of the file to download.
+/// * `path` - The local path to save the file to.
 /// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
 ///
 /// # Returns
@@ -59,25 +122,129 @@ pub fn fetch_files_in_vault(vault_id: &String, conf: &CliArgs) -> Vec<String> {
 pub fn download_file(
     vault_id: &String,
     filename: &String,
+    path: &str,
     conf: &CliArgs,
 ) -> Result<(), Box<dyn Error>> {
-    let res = conf
+    let mut res = conf
         .http
-        .get(format!("{}/{vault_id}/{filename}", conf.api_endpoint))
+        .get(file_url(vault_id, filename, &[], conf)?)
         .send()?;
 
-    let mut file = std::fs::File::create(filename)?;
-    let mut content = Cursor::new(res.bytes()?);
-    std::io::copy(&mut content, &mut file)?;
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::File::create(path)?;
+    res.copy_to(&mut file)?;
     Ok(())
 }
 
+/// Downloads a file from a vault, from the byte at `offset` on, and returns
+/// the response to read its content from.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault where the file is stored.
+/// * `filename` - The name of the file to download.
+/// * `offset` - The position of the first byte to download.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the response if the server sent the requested bytes,
+/// or an error if there's an issue sending the request.
+pub fn download_file_from(
+    vault_id: &String,
+    filename: &String,
+    offset: u64,
+    conf: &CliArgs,
+) -> Result<Response, Box<dyn Error>> {
+    let mut req = conf.http.get(file_url(vault_id, filename, &[], conf)?);
+    if offset > 0 {
+        req = req.header(RANGE, format!("bytes={offset}-"));
+    }
+    let res = req.send()?;
+    match res.status() {
+        StatusCode::OK if offset == 0 => Ok(res),
+        StatusCode::PARTIAL_CONTENT => Ok(res),
+        status => Err(format!("unexpected response status {status}").into()),
+    }
+}
+
+/// The proof of the chunks of a file, along with the proof of the file itself.
+#[derive(Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct ChunksProof<H: MerkleHasher> {
+    pub chunk_size: usize,
+    pub file_size: u64,
+    pub file_proof: MerkleProof<H>,
+    pub chunks: RangeProof<H>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(bound = "")]
+#[allow(dead_code)]
+struct ChunksProofResponse<H: MerkleHasher> {
+    success: bool,
+    message: Option<String>,
+    #[serde(flatten)]
+    proof: Option<ChunksProof<H>>,
+}
+
+/// Fetches the proof of the chunks of a file of a vault, from the chunk at
+/// position `start` to the last one.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault where the file is stored.
+/// * `filename` - The name of the file for which to fetch the proof.
+/// * `start` - The position of the first chunk to prove.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the proof if successful, or an error if there's an issue
+/// fetching or parsing it.
+pub fn fetch_chunks_proof_for_file<H: MerkleHasher>(
+    vault_id: &String,
+    filename: &String,
+    start: usize,
+    conf: &CliArgs,
+) -> Result<ChunksProof<H>, Box<dyn Error>> {
+    let res = conf
+        .http
+        .get(file_url(vault_id, filename, &["chunks"], conf)?)
+        .query(&[("start", start)])
+        .send()?;
+
+    let res = res
+        .json::<ChunksProofResponse<H>>()
+        .or(Err("Error while parsing response"))?;
+    match res.proof {
+        Some(proof) => Ok(proof),
+        None => {
+            if let Some(message) = res.message {
+                return Err(message.into());
+            }
+            Err("Unable to parse return value".into())
+        }
+    }
+}
+
+/// A proof as returned by the server: compact when it supports it, JSON otherwise.
+#[derive(Deserialize, Debug)]
+#[serde(bound = "", untagged)]
+enum ProofPayload<H: MerkleHasher> {
+    Compact(CompactProof),
+    Json(MerkleProof<H>),
+}
+
 #[derive(Deserialize, Debug)]
+#[serde(bound = "")]
 #[allow(dead_code)]
-struct ProofResponse {
+struct ProofResponse<H: MerkleHasher> {
     success: bool,
     message: Option<String>,
-    proof: Option<MerkleProof>,
+    proof: Option<ProofPayload<H>>,
 }
 
 /// Fetches a Merkle proof for a specific file in a vault.
@@ -92,18 +259,71 @@ struct ProofResponse {
 ///
 /// A `Result` containing the Merkle proof if successful, or an error if there's an issue
 /// fetching or parsing the proof.
-pub fn fetch_proof_for_file(
+pub fn fetch_proof_for_file<H: MerkleHasher>(
     vault_id: &String,
     filename: &String,
     conf: &CliArgs,
-) -> Result<MerkleProof, Box<dyn Error>> {
+) -> Result<MerkleProof<H>, Box<dyn Error>> {
+    let res = conf
+        .http
+        .get(file_url(vault_id, filename, &["proof"], conf)?)
+        .query(&[("format", "compact")])
+        .send()?;
+
+    let res = res
+        .json::<ProofResponse<H>>()
+        .or(Err("Error while parsing response"))?;
+    match res.proof {
+        Some(ProofPayload::Compact(proof)) => Ok(proof.decode()?),
+        Some(ProofPayload::Json(proof)) => Ok(proof),
+        None => {
+            if let Some(message) = res.message {
+                return Err(message.into());
+            }
+            Err("Unable to parse return value".into())
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MultiProofRequest<'a> {
+    files: &'a [String],
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(bound = "")]
+#[allow(dead_code)]
+struct MultiProofResponse<H: MerkleHasher> {
+    success: bool,
+    message: Option<String>,
+    proof: Option<MerkleMultiProof<H>>,
+}
+
+/// Fetches a single Merkle multi-proof for several files of a vault.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault where the files are stored.
+/// * `filenames` - The names of the files for which to fetch the Merkle multi-proof.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the Merkle multi-proof if successful, or an error if there's an
+/// issue fetching or parsing the proof.
+pub fn fetch_multi_proof_for_files<H: MerkleHasher>(
+    vault_id: &String,
+    filenames: &[String],
+    conf: &CliArgs,
+) -> Result<MerkleMultiProof<H>, Box<dyn Error>> {
     let res = conf
         .http
-        .get(format!("{}/{vault_id}/{filename}/proof", conf.api_endpoint))
+        .post(format!("{}/{vault_id}/proof", conf.api_endpoint))
+        .json(&MultiProofRequest { files: filenames })
         .send()?;
 
     let res = res
-        .json::<ProofResponse>()
+        .json::<MultiProofResponse<H>>()
         .or(Err("Error while parsing response"))?;
     match res.proof {
         Some(proof) => Ok(proof),
@@ -116,6 +336,86 @@ pub fn fetch_proof_for_file(
     }
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(bound = "")]
+#[allow(dead_code)]
+struct DirectoryProofResponse<H: MerkleHasher> {
+    success: bool,
+    message: Option<String>,
+    proof: Option<DirectoryProof<H>>,
+}
+
+/// Fetches the proof of a file or a folder of a vault, chaining the proof of
+/// its entry in its folder with the proofs of the folders above it up to the
+/// root of the directory tree of the vault.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault where the file or the folder is stored.
+/// * `path` - The `/` separated path of the file or the folder in the vault.
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the directory proof if successful, or an error if there's an
+/// issue fetching or parsing the proof.
+pub fn fetch_directory_proof<H: MerkleHasher>(
+    vault_id: &String,
+    path: &String,
+    conf: &CliArgs,
+) -> Result<DirectoryProof<H>, Box<dyn Error>> {
+    let res = conf
+        .http
+        .get(format!("{}/{vault_id}/directory-proof", conf.api_endpoint))
+        .query(&[("path", path)])
+        .send()?;
+
+    let res = res
+        .json::<DirectoryProofResponse<H>>()
+        .or(Err("Error while parsing response"))?;
+    match res.proof {
+        Some(proof) => Ok(proof),
+        None => {
+            if let Some(message) = res.message {
+                return Err(message.into());
+            }
+            Err("Unable to parse return value".into())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    message: Option<String>,
+    public_key: Option<String>,
+}
+
+/// Fetches the Ed25519 public key the server signs tree heads with.
+///
+/// # Arguments
+///
+/// * `conf` - The CLI configuration containing the HTTP client and API endpoint.
+///
+/// # Returns
+///
+/// A `Result` containing the hexadecimal public key, or an error if there's an
+/// issue fetching or parsing it.
+pub fn fetch_server_key(conf: &CliArgs) -> Result<String, Box<dyn Error>> {
+    let res = conf
+        .http
+        .get(format!("{}/public-key", conf.api_endpoint))
+        .send()?
+        .json::<PublicKeyResponse>()
+        .or(Err("Error while parsing response"))?;
+    match res.public_key {
+        Some(key) => Ok(key),
+        None => Err(res
+            .message
+            .unwrap_or("Unable to parse return value".to_string())
+            .into()),
+    }
+}
+
 pub fn delete_vault(vault_id: &String, conf: &CliArgs) {
     let _ = conf
         .http
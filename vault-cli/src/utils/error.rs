@@ -0,0 +1,51 @@
+use rs_merkle_tree::MerkleError;
+use std::error::Error;
+use std::process::exit;
+
+/// Returns the exit code matching an error of the Merkle library, so scripts
+/// can tell integrity failures apart from other errors.
+///
+/// # Arguments
+///
+/// * `err` - The error returned by the Merkle library.
+///
+/// # Returns
+///
+/// The exit code, from 10 to 27.
+pub fn merkle_exit_code(err: &MerkleError) -> i32 {
+    match err {
+        MerkleError::EmptyTree => 10,
+        MerkleError::LeafNotFound => 11,
+        MerkleError::IndexOutOfRange { .. } => 12,
+        MerkleError::MalformedProof(_) => 13,
+        MerkleError::HashLengthMismatch { .. } => 14,
+        MerkleError::AlgorithmMismatch { .. } => 15,
+        MerkleError::ModeMismatch { .. } => 16,
+        MerkleError::UnknownAlgorithm(_) => 17,
+        MerkleError::UnknownMode(_) => 18,
+        MerkleError::InvalidHex(_) => 19,
+        MerkleError::RootMismatch => 20,
+        MerkleError::InvalidDump(_) => 21,
+        MerkleError::ChunkMismatch { .. } => 22,
+        MerkleError::UnknownOddNodeStrategy(_) => 23,
+        MerkleError::ParamsMismatch { .. } => 24,
+        MerkleError::LeafCountMismatch { .. } => 25,
+        MerkleError::InvalidPath(_) => 26,
+        MerkleError::InvalidSignature => 27,
+    }
+}
+
+/// Prints an error and exits with the code matching it: the one given by
+/// `merkle_exit_code` for errors of the Merkle library, -1 otherwise.
+///
+/// # Arguments
+///
+/// * `context` - What was being done when the error occurred.
+/// * `err` - The error to report.
+pub fn exit_with_error(context: &str, err: &(dyn Error + 'static)) -> ! {
+    eprintln!("{context}: {err}");
+    match err.downcast_ref::<MerkleError>() {
+        Some(err) => exit(merkle_exit_code(err)),
+        None => exit(-1),
+    }
+}
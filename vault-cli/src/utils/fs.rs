@@ -1,7 +1,7 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader},
-    path::Path,
+    path::{Component, Path, PathBuf},
 };
 
 /// Reads lines from a file and returns them as a vector of strings.
@@ -18,3 +18,48 @@ use std::{
 pub fn lines_from_file(filename: impl AsRef<Path>) -> io::Result<Vec<String>> {
     BufReader::new(File::open(filename)?).lines().collect()
 }
+
+/// Returns the paths of the files in a directory and in its subdirectories.
+///
+/// # Arguments
+///
+/// * `dir` - The path of the directory to walk.
+///
+/// # Returns
+///
+/// An `Result<Vec<PathBuf>>` containing the path of each file, sorted by
+/// their components, or the `io::Error` met while reading a directory.
+pub fn list_files_recursively(dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+    let mut files = Vec::new();
+    for path in entries {
+        if path.is_dir() {
+            files.extend(list_files_recursively(&path)?);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Returns whether a file name sent by the server can be written to: a
+/// relative path which does not leave the current folder.
+///
+/// # Arguments
+///
+/// * `name` - The `/` separated path of a file in a vault.
+///
+/// # Returns
+///
+/// `false` if the name has no normal component, or has a `..`, a root or a
+/// prefix one, which the server never lists.
+pub fn is_safe_file_name(name: &str) -> bool {
+    let components: Vec<Component> = Path::new(name).components().collect();
+    components
+        .iter()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && components.iter().any(|c| matches!(c, Component::Normal(_)))
+}
@@ -1,9 +1,11 @@
 use crate::config::Config;
 use crate::utils::fs::lines_from_file;
+use rs_merkle_tree::{SignedTreeHead, TreeParams};
 use std::error::Error;
 use std::fs::OpenOptions;
 use std::fs::{self, File};
 use std::io::{self, Write};
+use std::path::Path;
 
 /// Returns a list of all vault names read from the vaults configuration file.
 ///
@@ -32,31 +34,222 @@ pub fn get_root_hash_for_vault(vault_id: &String) -> Result<String, Box<dyn Erro
         .to_string())
 }
 
+/// Retrieves the parameters of the tree the root hash of a specific vault was
+/// computed with.
+///
+/// The hashing mode is stored on the second line of the hash file, the odd
+/// node strategy and the leaf count commitment on the fourth and fifth ones.
+/// Vaults committed before they were recorded use the default parameters, and
+/// the vaults committed before the mode was recorded `HashMode::Legacy`.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault for which to retrieve the parameters.
+///
+/// # Returns
+///
+/// A `Result<TreeParams, Box<dyn Error>>` containing the tree parameters, or
+/// an error if the hash file cannot be read or holds unknown parameters.
+pub fn get_tree_params_for_vault(vault_id: &String) -> Result<TreeParams, Box<dyn Error>> {
+    let file = Config::config_dir().join(format!("{vault_id}.hash"));
+    let lines = lines_from_file(file)?;
+    let mut params = TreeParams::default();
+    if let Some(mode) = lines.get(1) {
+        params.mode = mode.parse()?;
+    }
+    if let Some(odd_node) = lines.get(3) {
+        params.odd_node = odd_node.parse()?;
+    }
+    if let Some(commit_leaf_count) = lines.get(4) {
+        params.commit_leaf_count = commit_leaf_count.parse()?;
+    }
+    Ok(params)
+}
+
+/// Retrieves the size of the chunks the files of a specific vault are split into.
+///
+/// The chunk size is stored on the third line of the hash file. Vaults
+/// committed before files were split into chunks only have the root hash and
+/// the hashing mode: the leaf of each file is its hash.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault for which to retrieve the chunk size.
+///
+/// # Returns
+///
+/// A `Result<Option<usize>, Box<dyn Error>>` containing the chunk size if any,
+/// or an error if the hash file cannot be read or holds an invalid size.
+pub fn get_chunk_size_for_vault(vault_id: &String) -> Result<Option<usize>, Box<dyn Error>> {
+    let file = Config::config_dir().join(format!("{vault_id}.hash"));
+    match lines_from_file(file)?.get(2) {
+        Some(chunk_size) => Ok(Some(chunk_size.parse()?)),
+        None => Ok(None),
+    }
+}
+
+/// Retrieves the root hash of the directory tree of a specific vault, which
+/// mirrors its folders.
+///
+/// The directory root is stored on the sixth line of the hash file. Vaults
+/// committed before folders were mirrored only have the flat root hash.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault for which to retrieve the directory root.
+///
+/// # Returns
+///
+/// A `Result<Option<String>, Box<dyn Error>>` containing the directory root if
+/// any, or an error if the hash file cannot be read.
+pub fn get_directory_root_for_vault(vault_id: &String) -> Result<Option<String>, Box<dyn Error>> {
+    let file = Config::config_dir().join(format!("{vault_id}.hash"));
+    Ok(lines_from_file(file)?.get(5).cloned())
+}
+
 /// Saves the root hash associated with a specific vault to a file.
 ///
 /// # Arguments
 ///
 /// * `vault_id` - The ID of the vault for which to save the root hash.
-/// * `hash` - The root hash to be saved.
+/// * `hash` - The root hash to be saved, tagged with its algorithm (see `MerkleRoot`).
+/// * `params` - The parameters of the tree the root hash was computed with.
+/// * `chunk_size` - The size of the chunks the files are split into.
+/// * `directory_root` - The root hash of the directory tree of the vault.
+/// * `sparse_root` - The root hash of the sparse Merkle tree of the vault,
+///   against which the absence proofs of the server are checked.
 ///
 /// # Returns
 ///
 /// An `Result<()>` indicating success or an error if there's an issue
 /// creating or writing to the file.
-pub fn save_vault_root_hash(vault_id: &String, hash: &String) -> io::Result<()> {
+pub fn save_vault_root_hash(
+    vault_id: &String,
+    hash: &String,
+    params: TreeParams,
+    chunk_size: usize,
+    directory_root: &String,
+    sparse_root: &String,
+) -> io::Result<()> {
     let file = Config::config_dir().join(format!("{vault_id}.hash"));
     let mut file = File::create(file)?;
-    file.write_all(hash.as_bytes())?;
+    writeln!(file, "{hash}")?;
+    writeln!(file, "{}", params.mode)?;
+    writeln!(file, "{chunk_size}")?;
+    writeln!(file, "{}", params.odd_node)?;
+    writeln!(file, "{}", params.commit_leaf_count)?;
+    writeln!(file, "{directory_root}")?;
+    writeln!(file, "{sparse_root}")?;
     Ok(())
 }
 
+/// Saves the tree head signed by the server when a specific vault was
+/// finalized, next to its root hash: the receipt of what the server agreed to
+/// store, which can be shown to a third party.
+///
+/// # Arguments
+///
+/// * `vault_id` - The ID of the vault for which to save the signed tree head.
+/// * `tree_head` - The signed tree head, saved as JSON.
+///
+/// # Returns
+///
+/// An `Result<()>` indicating success or an error if there's an issue
+/// creating or writing to the file.
+pub fn save_vault_tree_head(vault_id: &String, tree_head: &SignedTreeHead) -> io::Result<()> {
+    let file = Config::config_dir().join(format!("{vault_id}.sth"));
+    fs::write(file, serde_json::to_string_pretty(tree_head)?)
+}
+
+/// Returns the public key trusted for a server, pinned the first time a vault
+/// was committed to it.
+///
+/// # Arguments
+///
+/// * `server` - The API endpoint of the server.
+///
+/// # Returns
+///
+/// A `Result<Option<String>, Box<dyn Error>>` containing the hexadecimal key
+/// if one is pinned, or an error if the known servers file cannot be read.
+pub fn get_trusted_server_key(server: &str) -> Result<Option<String>, Box<dyn Error>> {
+    Ok(lines_from_file(Config::known_servers_file())?
+        .iter()
+        .find_map(|line| match line.split_once(' ') {
+            Some((endpoint, key)) if endpoint == server => Some(key.to_string()),
+            _ => None,
+        }))
+}
+
+/// Pins the public key of a server, whose tree heads are then only trusted if
+/// signed with it.
+///
+/// # Arguments
+///
+/// * `server` - The API endpoint of the server.
+/// * `key` - The hexadecimal public key of the server.
+///
+/// # Returns
+///
+/// An `Result<()>` indicating success or an error if there's an issue
+/// writing to the file.
+pub fn save_trusted_server_key(server: &str, key: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(Config::known_servers_file())?;
+    writeln!(file, "{server} {key}")
+}
+
 /// Returns a list of the staged files.
 ///
 /// # Returns
 ///
-/// A `Vec<String>` containing the names of staged files.
+/// A `Vec<String>` containing the local paths of staged files.
 pub fn get_staged_files() -> Vec<String> {
-    lines_from_file(Config::staging_file()).unwrap()
+    get_staged_entries()
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Returns a list of the staged files along with their name in the vault.
+///
+/// Each line of the staging file holds the local path of a file and, after a
+/// tab, its `/` separated path in the vault, e.g. `photos/cat.png` for a file
+/// staged from a folder. Files staged before folders were mirrored are named
+/// after their file name.
+///
+/// # Returns
+///
+/// A `Vec<(String, String)>` containing the local path and the vault name of
+/// each staged file, sorted by vault name folder by folder: the order of the
+/// leaves of the vault Merkle tree, in which the server lists the files.
+pub fn get_staged_entries() -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = lines_from_file(Config::staging_file())
+        .unwrap()
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once('\t') {
+            Some((path, name)) => (path.to_string(), name.to_string()),
+            None => {
+                let name = Path::new(&line).file_name().unwrap().to_str().unwrap();
+                (line.clone(), name.to_string())
+            }
+        })
+        .collect();
+    // names are ordered by their components: `a/b.txt` comes before `a.txt`
+    entries.sort_by(|(_, a), (_, b)| a.split('/').cmp(b.split('/')));
+    entries
+}
+
+/// Returns the line of the staging file for a file staged as `name`.
+///
+/// # Arguments
+///
+/// * `path` - The local path of the file.
+/// * `name` - The `/` separated path of the file in the vault.
+pub fn staged_entry_line(path: &str, name: &str) -> String {
+    format!("{path}\t{name}")
 }
 
 pub fn clear_staging() {
@@ -70,6 +263,8 @@ pub fn delete_vault_local(vault_id: &String) {
     if let Err(e) = fs::remove_file(vault_root_hash) {
         eprintln!("Couldn't delete vault root hash: {}", e);
     }
+    // vaults committed before tree heads were signed have none
+    let _ = fs::remove_file(Config::config_dir().join(format!("{vault_id}.sth")));
 
     // update vaults list
     let vaults = lines_from_file(Config::vaults_file()).unwrap();
@@ -10,32 +10,45 @@ for f in {a..z} {A..Z} {0..99} # creates 152 files
 do
     echo "this is file $f" > "$f.txt"
 done
+# a file of 3 chunks, kept aside to check its download
+head -c 3000000 /dev/urandom > big.bin
+cp big.bin /tmp/vault-end-to-end-big.orig
+# nested folders, mirrored in the vault
+mkdir -p docs/2023
+echo "this is the readme" > docs/readme.md
+echo "these are notes" > docs/2023/notes.txt
 
 
 echo "[*] Testing 'add' command"
 
 vault-cli -s "$VAULT_ENDPOINT" add /tmp/vault-end-to-end-tests
 # `status` cmd adds 2 lines of display
-[ "$(vault-cli -s "$VAULT_ENDPOINT" status | wc -l)" = "156" ] \
-    && echo "  [+] Staged 152 files" \
+[ "$(vault-cli -s "$VAULT_ENDPOINT" status | wc -l)" = "159" ] \
+    && echo "  [+] Staged 155 files" \
     || exit 1;
 
 
 echo "[*] Testing 'commit' command"
 
-[ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "0" ] && echo "[+] Uploaded 152 files" || exit 1;
+[ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "0" ] && echo "[+] Uploaded 153 files" || exit 1;
 
 vault-cli -s "$VAULT_ENDPOINT" --no-interaction commit
 
-[ "$(ls | wc -l)" = "0" ] \
+[ "$(find . -type f | wc -l)" = "0" ] \
   && echo "[+] Committed files removed successfully from local fs" \
   || exit 1
+grep -q "^$VAULT_ENDPOINT [0-9a-f]\{64\}$" $HOME/.config/vault/known_servers \
+  && echo "[+] Server key pinned" \
+  || exit 1
+[ "$(sed -n 7p $HOME/.config/vault/$(cat $HOME/.config/vault/vaults).hash | cut -d: -f1)" = "sha3-256" ] \
+  && echo "[+] Sparse root saved" \
+  || exit 1
 
 
 echo "[*] Testing 'list' command"
 # `list` cmd adds 2 lines of display
-[ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "154" ] \
-  && echo "[+] Uploaded 152 files" \
+[ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "157" ] \
+  && echo "[+] Uploaded 155 files" \
   || exit 1
 
 
@@ -46,7 +59,81 @@ vault-cli -s "$VAULT_ENDPOINT" download 42.txt
   && echo "[+] File download successful" \
   || exit 1
 
+vault-cli -s "$VAULT_ENDPOINT" download a.txt Z.txt 7.txt 99.txt
+[ "$(cat a.txt Z.txt 7.txt 99.txt)" = "$(printf 'this is file %s\n' a Z 7 99)" ] \
+  && echo "[+] Multiple files download successful" \
+  || exit 1
+
+vault-cli -s "$VAULT_ENDPOINT" download docs/readme.md notes.txt
+[ "$(cat docs/readme.md docs/2023/notes.txt)" = "$(printf 'this is the readme\nthese are notes')" ] \
+  && echo "[+] Nested files download successful" \
+  || exit 1
+
+vault-cli -s "$VAULT_ENDPOINT" download big.bin
+cmp -s big.bin /tmp/vault-end-to-end-big.orig \
+  && echo "[+] Chunked file download successful" \
+  || exit 1
+
+# resume from an interrupted download, cut in the middle of the second chunk
+rm big.bin
+head -c 1500000 /tmp/vault-end-to-end-big.orig > big.bin.part
+vault-cli -s "$VAULT_ENDPOINT" download big.bin
+cmp -s big.bin /tmp/vault-end-to-end-big.orig && [ ! -e big.bin.part ] \
+  && echo "[+] Chunked file download resumed successfully" \
+  || exit 1
+rm /tmp/vault-end-to-end-big.orig
+
+echo "[*] Testing 'debug tree' command"
+vault_id=$(cat $HOME/.config/vault/vaults)
+vault-cli -s "$VAULT_ENDPOINT" debug tree --file docs/readme.md "$vault_id" > tree.txt \
+  && grep -q '\[path\]  docs/readme.md$' tree.txt \
+  && [ "$(grep -c -- '-- ' tree.txt)" -gt "155" ] \
+  && vault-cli -s "$VAULT_ENDPOINT" debug tree --dot "$vault_id" | grep -q '^digraph merkle_tree {$' \
+  && echo "[+] Vault tree rendered" \
+  || exit 1
+rm tree.txt
+
+echo "[*] Testing commit with a changed server key"
+known_servers=$HOME/.config/vault/known_servers
+cp "$known_servers" known_servers.bak
+sed -i "s|^$VAULT_ENDPOINT .*|$VAULT_ENDPOINT $(printf '11%.0s' {1..32})|" "$known_servers"
+echo "this is another file" > other.txt
+vault-cli -s "$VAULT_ENDPOINT" add other.txt
+set +e
+vault-cli -s "$VAULT_ENDPOINT" --no-interaction commit
+code=$?
+set -e
+[ "$code" = "27" ] \
+  && [ -f other.txt ] \
+  && [ "$(wc -l < $HOME/.config/vault/vaults)" = "1" ] \
+  && echo "[+] Commit refused" \
+  || exit 1
+mv known_servers.bak "$known_servers"
+vault-cli -s "$VAULT_ENDPOINT" clear
+rm other.txt
+
 vault-cli -s "$VAULT_ENDPOINT" --no-interaction delete $(cat $HOME/.config/vault/vaults)
 [ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "0" ] \
   && echo "[+] Vault deletion successful" \
   || exit 1
+
+echo "[*] Testing commit of nested folders staged out of order"
+mkdir -p second/y first/a
+echo "x" > second/x.txt
+echo "z" > second/y/z.txt
+echo "b" > first/a/b.txt
+echo "a" > first/a.txt
+# staged as x.txt, y/z.txt then a.txt and a/b.txt, leaves are sorted by path
+vault-cli -s "$VAULT_ENDPOINT" add second
+vault-cli -s "$VAULT_ENDPOINT" add first
+vault-cli -s "$VAULT_ENDPOINT" --no-interaction commit
+[ "$(find first second -type f | wc -l)" = "0" ] \
+  && [ "$(vault-cli -s "$VAULT_ENDPOINT" list | wc -l)" = "6" ] \
+  && echo "[+] Nested folders committed" \
+  || exit 1
+vault-cli -s "$VAULT_ENDPOINT" download a/b.txt a.txt y/z.txt
+[ "$(cat a/b.txt a.txt y/z.txt)" = "$(printf 'b\na\nz')" ] \
+  && [ -z "$(find . -name '*.part')" ] \
+  && echo "[+] Nested folder files download successful" \
+  || exit 1
+vault-cli -s "$VAULT_ENDPOINT" --no-interaction delete $(cat $HOME/.config/vault/vaults)